@@ -139,11 +139,13 @@ pub async fn run(config_path: &Path, interaction: &(impl UserInteraction + Sync)
     if interaction.confirm("Add to config?", true)? {
         let chart_config = ChartConfig {
             name: chart_name.clone(),
+            release: None,
             repo_name: repo_name_to_use,
             namespace,
             version: details.version,
             chart_path: details.chart_path,
             comment: details.comment,
+            values_files: None,
         };
 
         ConfigUpdater::update(config_path, new_repo, chart_config)?;
@@ -153,6 +155,9 @@ pub async fn run(config_path: &Path, interaction: &(impl UserInteraction + Sync)
             use crate::cli::commands::SyncArgs;
             let args = SyncArgs {
                 charts: Some(vec![chart_name]),
+                selector: None,
+                with_dependencies: false,
+                with_dependents: false,
                 ignore_skip: false,
             };
             crate::cli::commands::sync::run(args, false, config_path).await?;
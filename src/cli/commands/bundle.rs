@@ -0,0 +1,54 @@
+use super::{BundleArgs, BundleCommands};
+use crate::config::Config;
+use crate::engine::bundle;
+use anyhow::Result;
+use console::style;
+use std::path::Path;
+
+pub fn run(args: BundleArgs, config_path: &Path) -> Result<()> {
+    // Paths of the configuration are relative to the working directory
+    let base = Path::new(".");
+    match args.command {
+        BundleCommands::Create(args) => {
+            let config = Config::load_from_path(config_path)?;
+            println!(
+                "{} Creating bundle {}...",
+                style("==>").bold().green(),
+                args.output.display()
+            );
+            let manifest = bundle::create(&config, config_path, base, &args.output)?;
+            println!(
+                " {} {} charts and {} files bundled",
+                style("[OK]").green(),
+                manifest.charts.len(),
+                manifest.files.len()
+            );
+        }
+        BundleCommands::Import(args) => {
+            println!(
+                "{} Importing bundle {}...",
+                style("==>").bold().green(),
+                args.path.display()
+            );
+            let manifest = bundle::import(&args.path, config_path, base, args.force)?;
+            println!(
+                " {} {} files verified and unpacked (bundle created {})",
+                style("[OK]").green(),
+                manifest.files.len(),
+                manifest.created
+            );
+            let offline = manifest
+                .charts
+                .iter()
+                .filter(|c| c.repo_name.is_some())
+                .count();
+            println!(
+                " {} {} remote charts marked no_sync in {}",
+                style("[OK]").green(),
+                offline,
+                config_path.display()
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,13 @@
+use crate::clients::HelmClient;
+use crate::clients::helm::RealHelmClient;
 use crate::config::{Config, RepoType};
+use crate::util::filter;
+use crate::util::state::state_dir;
 use anyhow::{Context, Result, anyhow};
 use colored::*;
 use console::style;
 use semver::Version;
 
-use std::process::Command;
-
 use super::sync;
 use super::{CheckUpdatesArgs, SyncArgs};
 
@@ -15,10 +17,16 @@ pub async fn run(
     config_path: &std::path::Path,
 ) -> Result<()> {
     // Load configuration
-    let mut config = Config::load_from_path(config_path)?;
+    let config = Config::load_from_path(config_path)?;
+    let selected = filter::select_charts(
+        &config.charts,
+        args.charts.as_deref(),
+        args.selector.as_ref(),
+    )?;
 
     // Update Helm repositories
-    update_helm_repos()?;
+    let helm_client = RealHelmClient::with_state_dir(&state_dir(config_path));
+    update_helm_repos(&helm_client, &config, selected.as_deref())?;
 
     let mut updates_found = false;
     let mut charts_to_update = Vec::new();
@@ -29,10 +37,10 @@ pub async fn run(
     );
 
     // Iterate over charts
-    for chart in &mut config.charts {
+    for chart in &config.charts {
         // Filter by positional charts arg
-        if let Some(charts) = &args.charts
-            && !charts.contains(&chart.name)
+        if let Some(charts) = &selected
+            && !charts.iter().any(|n| n == chart.release_name())
         {
             continue;
         }
@@ -59,7 +67,7 @@ pub async fn run(
                     chart.name.clone()
                 };
 
-                match get_latest_version(&chart_ref) {
+                match get_latest_version(&helm_client, &chart_ref) {
                     Ok(latest_version_str) => {
                         let current_version_str = chart.version.as_deref().unwrap_or("unknown");
 
@@ -77,7 +85,7 @@ pub async fn run(
                                         latest_version_str.green()
                                     );
                                     updates_found = true;
-                                    charts_to_update.push((chart.name.clone(), latest_version_str));
+                                    charts_to_update.push((chart, latest_version_str));
                                 }
                                 _ => println!("{}", "Up to date".green()),
                             },
@@ -91,7 +99,7 @@ pub async fn run(
                                         latest_version_str.green()
                                     );
                                     updates_found = true;
-                                    charts_to_update.push((chart.name.clone(), latest_version_str));
+                                    charts_to_update.push((chart, latest_version_str));
                                 } else {
                                     println!("{}", "Up to date".green());
                                 }
@@ -120,18 +128,21 @@ pub async fn run(
             println!("\n{} Applying updates...", "📝".bold());
 
             // Apply updates
-            for (chart_name, new_version) in &charts_to_update {
-                if let Err(e) =
-                    ConfigUpdater::update_chart_version(config_path, chart_name, new_version)
+            for (chart, new_version) in &charts_to_update {
+                if let Err(e) = ConfigUpdater::update_chart_version(config_path, chart, new_version)
                 {
                     println!(
                         "{} Failed to update {}: {}",
                         "Warning".yellow(),
-                        chart_name,
+                        chart.release_name(),
                         e
                     );
                 } else {
-                    println!("Updated {} to {}", chart_name.bold(), new_version.green());
+                    println!(
+                        "Updated {} to {}",
+                        chart.release_name().bold(),
+                        new_version.green()
+                    );
                 }
             }
 
@@ -140,7 +151,10 @@ pub async fn run(
             if args.apply_sync {
                 println!();
                 let sync_args = SyncArgs {
-                    charts: args.charts,
+                    charts: selected,
+                    selector: None,
+                    with_dependencies: false,
+                    with_dependents: false,
                     ignore_skip: false,
                 };
                 sync::run(sync_args, no_progress, config_path).await?;
@@ -157,22 +171,46 @@ pub async fn run(
     Ok(())
 }
 
-fn update_helm_repos() -> Result<()> {
+/// Registers and updates the Helm repositories used by the checked charts, in the
+/// project-local repository configuration.
+fn update_helm_repos(
+    helm_client: &impl HelmClient,
+    config: &Config,
+    only: Option<&[String]>,
+) -> Result<()> {
     println!("{} Updating Helm repositories...", "🔄".dimmed());
-    let status = Command::new("helm")
-        .arg("repo")
-        .arg("update")
-        .status()
-        .context("Failed to execute helm repo update")?;
 
-    if !status.success() {
-        return Err(anyhow!("Failed to update helm repositories"));
+    let mut names: Vec<String> = Vec::new();
+    for chart in &config.charts {
+        if let Some(only) = only
+            && !only.iter().any(|n| n == chart.release_name())
+        {
+            continue;
+        }
+        let Some(repo) = chart.repo_name.as_ref().and_then(|repo_name| {
+            config
+                .repositories
+                .iter()
+                .find(|r| r.name == *repo_name && r.r#type == RepoType::Helm)
+        }) else {
+            continue;
+        };
+        if !names.contains(&repo.name) {
+            helm_client
+                .repo_add(&repo.name, &repo.url)
+                .map_err(|e| anyhow!("Failed to update helm repositories: {}", e))?;
+            names.push(repo.name.clone());
+        }
     }
-    Ok(())
+
+    helm_client
+        .repo_update(&names)
+        .map_err(|e| anyhow!("Failed to update helm repositories: {}", e))
 }
 
-fn get_latest_version(chart_ref: &str) -> Result<String> {
-    let output = Command::new("helm")
+fn get_latest_version(helm_client: &RealHelmClient, chart_ref: &str) -> Result<String> {
+    let output = helm_client
+        .command()
         .arg("search")
         .arg("repo")
         .arg(chart_ref)
@@ -24,7 +24,11 @@ pub async fn run(
     // 2. Select Chart
     let chart = if let Some(name) = args.name {
         // Check for matches
-        let matches: Vec<&Chart> = config.charts.iter().filter(|c| c.name == name).collect();
+        let matches: Vec<&Chart> = config
+            .charts
+            .iter()
+            .filter(|c| c.release_name() == name)
+            .collect();
 
         if matches.is_empty() {
             return Err(anyhow!("Chart '{}' not found in configuration", name));
@@ -38,7 +42,7 @@ pub async fn run(
             );
             let items: Vec<String> = matches
                 .iter()
-                .map(|c| format!("{} (ns: {})", c.name, c.namespace))
+                .map(|c| format!("{} (ns: {})", c.release_name(), c.namespace))
                 .collect();
 
             let selection = interaction
@@ -56,11 +60,11 @@ pub async fn run(
 
         // Create a sorted list of references for selection index mapping
         let mut sorted_charts: Vec<_> = config.charts.iter().collect();
-        sorted_charts.sort_by(|a, b| a.name.cmp(&b.name));
+        sorted_charts.sort_by(|a, b| a.release_name().cmp(b.release_name()));
 
         let items: Vec<String> = sorted_charts
             .iter()
-            .map(|c| format!("{} ({})", c.name, style(&c.namespace).dim()))
+            .map(|c| format!("{} ({})", c.release_name(), style(&c.namespace).dim()))
             .collect();
 
         let selection = interaction
@@ -70,8 +74,16 @@ pub async fn run(
         sorted_charts[selection]
     };
 
-    let chart_name = &chart.name;
+    let chart_name = chart.release_name();
     let chart_ns = &chart.namespace;
+    // Other instances of the same chart version share its directory
+    let shares_chart = config.charts.iter().any(|c| {
+        !std::ptr::eq(c, chart)
+            && c.name == chart.name
+            && c.repo_name == chart.repo_name
+            && c.dest == chart.dest
+            && c.dir_name() == chart.dir_name()
+    });
 
     // 3. Check Dependencies
     let dependents = dag::get_dependents(&config.charts, chart_name)?;
@@ -82,7 +94,7 @@ pub async fn run(
             style(chart_name).bold()
         );
         for dep in dependents {
-            println!("  - {}", style(&dep.name).bold());
+            println!("  - {}", style(dep.release_name()).bold());
         }
         println!("Cannot delete chart while it has dependents.");
         return Ok(());
@@ -90,16 +102,14 @@ pub async fn run(
 
     // 4. Calculate Impact / Summary
     let chart_path = resolve_delete_path(&config, chart)?;
-    let path_exists = chart_path.exists();
+    let path_exists = chart_path.exists() && !shares_chart;
 
     let repo_to_remove = if let Some(repo_name) = &chart.repo_name {
         // Check if this repo is used by any OTHER chart
         // We must exclude the chart we are deleting from the check
         let is_used_by_others = config.charts.iter().any(|c| {
             // Check if it's a DIFFERENT chart instance
-            // (Same name AND same namespace = SAME chart)
-            let is_same_chart = &c.name == chart_name && &c.namespace == chart_ns;
-            !is_same_chart && c.repo_name.as_deref() == Some(repo_name)
+            !std::ptr::eq(c, chart) && c.repo_name.as_deref() == Some(repo_name)
         });
 
         if !is_used_by_others {
@@ -129,7 +139,9 @@ pub async fn run(
         style(chart_path.display()).bold()
     );
 
-    let status_str = if path_exists {
+    let status_str = if shares_chart {
+        style("Shared with another release (kept)").yellow()
+    } else if path_exists {
         style("Present (will be deleted)").green()
     } else {
         style("Missing (already deleted)").yellow()
@@ -204,6 +216,11 @@ pub async fn run(
         fs::remove_dir_all(&chart_path)
             .with_context(|| format!("Failed to delete directory {}", chart_path.display()))?;
         println!(" {} Directory removed.", style("[OK]").green());
+    } else if shares_chart {
+        println!(
+            "Directory {} is used by another release, skipping.",
+            chart_path.display()
+        );
     } else {
         println!(
             "Directory {} does not exist, skipping.",
@@ -212,14 +229,15 @@ pub async fn run(
     }
 
     // 6.2 Update Lockfile
-    if let Some(repo_name) = &chart.repo_name {
-        lockfile.remove(chart_name, repo_name);
+    if chart.repo_name.is_some() {
+        lockfile.migrate(&config.charts);
+        lockfile.remove(chart);
         lockfile.save().context("Failed to save lockfile")?;
         println!(" {} Removed from lockfile.", style("[OK]").green());
     }
 
     // 6.3 Update Config (Remove Chart non-destructively)
-    ConfigUpdater::remove_chart(config_path, chart_name, chart_ns)?;
+    ConfigUpdater::remove_chart(config_path, &chart.name, chart.release.as_deref(), chart_ns)?;
     println!(" {} Removed chart from config.", style("[OK]").green());
 
     // 6.4 Update Config (Remove Repo if needed non-destructively)
@@ -248,7 +266,7 @@ fn resolve_delete_path(config: &Config, chart: &Chart) -> Result<std::path::Path
     }
 
     let resolved_base = config.resolve_chart_destination(chart)?;
-    let standard_path = resolved_base.join(&chart.name);
+    let standard_path = resolved_base.join(chart.dir_name());
 
     // 2. Explicit Destination Adaptation
     if let Some(dest_str) = &chart.dest {
@@ -285,6 +303,7 @@ mod tests {
             vesshelm: None,
             variables_files: None,
             secrets_files: None,
+            policies: None,
         };
         let chart = Chart {
             name: "nginx".to_string(),
@@ -292,20 +311,19 @@ mod tests {
             version: Some("1.0".to_string()),
             namespace: "default".to_string(),
             dest: None, // Uses default
-            chart_path: None,
-            no_sync: false,
-            no_deploy: false,
-            comment: None,
-            values_files: None,
-            helm_args_append: None,
-            helm_args_override: None,
-            values: None,
-            depends: None,
-            no_interpolation: false,
+            ..Default::default()
         };
 
         let path = resolve_delete_path(&config, &chart).unwrap();
         assert_eq!(path.to_string_lossy(), "charts/nginx");
+
+        // Instances at several versions each have their own directory
+        let chart = Chart {
+            versioned_dir: true,
+            ..chart
+        };
+        let path = resolve_delete_path(&config, &chart).unwrap();
+        assert_eq!(path.to_string_lossy(), "charts/nginx-1.0");
     }
 
     #[test]
@@ -320,23 +338,13 @@ mod tests {
             vesshelm: None,
             variables_files: None,
             secrets_files: None,
+            policies: None,
         };
         let chart = Chart {
             name: "my-local".to_string(),
-            repo_name: None,
-            version: None,
             namespace: "default".to_string(),
-            dest: None,
             chart_path: Some("custom/path/my-local".to_string()),
-            no_sync: false,
-            no_deploy: false,
-            comment: None,
-            values_files: None,
-            helm_args_append: None,
-            helm_args_override: None,
-            values: None,
-            depends: None,
-            no_interpolation: false,
+            ..Default::default()
         };
 
         let path = resolve_delete_path(&config, &chart).unwrap();
@@ -359,6 +367,7 @@ mod tests {
             vesshelm: None,
             variables_files: None,
             secrets_files: None,
+            policies: None,
         };
         let chart = Chart {
             name: "nginx".to_string(),
@@ -366,16 +375,7 @@ mod tests {
             version: Some("1.0".to_string()),
             namespace: "default".to_string(),
             dest: Some("custom/folder".to_string()),
-            chart_path: None,
-            no_sync: false,
-            no_deploy: false,
-            comment: None,
-            values_files: None,
-            helm_args_append: None,
-            helm_args_override: None,
-            values: None,
-            depends: None,
-            no_interpolation: false,
+            ..Default::default()
         };
 
         // Default behavior (fallback to join)
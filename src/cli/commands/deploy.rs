@@ -1,12 +1,25 @@
+use std::collections::HashMap;
 use std::process::Stdio;
-
-use crate::config::{Chart, Config, Destination, VesshelmConfig};
+use std::time::Instant;
+
+use crate::config::{Chart, Config, Destination, Policies, PolicyLevel, VesshelmConfig};
+use crate::engine::audit::{self, AuditContext, AuditRecord};
+use crate::engine::hooks::{HookPhase, hooks_for, render_hook};
+use crate::engine::policy;
+use crate::engine::release::{
+    compute_diff, load_variable_context, prepare_release, render_manifest,
+};
+use crate::engine::run_state::DeployRunState;
+use crate::engine::values::{SCHEMA_FILE, schema_violations};
+use crate::error::VesshelmError;
+use crate::util::diff::DiffView;
 use crate::util::progress::ProgressTracker;
+use crate::util::state::state_dir;
 use crate::util::{dag, filter};
 use anyhow::{Context, Result, anyhow};
 use colored::*;
 use console::style;
-use dialoguer::Confirm;
+use dialoguer::{Confirm, Select};
 
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
@@ -29,23 +42,8 @@ pub async fn run(args: DeployArgs, no_progress: bool, config_path: &std::path::P
         }
     };
 
-    // Load variables
     // Load variables and secrets
-    let mut all_variable_files = Vec::new();
-    if let Some(files) = &config.variables_files {
-        all_variable_files.extend(files.clone());
-    }
-    if let Some(files) = &config.secrets_files {
-        all_variable_files.extend(files.clone());
-    }
-
-    let variable_context = if !all_variable_files.is_empty() {
-        let base_path = config_path.parent().unwrap_or(std::path::Path::new("."));
-        crate::util::variables::load_variables(&all_variable_files, base_path)
-            .context("Failed to load variables/secrets")?
-    } else {
-        serde_yaml_ng::Value::Null
-    };
+    let variable_context = load_variable_context(&config, config_path)?;
 
     println!("{} 🚀 Starting deployment...", style("==>").bold().green());
 
@@ -53,35 +51,137 @@ pub async fn run(args: DeployArgs, no_progress: bool, config_path: &std::path::P
     let mut sorted_charts =
         dag::sort_charts(&config.charts).context("Failed to resolve chart dependencies")?;
 
-    // Filter charts based on args.charts
-    if let Some(charts) = &args.charts {
-        let available_names: Vec<_> = sorted_charts.iter().map(|c| c.name.as_str()).collect();
+    // Resume the previous run with its filter, or start a new one
+    let state_dir = state_dir(config_path);
+    let previous_run = if args.resume {
+        let run = DeployRunState::load(&state_dir)?
+            .ok_or_else(|| anyhow!("No unfinished deploy run to resume"))?;
+        Some(run)
+    } else {
+        None
+    };
+    let chart_filter = match &previous_run {
+        Some(run) => run.charts.clone(),
+        None => filter::select_charts(
+            &config.charts,
+            args.charts.as_deref(),
+            args.selector.as_ref(),
+        )?
+        .map(|names| {
+            dag::expand_selection(
+                &config.charts,
+                &names,
+                args.with_dependencies,
+                args.with_dependents,
+            )
+        })
+        .transpose()?,
+    };
+
+    if previous_run.is_none()
+        && let Some(charts) = &chart_filter
+    {
+        filter::warn_excluded_dependencies(&config.charts, charts);
+    }
+
+    // Filter charts based on the requested charts
+    if let Some(charts) = &chart_filter {
+        let available_names: Vec<_> = sorted_charts.iter().map(|c| c.release_name()).collect();
         filter::validate_chart_args(&available_names, charts)?;
-        sorted_charts.retain(|chart| charts.contains(&chart.name));
+        sorted_charts.retain(|chart| charts.iter().any(|n| n == chart.release_name()));
+    }
+
+    if let Some(run) = &previous_run {
+        let remaining = run.remaining();
+        sorted_charts.retain(|chart| remaining.contains(&chart.release_name()));
+        println!(
+            "{} Resuming from {} ({} chart(s) already completed)",
+            style("==>").bold().green(),
+            style(remaining.first().copied().unwrap_or("-")).bold(),
+            run.completed.len()
+        );
+    } else {
+        // Slice the deployment order
+        let names: Vec<_> = sorted_charts.iter().map(|c| c.release_name()).collect();
+        let range = filter::slice_range(&names, args.from.as_deref(), args.until.as_deref())?;
+        sorted_charts = sorted_charts[range].to_vec();
     }
 
     let total_charts = sorted_charts.len() as u64;
     let tracker = ProgressTracker::new(total_charts, no_progress)
         .context("Failed to initialize progress tracker")?;
 
+    // Compute every diff up front and collect decisions before deploying anything
+    let review = if args.review_first {
+        match review_changes(
+            &sorted_charts,
+            ReviewOptions {
+                destinations: &config.destinations,
+                global_helm_config: helm_config,
+                no_interactive: args.no_interactive,
+                force: args.force,
+                variable_context: &variable_context,
+                diff_view: DiffView::from(&args.diff_view),
+            },
+            &tracker,
+        )
+        .await?
+        {
+            Some(review) => Some(review),
+            None => {
+                tracker.finish_with_message("Deployment aborted");
+                println!("\n {} Deployment aborted.", style("[ABORT]").dim());
+                return Ok(());
+            }
+        }
+    } else {
+        None
+    };
+
+    // Record progress so a failed run can be resumed (dry runs change nothing)
+    let mut run_state = (!args.dry_run).then(|| match previous_run {
+        Some(run) => DeployRunState {
+            failed: None,
+            ..run
+        },
+        None => DeployRunState {
+            charts: chart_filter.clone(),
+            planned: sorted_charts
+                .iter()
+                .map(|c| c.release_name().to_string())
+                .collect(),
+            completed: Vec::new(),
+            failed: None,
+        },
+    });
+    if let Some(run) = &run_state {
+        run.save(&state_dir)?;
+    }
+
+    let audit_context = (!args.dry_run).then(|| AuditContext::collect(config_path));
+
     let mut deployed_count = 0;
     let mut failed_count = 0;
     let mut skipped_count = 0;
     let mut ignored_count = 0;
+    let mut tests_passed = 0;
+    let mut tests_failed = 0;
 
     for chart in sorted_charts {
         if chart.no_deploy {
             tracker.println(&format!(
                 " {} {} (no_deploy=true)",
                 "⏭ ".yellow(),
-                chart.name
+                chart.release_name()
             ));
             skipped_count += 1;
             tracker.inc();
             continue;
         }
 
-        match deploy_chart(
+        let started = Instant::now();
+        let mut values_hash = None;
+        let result = deploy_chart(
             chart,
             DeployOptions {
                 destinations: &config.destinations,
@@ -91,13 +191,54 @@ pub async fn run(args: DeployArgs, no_progress: bool, config_path: &std::path::P
                 force: args.force,
                 take_ownership: args.take_ownership,
                 variable_context: &variable_context,
+                diff_view: DiffView::from(&args.diff_view),
+                review: review
+                    .as_ref()
+                    .and_then(|r| r.get(chart.release_name()).copied()),
+                run_tests: args.test,
+                policies: config.policies.as_ref(),
             },
             &tracker,
+            &mut values_hash,
         )
-        .await
-        {
+        .await;
+
+        // Record the outcome in the audit log (dry runs change nothing)
+        if let Some(context) = &audit_context {
+            let (status, error) = match &result {
+                Ok(status) => (status.as_str(), None),
+                Err(e) => ("failed", Some(format!("{:#}", e))),
+            };
+            let record = AuditRecord {
+                timestamp: audit::now_timestamp(),
+                user: context.user.clone(),
+                hostname: context.hostname.clone(),
+                git_head: context.git_head.clone(),
+                git_dirty: context.git_dirty,
+                chart: chart.release_name().to_string(),
+                version: chart.version.clone(),
+                namespace: chart.namespace.clone(),
+                values_hash,
+                status: status.to_string(),
+                duration_ms: started.elapsed().as_millis(),
+                error,
+            };
+            if let Err(e) = audit::append(&state_dir, &record) {
+                tracker.println(&format!(
+                    " {} Failed to write audit log: {:#}",
+                    style("WARN:").yellow(),
+                    e
+                ));
+            }
+        }
+
+        let completed = matches!(&result, Ok(status) if status.completes_run());
+        match result {
             Ok(DeployStatus::Deployed) => {
                 deployed_count += 1;
+                if args.test || chart.test_after_deploy {
+                    tests_passed += 1;
+                }
             }
             Ok(DeployStatus::Skipped) => {
                 skipped_count += 1;
@@ -107,11 +248,21 @@ pub async fn run(args: DeployArgs, no_progress: bool, config_path: &std::path::P
             }
             Err(e) => {
                 failed_count += 1;
+                if let Some(run) = &mut run_state {
+                    run.failed = Some(chart.release_name().to_string());
+                    run.save(&state_dir)?;
+                }
+                if matches!(
+                    e.downcast_ref::<VesshelmError>(),
+                    Some(VesshelmError::HelmTestFailed(..))
+                ) {
+                    tests_failed += 1;
+                }
                 tracker.println(&format!(
                     " {} {} {}: {:#}",
                     style("[Fail]").red(),
                     "✗", // CROSS emoji not imported here locally properly, using string
-                    chart.name,
+                    chart.release_name(),
                     e
                 ));
 
@@ -138,6 +289,10 @@ pub async fn run(args: DeployArgs, no_progress: bool, config_path: &std::path::P
                 break;
             }
         }
+        if completed && let Some(run) = &mut run_state {
+            run.completed.push(chart.release_name().to_string());
+            run.save(&state_dir)?;
+        }
         tracker.inc();
     }
 
@@ -148,11 +303,29 @@ pub async fn run(args: DeployArgs, no_progress: bool, config_path: &std::path::P
     println!("  Failed:   {}", style(failed_count).red());
     println!("  Skipped:  {}", style(skipped_count).yellow());
     println!("  Ignored:  {}", style(ignored_count).dim());
+    if tests_passed + tests_failed > 0 {
+        println!(
+            "  Tests:    {} passed, {} failed",
+            style(tests_passed).green(),
+            style(tests_failed).red()
+        );
+    }
 
     if failed_count > 0 {
+        if run_state.is_some() {
+            println!(
+                "\n{} Run {} to continue from the failed chart.",
+                "👉".cyan(),
+                "vesshelm deploy --resume".bold()
+            );
+        }
         anyhow::bail!("Deployment failed for some charts");
     }
 
+    if run_state.is_some() {
+        DeployRunState::clear(&state_dir)?;
+    }
+
     Ok(())
 }
 
@@ -162,6 +335,22 @@ enum DeployStatus {
     Ignored,
 }
 
+impl DeployStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeployStatus::Deployed => "deployed",
+            DeployStatus::Skipped => "skipped",
+            DeployStatus::Ignored => "ignored",
+        }
+    }
+
+    /// Whether the chart is recorded as completed for `--resume`: declined charts were never
+    /// deployed, so a resumed run offers them again.
+    fn completes_run(&self) -> bool {
+        matches!(self, DeployStatus::Deployed | DeployStatus::Skipped)
+    }
+}
+
 struct DeployOptions<'a> {
     destinations: &'a [Destination],
     global_helm_config: &'a VesshelmConfig,
@@ -170,6 +359,138 @@ struct DeployOptions<'a> {
     force: bool,
     take_ownership: bool,
     variable_context: &'a serde_yaml_ng::Value,
+    diff_view: DiffView,
+    /// Decision taken during `--review-first`; the diff is not computed again when set.
+    review: Option<ReviewDecision>,
+    /// Run `helm test` after the upgrade, whatever `test_after_deploy` says.
+    run_tests: bool,
+    /// Guardrails checked on the rendered manifests before anything is applied.
+    policies: Option<&'a Policies>,
+}
+
+/// Outcome of the up-front review of a chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReviewDecision {
+    Apply,
+    NoChanges,
+    Declined,
+}
+
+struct ReviewOptions<'a> {
+    destinations: &'a [Destination],
+    global_helm_config: &'a VesshelmConfig,
+    no_interactive: bool,
+    force: bool,
+    variable_context: &'a serde_yaml_ng::Value,
+    diff_view: DiffView,
+}
+
+/// Diffs every chart, prints the combined plan and asks for confirmation once.
+///
+/// Returns `None` when the user aborts the deployment.
+async fn review_changes(
+    charts: &[&Chart],
+    options: ReviewOptions<'_>,
+    tracker: &ProgressTracker,
+) -> Result<Option<HashMap<String, ReviewDecision>>> {
+    let ReviewOptions {
+        destinations,
+        global_helm_config,
+        no_interactive,
+        force,
+        variable_context,
+        diff_view,
+    } = options;
+
+    let mut decisions = HashMap::new();
+    let mut changed = Vec::new();
+
+    for chart in charts.iter().filter(|c| !c.no_deploy) {
+        tracker.set_message(format!("Reviewing {}...", chart.release_name()));
+        let prepared = prepare_release(
+            chart,
+            destinations,
+            global_helm_config,
+            variable_context,
+            tracker,
+        )?;
+        let diff_content = compute_diff(chart, &prepared, global_helm_config, tracker)
+            .await
+            .with_context(|| format!("Failed to diff chart {}", chart.release_name()))?;
+
+        if console::strip_ansi_codes(&diff_content).trim().is_empty() {
+            let decision = if force {
+                ReviewDecision::Apply
+            } else {
+                ReviewDecision::NoChanges
+            };
+            decisions.insert(chart.release_name().to_string(), decision);
+        } else {
+            tracker.println(&format!(
+                "{} Changes for {}",
+                "📦 ".blue(),
+                chart.release_name().bold()
+            ));
+            tracker.println(&diff_view.render(&diff_content));
+            decisions.insert(chart.release_name().to_string(), ReviewDecision::Apply);
+            changed.push(*chart);
+        }
+    }
+
+    tracker.println(&format!("\n{}", style("Deployment plan:").bold()));
+    for chart in charts {
+        let label = match decisions.get(chart.release_name()) {
+            None => "no_deploy".dimmed(),
+            Some(ReviewDecision::NoChanges) => "no changes, skipped".dimmed(),
+            Some(_)
+                if changed
+                    .iter()
+                    .any(|c| c.release_name() == chart.release_name()) =>
+            {
+                "changes".yellow()
+            }
+            Some(_) => "no changes, forced".yellow(),
+        };
+        tracker.println(&format!("  - {} ({})", chart.release_name().bold(), label));
+    }
+    tracker.println("");
+
+    if changed.is_empty() || no_interactive {
+        return Ok(Some(decisions));
+    }
+
+    let choice = tracker.suspend(|| {
+        Select::new()
+            .with_prompt("How do you want to proceed?")
+            .items(["Apply all", "Confirm each chart", "Abort"])
+            .default(0)
+            .interact()
+            .context("Failed to read user selection")
+    })?;
+
+    match choice {
+        0 => {}
+        1 => {
+            for chart in &changed {
+                let confirmation = tracker.suspend(|| {
+                    Confirm::new()
+                        .with_prompt(format!(
+                            "Do you want to deploy {}?",
+                            chart.release_name().bold().cyan()
+                        ))
+                        .default(false)
+                        .interact()
+                        .context("Failed to read user confirmation")
+                })?;
+                if !confirmation {
+                    decisions.insert(chart.release_name().to_string(), ReviewDecision::Declined);
+                }
+            }
+        }
+        _ => return Ok(None),
+    }
+
+    Ok(Some(decisions))
 }
 
 // Removed allow(clippy::too_many_arguments)
@@ -177,6 +498,7 @@ async fn deploy_chart(
     chart: &Chart,
     options: DeployOptions<'_>,
     tracker: &ProgressTracker,
+    values_hash: &mut Option<String>,
 ) -> Result<DeployStatus> {
     let DeployOptions {
         destinations,
@@ -186,246 +508,246 @@ async fn deploy_chart(
         force,
         take_ownership,
         variable_context,
+        diff_view,
+        review,
+        run_tests,
+        policies,
     } = options;
 
-    tracker.set_message(format!("Deploying {}...", chart.name));
+    tracker.set_message(format!("Deploying {}...", chart.release_name()));
     tracker.println(&format!(
         "{} Deploying chart {}",
         "📦 ".blue(),
-        chart.name.bold()
+        chart.release_name().bold()
     ));
-    // Determine destination path
-    let dest_path = get_destination_path(chart, destinations)?;
-
-    // Construct Helm arguments
-    let mut args_str = construct_helm_args(chart, global_helm_config)?;
-
-    // Prepare values flags
-    let mut values_flags = String::new();
-    // Use TempDir to persist files until the end of the deployment function (Drop cleans up)
-    let _rendered_temp_dir = tempfile::Builder::new()
-        .prefix("vesshelm-values-")
-        .tempdir()
-        .context("Failed to create temporary directory for values files")?;
-
-    // Handle local 'values.yaml' interpolation (Implicit default values)
-    if !variable_context.is_null()
-        && !chart.no_interpolation
-        && chart.repo_name.is_none()
-        && let Some(chart_src_path) = &chart.chart_path
-    {
-        let values_path = std::path::Path::new(chart_src_path).join("values.yaml");
-        if values_path.exists() {
-            match crate::util::variables::render_values_file(&values_path, variable_context) {
-                Ok(rendered) => {
-                    let tmp_path = _rendered_temp_dir.path().join("local-values.yaml");
-                    if let Err(e) = std::fs::write(&tmp_path, rendered) {
-                        tracker.println(&format!(
-                            "{} Failed to write temp values: {}",
-                            "⚠️ ".yellow(),
-                            e
-                        ));
-                    } else if let Some(path_str) = tmp_path.to_str() {
-                        values_flags.push_str(" -f ");
-                        values_flags.push_str(path_str);
-                    }
-                }
-                Err(e) => {
-                    return Err(anyhow::anyhow!("Failed to render local values.yaml: {}", e));
-                }
+
+    // Resolve destination, render values files and build helm arguments
+    let prepared = prepare_release(
+        chart,
+        destinations,
+        global_helm_config,
+        variable_context,
+        tracker,
+    )?;
+    *values_hash = audit::values_hash(&prepared.values_files).ok();
+
+    let hook_context = HookContext {
+        chart,
+        global_helm_config,
+        destination: &prepared.destination,
+        variable_context,
+    };
+
+    // Every failure from here on runs the `on_failure` hooks
+    let result: Result<DeployStatus> = async {
+        let violations = schema_violations(chart, destinations, &prepared)?;
+        if !violations.is_empty() {
+            for violation in &violations {
+                tracker.println(&format!("    {} {}", "➜".red(), violation));
             }
+            return Err(anyhow!(
+                "Values of {} do not match the chart's {}",
+                chart.release_name(),
+                SCHEMA_FILE
+            ));
         }
-    }
 
-    if let Some(files) = &chart.values_files {
-        for (i, file_path_str) in files.iter().enumerate() {
-            let path = std::path::Path::new(file_path_str);
-            let file_arg =
-                if !variable_context.is_null() && !chart.no_interpolation && path.exists() {
-                    let rendered =
-                        crate::util::variables::render_values_file(path, variable_context)
-                            .with_context(|| format!("Failed to render values file {:?}", path))?;
-                    let tmp_path = _rendered_temp_dir.path().join(format!("values-{}.yaml", i));
-                    std::fs::write(&tmp_path, rendered)?;
-                    tmp_path
-                        .to_str()
-                        .ok_or_else(|| anyhow!("Invalid temp path"))?
-                        .to_string()
+        if let Some(policies) = policies {
+            let manifest = render_manifest(chart, &prepared).await?;
+            let violations = policy::evaluate(policies, &manifest, &chart.namespace)?;
+            let mut errors = 0;
+            for violation in &violations {
+                if violation.level == PolicyLevel::Error {
+                    errors += 1;
+                    tracker.println(&format!("    {} {}", "➜".red(), violation));
                 } else {
-                    file_path_str.clone()
-                };
-            values_flags.push_str(" -f ");
-            values_flags.push_str(&file_arg);
+                    tracker.println(&format!("    {} {}", "⚠️ ".yellow(), violation));
+                }
+            }
+            if errors > 0 {
+                return Err(anyhow!(
+                    "{} violates {} polic{}",
+                    chart.release_name(),
+                    errors,
+                    if errors == 1 { "y" } else { "ies" }
+                ));
+            }
         }
-    }
 
-    // Handle inline values
-    if let Some(values) = &chart.values {
-        let content = crate::util::helm::merge_values(values)?;
-        let tmp_path = _rendered_temp_dir.path().join("inline-values.yaml");
-        std::fs::write(&tmp_path, content)?;
-        values_flags.push_str(" -f ");
-        values_flags.push_str(tmp_path.to_str().ok_or_else(|| anyhow!("Invalid path"))?);
-    }
-
-    // Append values to args
-    args_str.push_str(&values_flags);
-
-    // Apply CLI overrides
-    if take_ownership {
-        args_str.push_str(" --take-ownership");
-    }
-
-    // Interpolate variables
-    let final_args = interpolate_variables(&args_str, chart, &dest_path)?;
-
-    // Handle Diff
-    if dry_run || global_helm_config.diff_enabled {
-        // Use default if None
-        let diff_template = global_helm_config.diff_args.as_deref().unwrap_or(
-            "diff upgrade --suppress-secrets --allow-unreleased {{ name }} {{ destination }} -n {{ namespace }}",
-        );
-
-        let mut final_diff_args = interpolate_variables(diff_template, chart, &dest_path)?;
-        final_diff_args.push_str(&values_flags);
-
-        let diff_output = execute_helm_diff(&final_diff_args, tracker).await?;
+        // Apply CLI overrides
+        let mut final_args = prepared.args.clone();
+        if take_ownership {
+            final_args.push_str(" --take-ownership");
+        }
 
-        // Check if diff is empty (no changes)
-        let diff_content = String::from_utf8_lossy(&diff_output.stdout);
-        if console::strip_ansi_codes(&diff_content).trim().is_empty() {
-            if force {
-                tracker.println(&format!(
-                    "{} No changes detected, but forcing deployment for {}.",
-                    "⚠️ ".yellow(),
-                    chart.name.bold()
-                ));
-            } else {
-                tracker.println(&format!(
-                    "{} No changes for {}. Skipping.",
-                    "⏭ ".dimmed(),
-                    chart.name.bold()
-                ));
-                return Ok(DeployStatus::Skipped);
+        if let Some(decision) = review {
+            // Diff already reviewed up front
+            match decision {
+                ReviewDecision::Apply => {}
+                ReviewDecision::NoChanges => {
+                    tracker.println(&format!(
+                        "{} No changes for {}. Skipping.",
+                        "⏭ ".dimmed(),
+                        chart.release_name().bold()
+                    ));
+                    return Ok(DeployStatus::Skipped);
+                }
+                ReviewDecision::Declined => {
+                    tracker.println(&format!(
+                        " {} User skipped deployment of {}.",
+                        "⏭ ".yellow(),
+                        chart.release_name().bold()
+                    ));
+                    return Ok(DeployStatus::Ignored);
+                }
             }
-        }
+        } else if dry_run || global_helm_config.diff_enabled {
+            // Handle Diff
+            let diff_content = compute_diff(chart, &prepared, global_helm_config, tracker).await?;
 
-        // Print diff to stdout for user to see
-        tracker.println(&diff_content);
+            // Check if diff is empty (no changes)
+            if console::strip_ansi_codes(&diff_content).trim().is_empty() {
+                if force {
+                    tracker.println(&format!(
+                        "{} No changes detected, but forcing deployment for {}.",
+                        "⚠️ ".yellow(),
+                        chart.release_name().bold()
+                    ));
+                } else {
+                    tracker.println(&format!(
+                        "{} No changes for {}. Skipping.",
+                        "⏭ ".dimmed(),
+                        chart.release_name().bold()
+                    ));
+                    return Ok(DeployStatus::Skipped);
+                }
+            }
 
-        if dry_run {
-            return Ok(DeployStatus::Ignored);
-        }
+            // Print diff to stdout for user to see
+            tracker.println(&diff_view.render(&diff_content));
 
-        // Interactive Confirmation
-        if !(no_interactive || force) {
-            let confirmation = tracker.suspend(|| {
-                println!();
-                Confirm::new()
-                    .with_prompt(format!(
-                        "Do you want to deploy {}?",
-                        chart.name.bold().cyan()
-                    ))
-                    .default(false)
-                    .interact()
-                    .context("Failed to read user confirmation")
-            });
-            let confirmation = confirmation?;
-
-            if !confirmation {
-                tracker.println(&format!(
-                    " {} User skipped deployment of {}.",
-                    "⏭ ".yellow(),
-                    chart.name.bold()
-                ));
+            if dry_run {
                 return Ok(DeployStatus::Ignored);
             }
-        }
-    }
 
-    // Execute Helm command
-    execute_helm_command(&final_args, tracker).await?;
-
-    Ok(DeployStatus::Deployed)
-}
+            // Interactive Confirmation
+            if !(no_interactive || force) {
+                let confirmation = tracker.suspend(|| {
+                    println!();
+                    Confirm::new()
+                        .with_prompt(format!(
+                            "Do you want to deploy {}?",
+                            chart.release_name().bold().cyan()
+                        ))
+                        .default(false)
+                        .interact()
+                        .context("Failed to read user confirmation")
+                });
+                let confirmation = confirmation?;
+
+                if !confirmation {
+                    tracker.println(&format!(
+                        " {} User skipped deployment of {}.",
+                        "⏭ ".yellow(),
+                        chart.release_name().bold()
+                    ));
+                    return Ok(DeployStatus::Ignored);
+                }
+            }
+        }
 
-fn get_destination_path(
-    chart: &Chart,
-    destinations: &[crate::config::Destination],
-) -> Result<String> {
-    // 1. Check for destination override in chart
-    if let Some(override_path) = &chart.dest {
-        if let Some(d) = destinations.iter().find(|d| d.name == *override_path) {
-            return Ok(d.path.clone());
+        // Execute hooks and Helm command
+        run_hooks(&hook_context, HookPhase::PreDeploy, tracker).await?;
+        execute_helm_command(&final_args, tracker).await?;
+        if run_tests || chart.test_after_deploy {
+            run_helm_tests(chart, tracker).await?;
         }
-        // Use as direct path
-        return Ok(override_path.clone());
-    }
+        run_hooks(&hook_context, HookPhase::PostDeploy, tracker).await?;
 
-    // 2. Local chart support
-    if chart.repo_name.is_none()
-        && let Some(path) = &chart.chart_path
-    {
-        return Ok(path.clone());
+        Ok(DeployStatus::Deployed)
     }
+    .await;
 
-    // Default destination
-    if let Some(default_dest) = destinations.iter().find(|d| d.name == "default") {
-        return Ok(default_dest.path.clone());
+    if result.is_err()
+        && let Err(hook_error) = run_hooks(&hook_context, HookPhase::OnFailure, tracker).await
+    {
+        tracker.println(&format!(" {} {:#}", style("WARN:").yellow(), hook_error));
     }
-
-    Err(anyhow!(
-        "Could not determine destination path for chart {}",
-        chart.name
-    ))
+    result
 }
 
-fn construct_helm_args(chart: &Chart, global_helm_config: &VesshelmConfig) -> Result<String> {
-    if let Some(override_args) = &chart.helm_args_override {
-        return Ok(override_args.clone());
-    }
-
-    let mut args = global_helm_config.helm_args.clone();
+/// Runs `helm test` on a freshly upgraded release.
+///
+/// Fails with [`VesshelmError::HelmTestFailed`] so the summary can report test results.
+async fn run_helm_tests(chart: &Chart, tracker: &ProgressTracker) -> Result<()> {
+    let args = format!("test {} -n {}", chart.release_name(), chart.namespace);
+    tracker.println(&format!("{} helm {}", "🧪 ".dimmed(), args.dimmed()));
 
-    if let Some(append_args) = &chart.helm_args_append {
-        args.push(' ');
-        args.push_str(append_args);
+    let mut cmd = Command::new("helm");
+    cmd.args(args.split_whitespace());
+    if let Err(e) = execute_command(cmd, tracker).await {
+        return Err(VesshelmError::HelmTestFailed(
+            chart.release_name().to_string(),
+            format!("{:#}", e),
+        )
+        .into());
     }
 
-    Ok(args)
+    tracker.println(&format!(
+        " {} Tests passed for {}",
+        style("[OK]").green(),
+        chart.release_name().bold()
+    ));
+    Ok(())
 }
 
-fn interpolate_variables(args_template: &str, chart: &Chart, destination: &str) -> Result<String> {
-    let mut result = args_template.to_string();
+struct HookContext<'a> {
+    chart: &'a Chart,
+    global_helm_config: &'a VesshelmConfig,
+    destination: &'a str,
+    variable_context: &'a serde_yaml_ng::Value,
+}
 
-    // Calculate full chart path for robust replacement
-    let full_chart_path = if chart.repo_name.is_none() {
-        // For local charts, destination IS the chart path in our logic above
-        destination.to_string()
-    } else {
-        // For remote charts, destination is parent dir
-        format!("{}/{}", destination, chart.name)
-    };
+/// Runs the hooks of a phase in order, stopping at the first failure.
+async fn run_hooks(
+    context: &HookContext<'_>,
+    phase: HookPhase,
+    tracker: &ProgressTracker,
+) -> Result<()> {
+    for template in hooks_for(context.chart, context.global_helm_config, phase) {
+        // Only the template is shown or reported: the rendered command may hold decrypted secrets
+        tracker.println(&format!(
+            "{} {} {}",
+            "🪝 ".dimmed(),
+            phase.as_str().dimmed(),
+            template.dimmed()
+        ));
+        let command = render_hook(
+            template,
+            context.chart,
+            context.destination,
+            context.variable_context,
+        )?;
+
+        #[cfg(unix)]
+        let mut cmd = {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c");
+            cmd
+        };
+        #[cfg(windows)]
+        let mut cmd = {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C");
+            cmd
+        };
+        cmd.arg(&command);
 
-    // Smart replacement: Handle the common pattern "{{ destination }}/{{ name }}" first
-    // This prevents "./my-chart/my-chart" issue for local charts
-    result = result.replace("{{ destination }}/{{ name }}", &full_chart_path);
-    result = result.replace("{{destination}}/{{name}}", &full_chart_path);
-
-    result = result.replace("{{ name }}", &chart.name);
-    result = result.replace("{{ destination }}", destination);
-    result = result.replace("{{ namespace }}", &chart.namespace);
-    result = result.replace("{{ version }}", chart.version.as_deref().unwrap_or(""));
-    result = result.replace("{{ chart_path }}", &full_chart_path);
-
-    // Also support {{name}} without spaces just in case
-    result = result.replace("{{name}}", &chart.name);
-    result = result.replace("{{destination}}", destination);
-    result = result.replace("{{namespace}}", &chart.namespace);
-    result = result.replace("{{version}}", chart.version.as_deref().unwrap_or(""));
-    result = result.replace("{{chart_path}}", &full_chart_path);
-
-    Ok(result)
+        execute_command(cmd, tracker)
+            .await
+            .with_context(|| format!("{} hook '{}' failed", phase.as_str(), template))?;
+    }
+    Ok(())
 }
 
 async fn execute_helm_command(
@@ -438,18 +760,25 @@ async fn execute_helm_command(
 
     let mut cmd = Command::new("helm");
     cmd.args(&parts);
+    execute_command(cmd, tracker)
+        .await
+        .context("Helm command failed")
+}
+
+/// Runs a command, streaming its output through the tracker and forwarding termination signals.
+async fn execute_command(mut cmd: Command, tracker: &ProgressTracker) -> Result<()> {
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
     // We need to group the child process or simply track its PID.
     // In Rust tokio::process::Command, we get a Child handle.
 
-    let mut child = cmd.spawn().context("Failed to execute helm command")?;
+    let mut child = cmd.spawn().context("Failed to execute command")?;
 
     // Capture pid for signal forwarding
     let pid = child
         .id()
-        .ok_or_else(|| anyhow!("Failed to get helm process id"))?;
+        .ok_or_else(|| anyhow!("Failed to get process id"))?;
 
     let stdout = child.stdout.take().context("Failed to capture stdout")?;
     let stderr = child.stderr.take().context("Failed to capture stderr")?;
@@ -476,7 +805,7 @@ async fn execute_helm_command(
         res = child.wait() => res?,
         signal_res = wait_for_signal() => {
             let signal_name = signal_res?;
-            tracker.println(&format!("{}", format!("Received signal ({}). Stopping helm...", signal_name).yellow()));
+            tracker.println(&format!("{}", format!("Received signal ({}). Stopping command...", signal_name).yellow()));
 
             // Terminate child process
             terminate_process(&mut child, pid).await?;
@@ -490,7 +819,7 @@ async fn execute_helm_command(
 
     if !status.success() {
         return Err(anyhow!(
-            "Helm command failed/interrupted with status: {}",
+            "Command failed/interrupted with status: {}",
             status
         ));
     }
@@ -498,35 +827,6 @@ async fn execute_helm_command(
     Ok(())
 }
 
-async fn execute_helm_diff(
-    args: &str,
-    tracker: &crate::util::progress::ProgressTracker,
-) -> Result<std::process::Output> {
-    tracker.println(&format!("{} helm {}", "🔎 ".dimmed(), args.dimmed()));
-
-    let parts: Vec<&str> = args.split_whitespace().collect();
-
-    // Helm diff outputs to stdout mostly.
-    let output = Command::new("helm")
-        .args(&parts)
-        .env("HELM_DIFF_COLOR", "true")
-        .output()
-        .await
-        .context("Failed to execute helm diff command")?;
-
-    if !output.status.success() {
-        // Print stderr if failed
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        tracker.println(&stderr);
-        return Err(anyhow!(
-            "Helm diff command failed with status: {}",
-            output.status
-        ));
-    }
-
-    Ok(output)
-}
-
 #[cfg(unix)]
 async fn wait_for_signal() -> Result<String> {
     use tokio::signal::unix::{SignalKind, signal};
@@ -563,101 +863,6 @@ async fn terminate_process(child: &mut tokio::process::Child, _pid: u32) -> Resu
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::config::{Chart, VesshelmConfig};
-
-    #[test]
-    fn test_interpolate_variables() -> Result<()> {
-        let chart = Chart {
-            name: "my-chart".to_string(),
-            repo_name: Some("stable".to_string()),
-            version: Some("1.0.0".to_string()),
-            namespace: "my-ns".to_string(),
-            dest: None,
-            chart_path: None,
-            no_sync: false,
-            no_deploy: false,
-            comment: None,
-            values_files: None,
-            helm_args_append: None,
-            helm_args_override: None,
-            values: None,
-            depends: None,
-            no_interpolation: false,
-        };
-        let dest = "./charts/my-chart";
-        let template =
-            "upgrade {{ name }} {{ destination }} -n {{ namespace }} --version {{ version }}";
-
-        let result = interpolate_variables(template, &chart, dest)?;
-        assert_eq!(
-            result,
-            "upgrade my-chart ./charts/my-chart -n my-ns --version 1.0.0"
-        );
-        Ok(())
-    }
-
-    #[test]
-    fn test_construct_helm_args_override() -> Result<()> {
-        let chart = Chart {
-            name: "test".to_string(),
-            repo_name: Some("test".to_string()),
-            version: Some("1.0.0".to_string()),
-            namespace: "default".to_string(),
-            dest: None,
-            chart_path: None,
-            no_sync: false,
-            no_deploy: false,
-            comment: None,
-            values_files: None,
-            helm_args_append: Some("--ignore-this".to_string()),
-            helm_args_override: Some("override".to_string()),
-            values: None,
-            depends: None,
-            no_interpolation: false,
-        };
-        let global = VesshelmConfig {
-            helm_args: "default".to_string(),
-            diff_enabled: false,
-            diff_args: None,
-            deploy_debug_pause: true,
-        };
-
-        let result = construct_helm_args(&chart, &global).unwrap();
-        assert_eq!(result, "override");
-        Ok(())
-    }
-
-    #[test]
-    fn test_construct_helm_args_append() {
-        let chart = Chart {
-            name: "test".to_string(),
-            repo_name: Some("test".to_string()),
-            version: Some("1.0.0".to_string()),
-            namespace: "default".to_string(),
-            dest: None,
-            chart_path: None,
-            no_sync: false,
-            no_deploy: false,
-            comment: None,
-            values_files: None,
-            helm_args_append: Some("--extra".to_string()),
-            helm_args_override: None,
-            values: None,
-            depends: None,
-            no_interpolation: false,
-        };
-        let global = VesshelmConfig {
-            helm_args: "default".to_string(),
-            diff_enabled: false,
-            diff_args: None,
-            deploy_debug_pause: true,
-        };
-
-        let result = construct_helm_args(&chart, &global).unwrap();
-        assert_eq!(result, "default --extra");
-    }
-
     #[test]
     fn test_take_ownership_flag_logic() {
         let mut args = "upgrade my-release".to_string();
@@ -669,4 +874,27 @@ mod tests {
 
         assert_eq!(args, "upgrade my-release --take-ownership");
     }
+
+    #[test]
+    fn test_resume_offers_declined_charts() {
+        use super::DeployStatus;
+        use crate::engine::run_state::DeployRunState;
+
+        let mut run = DeployRunState {
+            planned: vec!["db".to_string(), "api".to_string(), "web".to_string()],
+            ..Default::default()
+        };
+        for (name, status) in [
+            ("db", DeployStatus::Deployed),
+            ("api", DeployStatus::Ignored),
+        ] {
+            if status.completes_run() {
+                run.completed.push(name.to_string());
+            }
+        }
+        run.failed = Some("web".to_string());
+
+        assert_eq!(run.remaining(), vec!["api", "web"]);
+        assert!(DeployStatus::Skipped.completes_run());
+    }
 }
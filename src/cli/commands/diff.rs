@@ -0,0 +1,105 @@
+use super::DiffArgs;
+use crate::config::Config;
+use crate::engine::release::{compute_diff, load_variable_context, prepare_release};
+use crate::error::VesshelmError;
+use crate::util::diff::DiffView;
+use crate::util::progress::ProgressTracker;
+use crate::util::{dag, filter};
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use console::style;
+use std::path::Path;
+
+/// Shows the pending changes of every chart without deploying anything.
+///
+/// Fails with [`VesshelmError::ChangesDetected`] (exit code 2) when any chart has changes.
+pub async fn run(args: DiffArgs, no_progress: bool, config_path: &Path) -> Result<()> {
+    let config = Config::load_from_path(config_path)?;
+
+    let helm_config = config
+        .vesshelm
+        .as_ref()
+        .ok_or_else(|| anyhow!("No vesshelm configuration found in vesshelm.yaml"))?;
+
+    // Load variables and secrets
+    let variable_context = load_variable_context(&config, config_path)?;
+
+    println!("{} Computing differences...", style("==>").bold().green());
+
+    let mut sorted_charts =
+        dag::sort_charts(&config.charts).context("Failed to resolve chart dependencies")?;
+
+    if let Some(charts) = filter::select_charts(
+        &config.charts,
+        args.charts.as_deref(),
+        args.selector.as_ref(),
+    )? {
+        sorted_charts.retain(|chart| charts.iter().any(|n| n == chart.release_name()));
+    }
+
+    let tracker = ProgressTracker::new(sorted_charts.len() as u64, no_progress)
+        .context("Failed to initialize progress tracker")?;
+
+    let diff_view = DiffView::from(&args.diff_view);
+    let mut changed = Vec::new();
+    let mut unchanged_count = 0;
+    let mut skipped_count = 0;
+
+    for chart in sorted_charts {
+        if chart.no_deploy {
+            tracker.println(&format!(
+                " {} {} (no_deploy=true)",
+                "⏭ ".yellow(),
+                chart.release_name()
+            ));
+            skipped_count += 1;
+            tracker.inc();
+            continue;
+        }
+
+        tracker.set_message(format!("Diffing {}...", chart.release_name()));
+
+        let prepared = prepare_release(
+            chart,
+            &config.destinations,
+            helm_config,
+            &variable_context,
+            &tracker,
+        )
+        .with_context(|| format!("Failed to prepare chart {}", chart.release_name()))?;
+        let diff_content = compute_diff(chart, &prepared, helm_config, &tracker)
+            .await
+            .with_context(|| format!("Failed to diff chart {}", chart.release_name()))?;
+
+        if console::strip_ansi_codes(&diff_content).trim().is_empty() {
+            tracker.println(&format!(
+                " {} {} has no changes",
+                style("[OK]").green(),
+                chart.release_name().bold()
+            ));
+            unchanged_count += 1;
+        } else {
+            tracker.println(&format!(
+                " {} {} has changes",
+                style("[CHANGED]").yellow(),
+                chart.release_name().bold()
+            ));
+            tracker.println(&diff_view.render(&diff_content));
+            changed.push(chart.release_name().to_string());
+        }
+        tracker.inc();
+    }
+
+    tracker.finish_with_message("Diff ended");
+
+    println!("\n\n{}", style("Summary:").bold());
+    println!("  Changed:   {}", style(changed.len()).yellow());
+    println!("  Unchanged: {}", style(unchanged_count).green());
+    println!("  Skipped:   {}", style(skipped_count).dim());
+
+    if !changed.is_empty() {
+        return Err(VesshelmError::ChangesDetected(changed).into());
+    }
+
+    Ok(())
+}
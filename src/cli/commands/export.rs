@@ -0,0 +1,200 @@
+use super::{ExportArgs, ExportFormat};
+use crate::config::{Chart, Config, RepoType};
+use crate::engine::export::{self, Resource};
+use crate::util::{dag, filter};
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use console::style;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Writes the selected charts as Argo CD `Application`s or Flux `HelmRelease`s.
+pub async fn run(args: ExportArgs, config_path: &Path) -> Result<()> {
+    let config = Config::load_from_path(config_path)?;
+    let context = export::placeholder_context(&config, config_path)?;
+
+    let mut sorted_charts =
+        dag::sort_charts(&config.charts).context("Failed to resolve chart dependencies")?;
+    if let Some(charts) = filter::select_charts(
+        &config.charts,
+        args.charts.as_deref(),
+        args.selector.as_ref(),
+    )? {
+        sorted_charts.retain(|chart| charts.iter().any(|n| n == chart.release_name()));
+    }
+
+    // Local charts have no source a GitOps controller could fetch them from
+    let (exported, skipped): (Vec<&Chart>, Vec<&Chart>) = sorted_charts
+        .into_iter()
+        .partition(|chart| chart.repo_name.is_some() && !chart.no_deploy);
+    for chart in &skipped {
+        let reason = if chart.no_deploy {
+            "no_deploy=true"
+        } else {
+            "local chart"
+        };
+        println!(" {} {} ({})", "⏭ ".yellow(), chart.release_name(), reason);
+    }
+
+    let namespace = args.namespace.as_deref().unwrap_or(match args.format {
+        ExportFormat::Argocd => "argocd",
+        ExportFormat::Flux => "flux-system",
+    });
+    let depths = export::dependency_depths(&exported);
+
+    // Dependencies outside the export cannot be ordered by the GitOps controller
+    let mut unmapped = Vec::new();
+    for chart in &exported {
+        for dep in chart.depends.iter().flatten() {
+            if exported.iter().any(|c| c.release_name() == dep) {
+                continue;
+            }
+            let reason = match config.charts.iter().find(|c| c.release_name() == dep) {
+                Some(c) if c.no_deploy => "no_deploy=true",
+                Some(c) if c.repo_name.is_none() => "local chart",
+                _ => "not selected",
+            };
+            unmapped.push((chart.release_name(), dep.as_str(), reason));
+        }
+    }
+
+    std::fs::create_dir_all(&args.output_dir)
+        .with_context(|| format!("Failed to create {}", args.output_dir.display()))?;
+
+    let mut used_repositories = BTreeSet::new();
+    let mut secret_notes = Vec::new();
+    let mut reordered = Vec::new();
+
+    for chart in &exported {
+        let name = export::resource_name(chart, &config.charts);
+        let repo_name = chart.repo_name.as_deref().unwrap_or_default();
+        let repo = config
+            .repositories
+            .iter()
+            .find(|r| r.name == repo_name)
+            .ok_or_else(|| anyhow!("Repository '{}' not found", repo_name))?;
+        let values = export::chart_values(chart, &context)
+            .with_context(|| format!("Failed to export values of {}", chart.release_name()))?;
+        for file in &values.secret_files {
+            secret_notes.push((name.clone(), file.clone()));
+        }
+        if values.secrets_reordered && args.format == ExportFormat::Flux {
+            reordered.push(name.clone());
+        }
+
+        let content = match args.format {
+            ExportFormat::Argocd => {
+                let wave = depths.get(chart.release_name()).copied().unwrap_or(0);
+                let app = export::argocd_application(chart, &name, repo, values, wave, namespace)?;
+                to_documents(&[app])?
+            }
+            ExportFormat::Flux => {
+                let depends_on = chart
+                    .depends
+                    .iter()
+                    .flatten()
+                    .filter_map(|dep| exported.iter().find(|c| c.release_name() == dep))
+                    .map(|dep| export::resource_name(dep, &config.charts))
+                    .collect();
+                let release =
+                    export::flux_helm_release(chart, &name, repo, values, depends_on, namespace);
+                if repo.r#type == RepoType::Git {
+                    let source = export::flux_git_repository(chart, &name, repo, namespace)?;
+                    format!("{}{}", to_documents(&[source])?, to_documents(&[release])?)
+                } else {
+                    used_repositories.insert(repo.name.as_str());
+                    to_documents(&[release])?
+                }
+            }
+        };
+
+        let path = args.output_dir.join(format!("{}.yaml", name));
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!(
+            " {} {} -> {}",
+            style("[OK]").green(),
+            chart.release_name().bold(),
+            path.display()
+        );
+    }
+
+    if !used_repositories.is_empty() {
+        let sources: Vec<_> = config
+            .repositories
+            .iter()
+            .filter(|r| used_repositories.contains(r.name.as_str()))
+            .map(|r| export::flux_helm_repository(r, namespace))
+            .collect();
+        let path = args.output_dir.join("repositories.yaml");
+        std::fs::write(&path, to_documents(&sources)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!(
+            " {} {} -> {}",
+            style("[OK]").green(),
+            "repositories".bold(),
+            path.display()
+        );
+    }
+
+    if !secret_notes.is_empty() {
+        match args.format {
+            ExportFormat::Argocd => println!(
+                "\n{} Secret values files were not exported, provide them to Argo CD separately:",
+                "⚠️ ".yellow()
+            ),
+            ExportFormat::Flux => println!(
+                "\n{} Secret values files were left as references:",
+                "⚠️ ".yellow()
+            ),
+        }
+        for (name, file) in &secret_notes {
+            match args.format {
+                ExportFormat::Argocd => println!("  - {}: {}", name, file),
+                ExportFormat::Flux => println!(
+                    "  - {}: {} (Secret '{}', key 'values.yaml')",
+                    name,
+                    file,
+                    export::secret_name(name, file)
+                ),
+            }
+        }
+    }
+
+    if !reordered.is_empty() {
+        println!(
+            "\n{} Secret values files listed after plain values files now have a lower precedence (valuesFrom is applied before values): {}",
+            "⚠️ ".yellow(),
+            reordered.join(", ")
+        );
+    }
+
+    if !unmapped.is_empty() {
+        println!(
+            "\n{} Dependencies on charts that were not exported are not ordered:",
+            "⚠️ ".yellow()
+        );
+        for (name, dep, reason) in &unmapped {
+            println!("  - {} depends on {} ({})", name, dep, reason);
+        }
+    }
+
+    println!(
+        "\n{} Exported {} chart(s) to {}",
+        style("==>").bold().green(),
+        exported.len(),
+        args.output_dir.display()
+    );
+    Ok(())
+}
+
+/// Serializes resources as a multi-document YAML stream.
+fn to_documents<S: Serialize>(resources: &[Resource<S>]) -> Result<String> {
+    let mut out = String::new();
+    for resource in resources {
+        out.push_str("---\n");
+        out.push_str(&serde_yaml_ng::to_string(resource).context("Failed to serialize resource")?);
+    }
+    Ok(out)
+}
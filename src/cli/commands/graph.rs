@@ -18,27 +18,33 @@ pub async fn run(config_path: &std::path::Path) -> Result<()> {
     let mut has_dependencies: HashMap<String, bool> = HashMap::new(); // Tracks if a chart HAS dependencies (is a child)
 
     for chart in &config.charts {
-        all_charts.insert(chart.name.clone(), chart);
+        all_charts.insert(chart.release_name().to_string(), chart);
 
         // Initialize entry
-        dependents_map.entry(chart.name.clone()).or_default();
+        dependents_map
+            .entry(chart.release_name().to_string())
+            .or_default();
 
         if let Some(deps) = &chart.depends {
             if !deps.is_empty() {
-                has_dependencies.insert(chart.name.clone(), true);
+                has_dependencies.insert(chart.release_name().to_string(), true);
                 for dep in deps {
                     dependents_map
                         .entry(dep.clone())
                         .or_default()
-                        .push(chart.name.clone());
+                        .push(chart.release_name().to_string());
                     // Also ensure dependency exists in has_dependencies map (default false)
                     has_dependencies.entry(dep.clone()).or_insert(false);
                 }
             } else {
-                has_dependencies.entry(chart.name.clone()).or_insert(false);
+                has_dependencies
+                    .entry(chart.release_name().to_string())
+                    .or_insert(false);
             }
         } else {
-            has_dependencies.entry(chart.name.clone()).or_insert(false);
+            has_dependencies
+                .entry(chart.release_name().to_string())
+                .or_insert(false);
         }
     }
 
@@ -0,0 +1,138 @@
+use super::{HistoryArgs, OutputFormat};
+use crate::engine::audit::{self, AuditRecord};
+use crate::util::state::state_dir;
+use anyhow::{Context, Result};
+use console::style;
+use std::path::Path;
+
+pub async fn run(args: HistoryArgs, config_path: &Path) -> Result<()> {
+    let records = audit::load(&state_dir(config_path))?;
+    let records = select_records(records, args.chart.as_deref(), args.limit);
+
+    match args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&records).context("Failed to serialize history")?
+        ),
+        OutputFormat::Table => print_table(&records),
+    }
+    Ok(())
+}
+
+/// Keeps the records of `chart` (all charts if `None`), limited to the `limit` most recent ones.
+fn select_records(
+    records: Vec<AuditRecord>,
+    chart: Option<&str>,
+    limit: usize,
+) -> Vec<AuditRecord> {
+    let mut records: Vec<_> = records
+        .into_iter()
+        .filter(|r| chart.is_none_or(|name| r.chart == name))
+        .collect();
+    let skip = records.len().saturating_sub(limit);
+    records.drain(..skip);
+    records
+}
+
+fn print_table(records: &[AuditRecord]) {
+    if records.is_empty() {
+        println!("No deployment recorded yet.");
+        return;
+    }
+
+    let headers = [
+        "TIMESTAMP",
+        "CHART",
+        "VERSION",
+        "NAMESPACE",
+        "STATUS",
+        "USER",
+        "GIT",
+        "DURATION",
+    ];
+    let rows: Vec<[String; 8]> = records
+        .iter()
+        .map(|r| {
+            let git = match &r.git_head {
+                Some(head) => format!(
+                    "{}{}",
+                    &head[..head.len().min(8)],
+                    if r.git_dirty { "*" } else { "" }
+                ),
+                None => "-".to_string(),
+            };
+            [
+                r.timestamp.clone(),
+                r.chart.clone(),
+                r.version.clone().unwrap_or_else(|| "-".to_string()),
+                r.namespace.clone(),
+                r.status.clone(),
+                format!("{}@{}", r.user, r.hostname),
+                git,
+                format!("{:.1}s", r.duration_ms as f64 / 1000.0),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: &[String]| -> String {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, w)| format!("{:<w$}", cell))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    println!("{}", style(format_row(&headers)).bold());
+    for row in &rows {
+        println!("{}", format_row(row));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(chart: &str, version: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            user: "alice".to_string(),
+            hostname: "laptop".to_string(),
+            git_head: None,
+            git_dirty: false,
+            chart: chart.to_string(),
+            version: Some(version.to_string()),
+            namespace: "default".to_string(),
+            values_hash: None,
+            status: "deployed".to_string(),
+            duration_ms: 1200,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_select_records() {
+        let records = vec![
+            record("api", "1.0.0"),
+            record("web", "1.0.0"),
+            record("api", "1.1.0"),
+            record("api", "1.2.0"),
+        ];
+
+        let selected = select_records(records.clone(), Some("api"), 2);
+        let versions: Vec<_> = selected.iter().map(|r| r.version.as_deref()).collect();
+        assert_eq!(versions, vec![Some("1.1.0"), Some("1.2.0")]);
+
+        assert_eq!(select_records(records, None, 10).len(), 4);
+    }
+}
@@ -0,0 +1,266 @@
+use super::{ImagesArgs, ImagesFormat};
+use crate::config::Config;
+use crate::engine::release::{load_variable_context, prepare_release, render_manifest};
+use crate::util::manifest::{parse_manifest, pod_spec};
+use crate::util::progress::ProgressTracker;
+use crate::util::{dag, filter};
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use serde::Serialize;
+use serde_yaml_ng::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Container lists of a pod spec.
+const CONTAINER_LISTS: [&str; 3] = ["initContainers", "containers", "ephemeralContainers"];
+
+#[derive(Serialize)]
+struct ChartImages {
+    chart: String,
+    namespace: String,
+    images: Vec<String>,
+}
+
+/// Lists the container images every selected release runs, from its manifests rendered with
+/// `helm template`.
+pub async fn run(args: ImagesArgs, config_path: &Path) -> Result<()> {
+    let config = Config::load_from_path(config_path)?;
+
+    let helm_config = config
+        .vesshelm
+        .as_ref()
+        .ok_or_else(|| anyhow!("No vesshelm configuration found in vesshelm.yaml"))?;
+
+    let variable_context = load_variable_context(&config, config_path)?;
+
+    let mut sorted_charts =
+        dag::sort_charts(&config.charts).context("Failed to resolve chart dependencies")?;
+    if let Some(charts) = filter::select_charts(
+        &config.charts,
+        args.charts.as_deref(),
+        args.selector.as_ref(),
+    )? {
+        sorted_charts.retain(|chart| charts.iter().any(|n| n == chart.release_name()));
+    }
+
+    // Progress goes to stderr, keeping stdout for the inventory
+    let tracker = ProgressTracker::new(0, true)?;
+    let mut inventory = Vec::new();
+    for chart in sorted_charts {
+        if chart.no_deploy {
+            eprintln!(
+                " {} {} (no_deploy=true)",
+                "⏭ ".yellow(),
+                chart.release_name()
+            );
+            continue;
+        }
+
+        let prepared = prepare_release(
+            chart,
+            &config.destinations,
+            helm_config,
+            &variable_context,
+            &tracker,
+        )?;
+        let manifest = render_manifest(chart, &prepared)
+            .await
+            .with_context(|| format!("Failed to render {}", chart.release_name()))?;
+        inventory.push(ChartImages {
+            chart: chart.release_name().to_string(),
+            namespace: chart.namespace.clone(),
+            images: manifest_images(&manifest, &chart.namespace)?,
+        });
+    }
+
+    match args.format {
+        ImagesFormat::Text => {
+            for chart in &inventory {
+                println!("{}:", chart.chart.bold());
+                for image in &chart.images {
+                    println!("  {}", image);
+                }
+            }
+        }
+        ImagesFormat::Json => println!("{}", serde_json::to_string_pretty(&inventory)?),
+        ImagesFormat::Mirror => print!("{}", mirror_script(&inventory)),
+    }
+    Ok(())
+}
+
+/// Deduplicated, sorted images of the pod specs of a multi-document manifest.
+fn manifest_images(manifest: &str, namespace: &str) -> Result<Vec<String>> {
+    let mut images = BTreeSet::new();
+    for (key, object) in parse_manifest(manifest, namespace)? {
+        let Some(pod) = pod_spec(&key.kind, &object) else {
+            continue;
+        };
+        for list in CONTAINER_LISTS {
+            let containers = pod.get(list).and_then(Value::as_sequence);
+            for container in containers.into_iter().flatten() {
+                if let Some(image) = container.get("image").and_then(Value::as_str) {
+                    images.insert(image.to_string());
+                }
+            }
+        }
+    }
+    Ok(images.into_iter().collect())
+}
+
+/// Shell script copying every image once to `$REGISTRY` with skopeo, keeping its repository path.
+fn mirror_script(inventory: &[ChartImages]) -> String {
+    let mut script = String::from(
+        "#!/bin/sh\nset -e\nREGISTRY=\"${REGISTRY:?set REGISTRY to the mirror registry}\"\n",
+    );
+    let mut copied = BTreeSet::new();
+    for chart in inventory {
+        let images: Vec<&String> = chart
+            .images
+            .iter()
+            .filter(|image| copied.insert(image.as_str()))
+            .collect();
+        if images.is_empty() {
+            continue;
+        }
+        script.push_str(&format!("\n# {}\n", chart.chart));
+        for image in images {
+            let (source, path) = normalize_image(image);
+            script.push_str(&format!(
+                "skopeo copy --all --preserve-digests docker://{} docker://$REGISTRY/{}\n",
+                source, path
+            ));
+        }
+    }
+    script
+}
+
+/// Fully qualified reference of an image, and its repository path (with tag) under a mirror.
+///
+/// Short names resolve to Docker Hub like the container runtimes do (`nginx` is
+/// `docker.io/library/nginx`). A digest is kept on the source only, as a destination must be a tag.
+fn normalize_image(image: &str) -> (String, String) {
+    let (first, rest) = image.split_once('/').unwrap_or(("", image));
+    let is_registry = first.contains('.') || first.contains(':') || first == "localhost";
+    let (registry, repository) = match (first, is_registry) {
+        ("", _) => ("docker.io".to_string(), format!("library/{}", rest)),
+        (_, true) => (first.to_string(), rest.to_string()),
+        (_, false) => ("docker.io".to_string(), image.to_string()),
+    };
+    let path = repository
+        .split_once('@')
+        .map_or(repository.as_str(), |(name, _)| name)
+        .to_string();
+    (format!("{}/{}", registry, repository), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_images() {
+        let manifest = r#"---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  template:
+    spec:
+      initContainers:
+        - name: init
+          image: busybox:1.36
+      containers:
+        - name: web
+          image: nginx:1.25
+        - name: sidecar
+          image: busybox:1.36
+---
+apiVersion: batch/v1
+kind: CronJob
+metadata:
+  name: backup
+spec:
+  jobTemplate:
+    spec:
+      template:
+        spec:
+          containers:
+            - name: backup
+              image: ghcr.io/acme/backup:2.0
+---
+apiVersion: v1
+kind: Pod
+metadata:
+  name: debug
+spec:
+  ephemeralContainers:
+    - name: debugger
+      image: alpine@sha256:abc
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: config
+data:
+  image: not-an-image
+"#;
+        assert_eq!(
+            manifest_images(manifest, "default").unwrap(),
+            vec![
+                "alpine@sha256:abc",
+                "busybox:1.36",
+                "ghcr.io/acme/backup:2.0",
+                "nginx:1.25"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_image() {
+        assert_eq!(
+            normalize_image("nginx:1.25"),
+            (
+                "docker.io/library/nginx:1.25".to_string(),
+                "library/nginx:1.25".to_string()
+            )
+        );
+        assert_eq!(
+            normalize_image("bitnami/redis:7.2"),
+            (
+                "docker.io/bitnami/redis:7.2".to_string(),
+                "bitnami/redis:7.2".to_string()
+            )
+        );
+        assert_eq!(
+            normalize_image("registry.local:5000/team/app:1.0@sha256:abc"),
+            (
+                "registry.local:5000/team/app:1.0@sha256:abc".to_string(),
+                "team/app:1.0".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_mirror_script_copies_each_image_once() {
+        let inventory = vec![
+            ChartImages {
+                chart: "web".to_string(),
+                namespace: "front".to_string(),
+                images: vec!["nginx:1.25".to_string()],
+            },
+            ChartImages {
+                chart: "web-canary".to_string(),
+                namespace: "front".to_string(),
+                images: vec!["nginx:1.25".to_string()],
+            },
+        ];
+
+        let script = mirror_script(&inventory);
+        assert_eq!(script.matches("skopeo copy").count(), 1);
+        assert!(script.contains(
+            "# web\nskopeo copy --all --preserve-digests docker://docker.io/library/nginx:1.25 docker://$REGISTRY/library/nginx:1.25\n"
+        ));
+        assert!(!script.contains("web-canary"));
+    }
+}
@@ -0,0 +1,696 @@
+//! Translation of a `helmfile.yaml` into a vesshelm configuration.
+
+use crate::config::{Chart, Config, Destination, Hooks, RepoType, Repository, VesshelmConfig};
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde_yaml_ng::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const BASE_HELM_ARGS: &str =
+    "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}";
+
+/// Configuration translated from a helmfile, with the features that could not be mapped.
+pub struct Translation {
+    pub config: Config,
+    pub unmapped: Vec<String>,
+}
+
+/// Parses every YAML document of a helmfile, with merge keys (`<<: *template`) applied.
+pub fn parse(content: &str) -> Result<Vec<Value>> {
+    let mut documents = Vec::new();
+    for document in serde_yaml_ng::Deserializer::from_str(content) {
+        let mut value = Value::deserialize(document).context("Failed to parse helmfile")?;
+        value
+            .apply_merge()
+            .context("Failed to apply YAML merge keys")?;
+        if !value.is_null() {
+            documents.push(value);
+        }
+    }
+    Ok(documents)
+}
+
+/// Translates the documents of a helmfile located in `helmfile_dir`; paths are made relative
+/// to `config_dir`, the directory of the generated `vesshelm.yaml`.
+pub fn translate(
+    documents: &[Value],
+    helmfile_dir: &Path,
+    config_dir: &Path,
+) -> Result<Translation> {
+    let mut translator = Translator {
+        helmfile_dir,
+        config_dir,
+        repositories: Vec::new(),
+        charts: Vec::new(),
+        unmapped: Vec::new(),
+        create_namespace: true,
+    };
+
+    let mut defaults = Mapping::new();
+    let mut common_labels = BTreeMap::new();
+    let mut releases = Vec::new();
+
+    for document in documents {
+        let map = document
+            .as_mapping()
+            .ok_or_else(|| anyhow!("helmfile documents must be mappings"))?;
+        for (key, value) in map {
+            match key.as_str().unwrap_or_default() {
+                "repositories" => {
+                    for repo in value.as_sequence().into_iter().flatten() {
+                        translator.add_repository(repo)?;
+                    }
+                }
+                "releases" => releases.extend(value.as_sequence().into_iter().flatten()),
+                "helmDefaults" => {
+                    if let Some(map) = value.as_mapping() {
+                        defaults.extend(map.clone());
+                    }
+                }
+                "commonLabels" => common_labels.extend(string_map(value)),
+                // Only holds the anchors expanded by the parser
+                "templates" => {}
+                other => translator
+                    .unmapped
+                    .push(format!("top-level '{}' is not supported", other)),
+            }
+        }
+    }
+
+    let helm_args = translator.default_args(&defaults);
+    for release in releases {
+        translator.add_release(release, &common_labels)?;
+    }
+
+    let Translator {
+        repositories,
+        charts,
+        unmapped,
+        ..
+    } = translator;
+
+    Ok(Translation {
+        config: Config {
+            repositories,
+            charts,
+            destinations: vec![Destination {
+                name: "default".to_string(),
+                path: "./charts".to_string(),
+            }],
+            vesshelm: Some(VesshelmConfig {
+                helm_args,
+                diff_enabled: true,
+                diff_args: None,
+                deploy_debug_pause: true,
+                diff_engine: Default::default(),
+                hooks: None,
+            }),
+            variables_files: None,
+            secrets_files: None,
+            policies: None,
+        },
+        unmapped,
+    })
+}
+
+struct Translator<'a> {
+    helmfile_dir: &'a Path,
+    config_dir: &'a Path,
+    repositories: Vec<Repository>,
+    charts: Vec<Chart>,
+    unmapped: Vec<String>,
+    /// `helmDefaults.createNamespace`, true unless disabled.
+    create_namespace: bool,
+}
+
+impl Translator<'_> {
+    fn add_repository(&mut self, repo: &Value) -> Result<()> {
+        let name = str_field(repo, "name").ok_or_else(|| anyhow!("repository without name"))?;
+        let url =
+            str_field(repo, "url").ok_or_else(|| anyhow!("repository '{}' without url", name))?;
+        let oci = repo.get("oci").and_then(Value::as_bool).unwrap_or(false);
+
+        for key in repo.as_mapping().into_iter().flat_map(|m| m.keys()) {
+            let key = key.as_str().unwrap_or_default();
+            if !matches!(key, "name" | "url" | "oci") {
+                self.unmapped
+                    .push(format!("repository '{}': '{}' is not supported", name, key));
+            }
+        }
+
+        self.repositories.push(if oci {
+            Repository {
+                name,
+                url: format!("oci://{}", url.trim_start_matches("oci://")),
+                r#type: RepoType::Oci,
+            }
+        } else {
+            Repository {
+                name,
+                url,
+                r#type: RepoType::Helm,
+            }
+        });
+        Ok(())
+    }
+
+    /// `helm_args` of the configuration, from `helmDefaults`.
+    fn default_args(&mut self, defaults: &Mapping) -> String {
+        let mut args = vec![BASE_HELM_ARGS.to_string()];
+        for (key, value) in defaults {
+            let key = key.as_str().unwrap_or_default();
+            match key {
+                "createNamespace" => self.create_namespace = value.as_bool().unwrap_or(true),
+                "args" => args.extend(
+                    value
+                        .as_sequence()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(scalar_to_string),
+                ),
+                _ => match helm_flag(key, value) {
+                    Some(flag) => args.extend(flag),
+                    None => self
+                        .unmapped
+                        .push(format!("helmDefaults.{} is not supported", key)),
+                },
+            }
+        }
+        if self.create_namespace {
+            args.push("--create-namespace".to_string());
+        }
+        args.join(" ")
+    }
+
+    fn add_release(
+        &mut self,
+        release: &Value,
+        common_labels: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let release_name =
+            str_field(release, "name").ok_or_else(|| anyhow!("release without name"))?;
+        let chart_ref = str_field(release, "chart")
+            .ok_or_else(|| anyhow!("release '{}' without chart", release_name))?;
+
+        let mut chart = Chart {
+            name: release_name.clone(),
+            version: release.get("version").and_then(scalar_to_string),
+            namespace: match str_field(release, "namespace") {
+                Some(namespace) => namespace,
+                None => {
+                    self.unmapped.push(format!(
+                        "release '{}': no namespace, 'default' used",
+                        release_name
+                    ));
+                    "default".to_string()
+                }
+            },
+            ..Default::default()
+        };
+        self.resolve_chart(&mut chart, &chart_ref, &release_name);
+        if chart.name != release_name {
+            chart.release = Some(release_name.clone());
+        }
+        if chart.repo_name.is_some() && chart.version.is_none() {
+            self.unmapped.push(format!(
+                "release '{}': no chart version, set one before syncing",
+                release_name
+            ));
+        }
+
+        let mut labels = common_labels.clone();
+        let mut values_files = Vec::new();
+        let mut values = Vec::new();
+        let mut flags = Vec::new();
+
+        for (key, value) in release.as_mapping().into_iter().flatten() {
+            let key = key.as_str().unwrap_or_default();
+            match key {
+                "name" | "chart" | "version" | "namespace" => {}
+                "values" | "secrets" => {
+                    for entry in value.as_sequence().into_iter().flatten() {
+                        match entry {
+                            Value::String(file) => {
+                                if file.contains("{{") || file.ends_with(".gotmpl") {
+                                    self.unmapped.push(format!(
+                                        "release '{}': values file '{}' is a Go template, it is not rendered",
+                                        release_name, file
+                                    ));
+                                }
+                                values_files.push(self.relocate(file));
+                            }
+                            Value::Mapping(_) => values.push(entry.clone()),
+                            _ => self.unmapped.push(format!(
+                                "release '{}': unsupported {} entry",
+                                release_name, key
+                            )),
+                        }
+                    }
+                }
+                "set" | "setString" => {
+                    let mut set_values = Value::Mapping(Mapping::new());
+                    for entry in value.as_sequence().into_iter().flatten() {
+                        let (Some(name), Some(value)) =
+                            (str_field(entry, "name"), entry.get("value"))
+                        else {
+                            self.unmapped.push(format!(
+                                "release '{}': {} entry without name or value",
+                                release_name, key
+                            ));
+                            continue;
+                        };
+                        if name.contains('[') {
+                            self.unmapped.push(format!(
+                                "release '{}': {} '{}' uses list indexes",
+                                release_name, key, name
+                            ));
+                            continue;
+                        }
+                        let value = if key == "setString" {
+                            Value::String(scalar_to_string(value).unwrap_or_default())
+                        } else {
+                            value.clone()
+                        };
+                        set_path(&mut set_values, &split_set_path(&name), value);
+                    }
+                    values.push(set_values);
+                }
+                "needs" => {
+                    // `needs` entries are `[kube-context/][namespace/]name`
+                    let depends: Vec<String> = value
+                        .as_sequence()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(|need| need.rsplit('/').next().unwrap_or(need).to_string())
+                        .collect();
+                    chart.depends = Some(depends);
+                }
+                "labels" => labels.extend(string_map(value)),
+                "installed" => {
+                    if value.as_bool() == Some(false) {
+                        chart.no_deploy = true;
+                        self.unmapped.push(format!(
+                            "release '{}': installed: false mapped to no_deploy, uninstall it manually",
+                            release_name
+                        ));
+                    }
+                }
+                "hooks" => chart.hooks = self.hooks(&release_name, value),
+                "createNamespace" => match value.as_bool() {
+                    Some(true) if !self.create_namespace => {
+                        flags.push("--create-namespace".to_string())
+                    }
+                    Some(false) if self.create_namespace => self.unmapped.push(format!(
+                        "release '{}': createNamespace: false cannot override helmDefaults",
+                        release_name
+                    )),
+                    _ => {}
+                },
+                _ => match helm_flag(key, value) {
+                    Some(flag) => flags.extend(flag),
+                    None => self.unmapped.push(format!(
+                        "release '{}': '{}' is not supported",
+                        release_name, key
+                    )),
+                },
+            }
+        }
+
+        if !labels.is_empty() {
+            chart.labels = Some(labels);
+        }
+        if !values_files.is_empty() {
+            chart.values_files = Some(values_files);
+        }
+        if !values.is_empty() {
+            chart.values = Some(values);
+        }
+        if !flags.is_empty() {
+            chart.helm_args_append = Some(flags.join(" "));
+        }
+        self.charts.push(chart);
+        Ok(())
+    }
+
+    /// Sets the repository or local path of the chart from a helmfile chart reference.
+    fn resolve_chart(&mut self, chart: &mut Chart, chart_ref: &str, release_name: &str) {
+        if let Some(reference) = chart_ref.strip_prefix("oci://") {
+            let (registry, name) = reference.rsplit_once('/').unwrap_or(("", reference));
+            let url = format!("oci://{}", registry);
+            let repo_name = match self.repositories.iter().find(|r| r.url == url) {
+                Some(repo) => repo.name.clone(),
+                None => {
+                    let mut repo_name = registry.rsplit('/').next().unwrap_or(name).to_string();
+                    while self.repositories.iter().any(|r| r.name == repo_name) {
+                        repo_name.push_str("-oci");
+                    }
+                    self.repositories.push(Repository {
+                        name: repo_name.clone(),
+                        url,
+                        r#type: RepoType::Oci,
+                    });
+                    repo_name
+                }
+            };
+            chart.name = name.to_string();
+            chart.repo_name = Some(repo_name);
+            return;
+        }
+
+        if let Some((repo, name)) = chart_ref.split_once('/')
+            && self.repositories.iter().any(|r| r.name == repo)
+        {
+            chart.name = name.to_string();
+            chart.repo_name = Some(repo.to_string());
+            return;
+        }
+
+        let path = self.relocate(chart_ref);
+        if !self.helmfile_dir.join(chart_ref).exists() {
+            self.unmapped.push(format!(
+                "release '{}': chart '{}' is neither a known repository nor a local path",
+                release_name, chart_ref
+            ));
+        }
+        chart.name = Path::new(chart_ref)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(release_name)
+            .to_string();
+        chart.chart_path = Some(path);
+    }
+
+    /// Maps `presync`/`postsync` hooks to `pre_deploy`/`post_deploy`.
+    fn hooks(&mut self, release_name: &str, value: &Value) -> Option<Hooks> {
+        let mut hooks = Hooks::default();
+        for hook in value.as_sequence().into_iter().flatten() {
+            let Some(command) = str_field(hook, "command") else {
+                continue;
+            };
+            let mut line = vec![shell_quote(&command)];
+            line.extend(
+                hook.get("args")
+                    .and_then(Value::as_sequence)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(scalar_to_string)
+                    .map(|arg| shell_quote(&arg)),
+            );
+            let line = line.join(" ");
+
+            for event in hook
+                .get("events")
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                let phase = match event {
+                    "presync" => &mut hooks.pre_deploy,
+                    "postsync" => &mut hooks.post_deploy,
+                    other => {
+                        self.unmapped.push(format!(
+                            "release '{}': '{}' hook events are not supported",
+                            release_name, other
+                        ));
+                        continue;
+                    }
+                };
+                phase.get_or_insert_with(Vec::new).push(line.clone());
+            }
+        }
+        (hooks != Hooks::default()).then_some(hooks)
+    }
+
+    /// Path relative to the helmfile, rewritten relative to the generated configuration.
+    fn relocate(&self, path: &str) -> String {
+        if Path::new(path).is_absolute() {
+            return path.to_string();
+        }
+        let joined: PathBuf = self.helmfile_dir.join(path);
+        joined
+            .strip_prefix(self.config_dir)
+            .unwrap_or(&joined)
+            .display()
+            .to_string()
+    }
+}
+
+/// helm flag of a helmfile release option shared with `helmDefaults`.
+///
+/// Returns `None` for unknown options, and no flag for disabled ones.
+fn helm_flag(key: &str, value: &Value) -> Option<Vec<String>> {
+    let enabled = value.as_bool().unwrap_or(false);
+    let switch = |flag: &str| {
+        if enabled {
+            vec![flag.to_string()]
+        } else {
+            Vec::new()
+        }
+    };
+    let with_value = |flag: &str, suffix: &str| {
+        scalar_to_string(value)
+            .map(|v| vec![format!("{} {}{}", flag, v, suffix)])
+            .unwrap_or_default()
+    };
+    Some(match key {
+        "wait" => switch("--wait"),
+        "waitForJobs" => switch("--wait-for-jobs"),
+        "atomic" => switch("--rollback-on-failure"),
+        "force" => switch("--force"),
+        "cleanupOnFail" => switch("--cleanup-on-fail"),
+        "timeout" => with_value("--timeout", "s"),
+        "historyMax" => with_value("--history-max", ""),
+        "kubeContext" => with_value("--kube-context", ""),
+        _ => return None,
+    })
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(scalar_to_string)
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn string_map(value: &Value) -> BTreeMap<String, String> {
+    value
+        .as_mapping()
+        .into_iter()
+        .flatten()
+        .filter_map(|(k, v)| Some((scalar_to_string(k)?, scalar_to_string(v)?)))
+        .collect()
+}
+
+/// Splits a `--set` path on dots, `\.` escaping a dot.
+fn split_set_path(path: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    parts.last_mut().unwrap().push(next);
+                }
+            }
+            '.' => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+fn set_path(target: &mut Value, path: &[String], value: Value) {
+    let Some((first, rest)) = path.split_first() else {
+        *target = value;
+        return;
+    };
+    if !target.is_mapping() {
+        *target = Value::Mapping(Mapping::new());
+    }
+    let map = target.as_mapping_mut().expect("target is a mapping");
+    let child = map
+        .entry(Value::String(first.clone()))
+        .or_insert(Value::Null);
+    set_path(child, rest, value);
+}
+
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate_str(content: &str) -> Translation {
+        let documents = parse(content).unwrap();
+        translate(&documents, Path::new(""), Path::new("")).unwrap()
+    }
+
+    #[test]
+    fn test_translate_releases() {
+        let translation = translate_str(
+            r#"
+repositories:
+  - name: bitnami
+    url: https://charts.bitnami.com/bitnami
+  - name: ghcr
+    url: ghcr.io/acme/charts
+    oci: true
+helmDefaults:
+  wait: true
+  timeout: 600
+releases:
+  - name: db
+    namespace: data
+    chart: bitnami/postgresql
+    version: 15.0.0
+    values:
+      - values/db.yaml
+      - primary:
+          replicas: 2
+    secrets:
+      - secrets/db.yaml
+    set:
+      - name: auth.database
+        value: app
+      - name: podAnnotations.prometheus\.io/scrape
+        value: "true"
+  - name: api
+    namespace: app
+    chart: ghcr/api
+    version: 1.0.0
+    needs:
+      - data/db
+    labels:
+      tier: backend
+    atomic: true
+"#,
+        );
+        let config = translation.config;
+        assert_eq!(config.repositories[1].url, "oci://ghcr.io/acme/charts");
+        assert_eq!(config.repositories[1].r#type, RepoType::Oci);
+        assert_eq!(
+            config.vesshelm.unwrap().helm_args,
+            format!(
+                "{} --wait --timeout 600s --create-namespace",
+                BASE_HELM_ARGS
+            )
+        );
+
+        let db = &config.charts[0];
+        assert_eq!(db.name, "postgresql");
+        assert_eq!(db.release.as_deref(), Some("db"));
+        assert_eq!(db.repo_name.as_deref(), Some("bitnami"));
+        assert_eq!(
+            db.values_files,
+            Some(vec![
+                "values/db.yaml".to_string(),
+                "secrets/db.yaml".to_string()
+            ])
+        );
+        let values = db.values.as_ref().unwrap();
+        assert_eq!(
+            values[0],
+            serde_yaml_ng::from_str::<Value>("primary: {replicas: 2}").unwrap()
+        );
+        assert_eq!(
+            values[1],
+            serde_yaml_ng::from_str::<Value>(
+                "auth: {database: app}\npodAnnotations: {prometheus.io/scrape: 'true'}"
+            )
+            .unwrap()
+        );
+
+        let api = &config.charts[1];
+        assert_eq!(api.release, None);
+        assert_eq!(api.depends, Some(vec!["db".to_string()]));
+        assert_eq!(
+            api.helm_args_append.as_deref(),
+            Some("--rollback-on-failure")
+        );
+        assert_eq!(api.labels.as_ref().unwrap()["tier"], "backend");
+        assert!(
+            translation.unmapped.is_empty(),
+            "{:?}",
+            translation.unmapped
+        );
+    }
+
+    #[test]
+    fn test_translate_reports_unmapped_features() {
+        let translation = translate_str(
+            r#"
+environments:
+  default: {}
+repositories:
+  - name: private
+    url: https://charts.example.com
+    username: bot
+helmDefaults:
+  createNamespace: false
+  verify: true
+---
+releases:
+  - name: web
+    chart: oci://registry.example.com/team/web
+    version: 2.0.0
+    installed: false
+    condition: web.enabled
+    hooks:
+      - events: ["presync", "cleanup"]
+        command: kubectl
+        args: ["apply", "-f", "crds dir/"]
+"#,
+        );
+        let web = &translation.config.charts[0];
+        assert_eq!(web.name, "web");
+        assert_eq!(web.namespace, "default");
+        assert!(web.no_deploy);
+        assert_eq!(
+            web.hooks.as_ref().unwrap().pre_deploy,
+            Some(vec!["kubectl apply -f 'crds dir/'".to_string()])
+        );
+        let oci = &translation.config.repositories[1];
+        assert_eq!(oci.url, "oci://registry.example.com/team");
+        assert_eq!(web.repo_name.as_deref(), Some(oci.name.as_str()));
+        assert!(
+            !translation
+                .config
+                .vesshelm
+                .unwrap()
+                .helm_args
+                .contains("--create-namespace")
+        );
+
+        assert_eq!(
+            translation.unmapped,
+            vec![
+                "top-level 'environments' is not supported",
+                "repository 'private': 'username' is not supported",
+                "helmDefaults.verify is not supported",
+                "release 'web': no namespace, 'default' used",
+                "release 'web': installed: false mapped to no_deploy, uninstall it manually",
+                "release 'web': 'condition' is not supported",
+                "release 'web': 'cleanup' hook events are not supported",
+            ]
+        );
+    }
+}
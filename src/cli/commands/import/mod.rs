@@ -0,0 +1,75 @@
+use super::{ImportArgs, ImportCommands, ImportHelmfileArgs};
+use crate::clients::artifacthub::ArtifactHubClient;
+use crate::clients::helm::RealHelmClient;
+use crate::util::interaction::TerminalInteraction;
+use crate::util::state::state_dir;
+use anyhow::{Context, Result, bail};
+use colored::*;
+use console::style;
+use std::path::Path;
+
+mod helmfile;
+mod releases;
+
+pub async fn run(args: ImportArgs, config_path: &Path) -> Result<()> {
+    match args.command {
+        ImportCommands::Helmfile(args) => import_helmfile(args, config_path),
+        ImportCommands::Releases(args) => {
+            let helm_client = RealHelmClient::with_state_dir(&state_dir(config_path));
+            releases::import_releases(
+                args,
+                config_path,
+                &helm_client,
+                &ArtifactHubClient::new(),
+                &TerminalInteraction,
+            )
+            .await
+        }
+    }
+}
+
+fn import_helmfile(args: ImportHelmfileArgs, config_path: &Path) -> Result<()> {
+    println!(
+        "{} Importing {}...",
+        style("==>").bold().green(),
+        args.path.display()
+    );
+
+    if config_path.exists() && !args.force {
+        bail!(
+            "Configuration file {:?} already exists, use --force to overwrite it",
+            config_path
+        );
+    }
+
+    let content = std::fs::read_to_string(&args.path)
+        .with_context(|| format!("Failed to read {}", args.path.display()))?;
+    let documents = helmfile::parse(&content)?;
+    let helmfile_dir = args.path.parent().unwrap_or(Path::new(""));
+    let config_dir = config_path.parent().unwrap_or(Path::new(""));
+    let translation = helmfile::translate(&documents, helmfile_dir, config_dir)?;
+
+    let yaml = serde_yaml_ng::to_string(&translation.config)
+        .context("Failed to serialize configuration")?;
+    std::fs::write(config_path, yaml)
+        .with_context(|| format!("Failed to write {:?}", config_path))?;
+
+    println!(
+        " {} {} repositories and {} charts written to {}",
+        style("[OK]").green(),
+        translation.config.repositories.len(),
+        translation.config.charts.len(),
+        config_path.display()
+    );
+
+    if !translation.unmapped.is_empty() {
+        println!(
+            "\n{} The following helmfile features were not mapped:",
+            "⚠️ ".yellow()
+        );
+        for item in &translation.unmapped {
+            println!("  - {}", item);
+        }
+    }
+    Ok(())
+}
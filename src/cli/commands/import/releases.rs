@@ -0,0 +1,495 @@
+//! Adoption of the helm releases of a cluster into `vesshelm.yaml`.
+
+use super::super::ImportReleasesArgs;
+use crate::clients::artifacthub::ArtifactHubClient;
+use crate::clients::helm::{HelmClient, HelmRelease};
+use crate::config::{Config, RepoType, Repository};
+use crate::util::config_updater::{ChartConfig, ConfigUpdater};
+use crate::util::interaction::UserInteraction;
+use anyhow::{Context, Result};
+use colored::*;
+use console::style;
+use std::path::{Path, PathBuf};
+
+/// Repository a release chart is fetched from.
+struct ChartSource {
+    repo_name: String,
+    /// Set when the repository is not configured yet.
+    new_repo: Option<Repository>,
+}
+
+pub async fn import_releases(
+    args: ImportReleasesArgs,
+    config_path: &Path,
+    helm_client: &impl HelmClient,
+    artifact_hub: &ArtifactHubClient,
+    interaction: &impl UserInteraction,
+) -> Result<()> {
+    println!("{} Listing helm releases...", style("==>").bold().green());
+
+    let config = Config::load_from_path(config_path)?;
+    let releases: Vec<HelmRelease> = helm_client
+        .list_releases()?
+        .into_iter()
+        .filter(HelmRelease::is_installed)
+        .filter(|r| args.all_namespaces || args.namespace.as_deref() == Some(r.namespace.as_str()))
+        .filter(|r| {
+            !config
+                .charts
+                .iter()
+                .any(|c| c.release_name() == r.name && c.namespace == r.namespace)
+        })
+        .collect();
+
+    if releases.is_empty() {
+        println!("No release missing from vesshelm.yaml.");
+        return Ok(());
+    }
+
+    // Chart lookups go through `helm search repo`, which needs the repositories registered
+    let helm_repos: Vec<String> = config
+        .repositories
+        .iter()
+        .filter(|r| r.r#type == RepoType::Helm)
+        .map(|r| {
+            helm_client.repo_add(&r.name, &r.url)?;
+            Ok(r.name.clone())
+        })
+        .collect::<Result<_>>()?;
+    helm_client.repo_update(&helm_repos)?;
+
+    let mut repositories: Vec<(String, String)> = config
+        .repositories
+        .iter()
+        .map(|r| (r.name.clone(), r.url.clone()))
+        .collect();
+    let config_dir = config_path.parent().unwrap_or(Path::new(""));
+    let mut imported = 0;
+    let mut skipped = 0;
+    let mut values_written = false;
+
+    for release in &releases {
+        let (Some(chart), Some(version)) = (release.chart_name(), release.chart_version()) else {
+            println!(
+                " {} {}/{}: cannot read the chart of '{}'",
+                "⏭ ".yellow(),
+                release.namespace,
+                release.name,
+                release.chart
+            );
+            skipped += 1;
+            continue;
+        };
+        println!(
+            "\n{} {}/{} (chart {} {})",
+            style("==>").bold().cyan(),
+            release.namespace,
+            style(&release.name).bold(),
+            chart,
+            version
+        );
+
+        let source =
+            match find_in_repositories(helm_client, &helm_repos, chart, version, interaction)? {
+                Some(source) => Some(source),
+                None => {
+                    look_up_artifact_hub(artifact_hub, chart, &repositories, interaction).await?
+                }
+            };
+        let Some(source) = source else {
+            println!(" {} Skipped {}", "⏭ ".yellow(), release.name);
+            skipped += 1;
+            continue;
+        };
+
+        let values = helm_client.get_values(&release.name, &release.namespace)?;
+        let has_values = values.as_mapping().is_some_and(|m| !m.is_empty());
+        let values_file = PathBuf::from("values")
+            .join(&release.namespace)
+            .join(format!("{}.yaml", release.name));
+
+        println!("\n{}", style("Summary:").bold());
+        match &source.new_repo {
+            Some(repo) => println!(
+                " {:<7} Repository: {} {}",
+                style("[NEW]").green(),
+                style(&repo.name).bold(),
+                style(format!("({})", repo.url)).dim()
+            ),
+            None => println!(
+                " {:<7} Repository: {}",
+                style("[OK]").green(),
+                style(&source.repo_name).bold()
+            ),
+        }
+        println!(
+            " {:<7} Chart: {}",
+            style("[NEW]").green(),
+            style(chart).bold()
+        );
+        println!("         Release: {}", release.name);
+        println!("         Version: {}", version);
+        println!("         Namespace: {}", release.namespace);
+        if has_values {
+            println!("         Values: {}", values_file.display());
+        }
+
+        if !interaction.confirm(&format!("Import release {}?", release.name), true)? {
+            println!(" {} Skipped {}", "⏭ ".yellow(), release.name);
+            skipped += 1;
+            continue;
+        }
+
+        if has_values {
+            let path = config_dir.join(&values_file);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            std::fs::write(&path, serde_yaml_ng::to_string(&values)?)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            values_written = true;
+        }
+
+        if let Some(repo) = &source.new_repo {
+            repositories.push((repo.name.clone(), repo.url.clone()));
+        }
+        ConfigUpdater::update(
+            config_path,
+            source.new_repo,
+            ChartConfig {
+                name: chart.to_string(),
+                release: (chart != release.name).then(|| release.name.clone()),
+                repo_name: source.repo_name,
+                namespace: release.namespace.clone(),
+                version: Some(version.to_string()),
+                chart_path: None,
+                comment: None,
+                values_files: has_values.then(|| vec![values_file.display().to_string()]),
+            },
+        )?;
+        println!(" {} Imported {}", style("[OK]").green(), release.name);
+        imported += 1;
+    }
+
+    println!("\n\n{}", style("Summary:").bold());
+    println!("  Imported: {}", style(imported).green());
+    println!("  Skipped:  {}", style(skipped).dim());
+    if values_written {
+        println!(
+            "\n{} Values files are written in clear text, move their secrets to a SOPS-encrypted file.",
+            "⚠️ ".yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Finds the configured helm repositories providing `chart` at `version`.
+fn find_in_repositories(
+    helm_client: &impl HelmClient,
+    helm_repos: &[String],
+    chart: &str,
+    version: &str,
+    interaction: &impl UserInteraction,
+) -> Result<Option<ChartSource>> {
+    if helm_repos.is_empty() {
+        return Ok(None);
+    }
+    let mut candidates: Vec<String> = helm_client
+        .search_repo(chart)?
+        .into_iter()
+        .filter(|result| result.version == version)
+        .filter_map(|result| {
+            let (repo, name) = result.name.split_once('/')?;
+            (name == chart && helm_repos.iter().any(|r| r == repo)).then(|| repo.to_string())
+        })
+        .collect();
+    candidates.dedup();
+
+    let repo_name = match candidates.len() {
+        0 => return Ok(None),
+        1 => candidates.remove(0),
+        _ => {
+            let selection =
+                interaction.select("Select the repository of the chart", &candidates, 0)?;
+            candidates.swap_remove(selection)
+        }
+    };
+    Ok(Some(ChartSource {
+        repo_name,
+        new_repo: None,
+    }))
+}
+
+/// Offers the Artifact Hub repositories publishing `chart`.
+async fn look_up_artifact_hub(
+    artifact_hub: &ArtifactHubClient,
+    chart: &str,
+    repositories: &[(String, String)],
+    interaction: &impl UserInteraction,
+) -> Result<Option<ChartSource>> {
+    if !interaction.confirm(
+        &format!(
+            "Chart {} not found in the configured repositories. Look it up on Artifact Hub?",
+            chart
+        ),
+        true,
+    )? {
+        return Ok(None);
+    }
+
+    let packages: Vec<_> = artifact_hub
+        .search_packages(chart)
+        .await?
+        .into_iter()
+        .filter(|p| p.name == chart)
+        .collect();
+    if packages.is_empty() {
+        println!("Chart {} not found on Artifact Hub.", chart);
+        return Ok(None);
+    }
+
+    let mut items: Vec<String> = packages
+        .iter()
+        .map(|p| format!("{} ({})", p.repository.name, p.repository.url))
+        .collect();
+    items.push("Skip".to_string());
+    let selection = interaction.select("Select the repository of the chart", &items, 0)?;
+    let Some(package) = packages.get(selection) else {
+        return Ok(None);
+    };
+
+    let url = &package.repository.url;
+    if let Some((name, _)) = repositories
+        .iter()
+        .find(|(_, u)| u.trim_end_matches('/') == url.trim_end_matches('/'))
+    {
+        return Ok(Some(ChartSource {
+            repo_name: name.clone(),
+            new_repo: None,
+        }));
+    }
+
+    let name = loop {
+        let name = interaction.input("New Repository Name", Some(&package.repository.name))?;
+        if repositories.iter().any(|(n, _)| *n == name) {
+            println!("Repository name already exists. Please choose another.");
+            continue;
+        }
+        break name;
+    };
+    Ok(Some(ChartSource {
+        repo_name: name.clone(),
+        new_repo: Some(Repository {
+            name,
+            url: url.clone(),
+            r#type: RepoType::Helm,
+        }),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::helm::{ChartSearchResult, MockHelmClient};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockInteraction {
+        selects: Mutex<Vec<usize>>,
+        confirms: Mutex<Vec<bool>>,
+    }
+
+    impl UserInteraction for MockInteraction {
+        fn confirm(&self, _prompt: &str, _default: bool) -> Result<bool> {
+            let mut confirms = self.confirms.lock().unwrap();
+            Ok(if confirms.is_empty() {
+                true
+            } else {
+                confirms.remove(0)
+            })
+        }
+
+        fn input(&self, _prompt: &str, default: Option<&str>) -> Result<String> {
+            Ok(default.unwrap_or_default().to_string())
+        }
+
+        fn select(&self, _prompt: &str, _items: &[String], _default: usize) -> Result<usize> {
+            let mut selects = self.selects.lock().unwrap();
+            Ok(if selects.is_empty() {
+                0
+            } else {
+                selects.remove(0)
+            })
+        }
+
+        fn fuzzy_select(&self, prompt: &str, items: &[String], default: usize) -> Result<usize> {
+            self.select(prompt, items, default)
+        }
+    }
+
+    fn release(name: &str, namespace: &str, chart: &str) -> HelmRelease {
+        HelmRelease {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            revision: "1".to_string(),
+            status: "deployed".to_string(),
+            chart: chart.to_string(),
+            app_version: String::new(),
+        }
+    }
+
+    fn setup() -> (tempfile::TempDir, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let config_path = temp.path().join("vesshelm.yaml");
+        std::fs::write(
+            &config_path,
+            r#"repositories:
+  - name: bitnami
+    url: https://charts.bitnami.com/bitnami
+charts:
+  - name: nginx
+    repo_name: bitnami
+    version: 15.0.0
+    namespace: web
+destinations:
+  - name: default
+    path: ./charts
+"#,
+        )
+        .unwrap();
+        (temp, config_path)
+    }
+
+    fn mock_client() -> MockHelmClient {
+        let mut client = MockHelmClient::new();
+        client.expect_list_releases().returning(|| {
+            Ok(vec![
+                release("nginx", "web", "nginx-15.0.0"),
+                release("cache", "data", "redis-19.0.0"),
+                release("api", "app", "api-1.0.0"),
+            ])
+        });
+        client.expect_repo_add().returning(|_, _| Ok(()));
+        client.expect_repo_update().returning(|_| Ok(()));
+        client.expect_search_repo().returning(|keyword| {
+            Ok(vec![
+                ChartSearchResult {
+                    name: format!("bitnami/{}", keyword),
+                    version: "19.0.0".to_string(),
+                },
+                ChartSearchResult {
+                    name: format!("bitnami/{}-cluster", keyword),
+                    version: "19.0.0".to_string(),
+                },
+            ])
+        });
+        client
+    }
+
+    #[tokio::test]
+    async fn test_import_release_from_configured_repository() {
+        let (_temp, config_path) = setup();
+        let mut client = mock_client();
+        client
+            .expect_get_values()
+            .withf(|name, namespace| name == "cache" && namespace == "data")
+            .returning(|_, _| Ok(serde_yaml_ng::from_str("auth:\n  enabled: false\n").unwrap()));
+        let args = ImportReleasesArgs {
+            namespace: Some("data".to_string()),
+            all_namespaces: false,
+        };
+
+        import_releases(
+            args,
+            &config_path,
+            &client,
+            &ArtifactHubClient::new(),
+            &MockInteraction::default(),
+        )
+        .await
+        .unwrap();
+
+        // Values files are validated relative to the working directory, so read the raw config
+        let config: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        let charts = config["charts"].as_sequence().unwrap();
+        assert_eq!(charts.len(), 2);
+        let chart = charts
+            .iter()
+            .find(|c| c["name"].as_str() == Some("redis"))
+            .unwrap();
+        assert_eq!(chart["release"].as_str(), Some("cache"));
+        assert_eq!(chart["repo_name"].as_str(), Some("bitnami"));
+        assert_eq!(chart["version"].as_str(), Some("19.0.0"));
+        assert_eq!(chart["namespace"].as_str(), Some("data"));
+        assert_eq!(
+            chart["values_files"][0].as_str(),
+            Some("values/data/cache.yaml")
+        );
+        let values =
+            std::fs::read_to_string(config_path.parent().unwrap().join("values/data/cache.yaml"))
+                .unwrap();
+        assert_eq!(values, "auth:\n  enabled: false\n");
+    }
+
+    #[tokio::test]
+    async fn test_import_releases_skips_declined_and_unknown_charts() {
+        let (_temp, config_path) = setup();
+        let before = std::fs::read_to_string(&config_path).unwrap();
+        let mut client = mock_client();
+        client
+            .expect_get_values()
+            .returning(|_, _| Ok(serde_yaml_ng::Value::Null));
+        let interaction = MockInteraction::default();
+        // Decline the import of `cache`, then the Artifact Hub lookup of `api`
+        interaction.confirms.lock().unwrap().extend([false, false]);
+        let args = ImportReleasesArgs {
+            namespace: None,
+            all_namespaces: true,
+        };
+
+        import_releases(
+            args,
+            &config_path,
+            &client,
+            &ArtifactHubClient::new(),
+            &interaction,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), before);
+    }
+
+    #[tokio::test]
+    async fn test_import_releases_skips_removed_releases() {
+        let (_temp, config_path) = setup();
+        let before = std::fs::read_to_string(&config_path).unwrap();
+        let mut client = MockHelmClient::new();
+        client.expect_list_releases().returning(|| {
+            let mut removed = release("cache", "data", "redis-19.0.0");
+            removed.status = "uninstalled".to_string();
+            let mut replaced = release("queue", "data", "redis-19.0.0");
+            replaced.status = "superseded".to_string();
+            Ok(vec![removed, replaced])
+        });
+        let args = ImportReleasesArgs {
+            namespace: None,
+            all_namespaces: true,
+        };
+
+        // Neither the repositories nor the values of the releases are fetched
+        import_releases(
+            args,
+            &config_path,
+            &client,
+            &ArtifactHubClient::new(),
+            &MockInteraction::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), before);
+    }
+}
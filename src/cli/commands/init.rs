@@ -1,4 +1,5 @@
 use crate::clients::helm::{HelmClient, RealHelmClient};
+use crate::config::{Config, DiffEngine};
 use anyhow::{Context, Result};
 use console::style;
 use std::fs;
@@ -14,9 +15,15 @@ pub async fn run(config_path: &Path) -> Result<()> {
         );
     }
 
-    // Check for helm-diff plugin
+    // Check for helm-diff plugin (not needed by the native diff engine)
     let helm_client = RealHelmClient::new();
-    if !helm_client.is_plugin_installed("diff")? {
+    let native_diff = Config::load_from_path(config_path)
+        .ok()
+        .and_then(|config| config.vesshelm)
+        .is_some_and(|vesshelm| vesshelm.diff_engine == DiffEngine::Native);
+    if native_diff {
+        println!("Native diff engine configured, skipping helm-diff plugin.");
+    } else if !helm_client.is_plugin_installed("diff")? {
         println!("Installing helm-diff plugin...");
         helm_client.install_plugin("diff", "https://github.com/databus23/helm-diff", false)?;
         println!("Successfully installed helm-diff plugin.");
@@ -37,6 +44,7 @@ destinations:
 vesshelm:
   helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }} --wait --rollback-on-failure --create-namespace"
   diff_enabled: true
+  diff_engine: plugin
   diff_args: "diff upgrade --suppress-secrets --allow-unreleased {{ name }} {{ destination }} -n {{ namespace }}"
 
 variables_files: []
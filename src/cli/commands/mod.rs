@@ -1,15 +1,27 @@
 pub mod add;
+pub mod bundle;
 pub mod check_updates;
 pub mod completion;
 pub mod delete;
 pub mod deploy;
+pub mod diff;
+pub mod export;
 pub mod graph;
+pub mod history;
+pub mod images;
+pub mod import;
 pub mod init;
+pub mod rollback;
+pub mod status;
 pub mod sync;
+pub mod template;
 pub mod uninstall;
 pub mod validate;
+pub mod values;
 
+use crate::util::filter::Selector;
 use clap::{Args, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
@@ -18,9 +30,11 @@ pub enum Commands {
     /// Sync charts defined in vesshelm.yaml
     Sync(SyncArgs),
     /// Validate the configuration file
-    Validate,
+    Validate(ValidateArgs),
     /// Deploy charts using local helm binary
     Deploy(DeployArgs),
+    /// Show pending changes without deploying (exits with 2 when changes are detected)
+    Diff(DiffArgs),
     /// Show the dependency graph of charts
     Graph,
     /// Uninstall a chart release
@@ -35,6 +49,32 @@ pub enum Commands {
     Add,
     /// Delete a chart from configuration and filesystem
     Delete(DeleteArgs),
+    /// Roll back a chart release to a previous revision
+    Rollback(RollbackArgs),
+    /// Compare configured charts with the releases deployed in the cluster
+    Status(StatusArgs),
+    /// Show the local deployment audit log
+    History(HistoryArgs),
+    /// Show the merged values of a chart, annotated with their source file
+    Values(ValuesArgs),
+    /// Render the manifests of the charts to disk with helm template
+    Template(TemplateArgs),
+    /// Export the charts as Argo CD Applications or Flux HelmReleases
+    Export(ExportArgs),
+    /// Import charts from other tools into vesshelm.yaml
+    Import(ImportArgs),
+    /// List the container images each release runs
+    Images(ImagesArgs),
+    /// Pack the project for a disconnected site, or unpack such a bundle
+    Bundle(BundleArgs),
+}
+
+/// Output format for commands producing reports
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -50,9 +90,21 @@ pub struct DeployArgs {
     #[clap(long)]
     pub dry_run: bool,
 
-    /// Only deploy the specified charts
+    /// Only deploy the specified charts (glob patterns allowed)
     pub charts: Option<Vec<String>>,
 
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
+
+    /// Also select the charts the selected charts depend on (transitively)
+    #[clap(long)]
+    pub with_dependencies: bool,
+
+    /// Also select the charts depending on the selected charts (transitively)
+    #[clap(long)]
+    pub with_dependents: bool,
+
     /// Skip interactive confirmation
     #[clap(long)]
     pub no_interactive: bool,
@@ -64,13 +116,89 @@ pub struct DeployArgs {
     /// Force deployment even if no changes are detected
     #[clap(long, short = 'f', conflicts_with = "dry_run")]
     pub force: bool,
+
+    /// Resume the last failed run from the failed chart, with the same charts
+    #[clap(long, conflicts_with_all = [
+        "charts",
+        "selector",
+        "with_dependencies",
+        "with_dependents",
+        "from",
+        "until",
+        "dry_run"
+    ])]
+    pub resume: bool,
+
+    /// Start the deployment order at this chart
+    #[clap(long)]
+    pub from: Option<String>,
+
+    /// Stop the deployment order after this chart
+    #[clap(long)]
+    pub until: Option<String>,
+
+    /// Run `helm test` after each successful upgrade
+    #[clap(long, conflicts_with = "dry_run")]
+    pub test: bool,
+
+    /// Compute all diffs first, confirm once, then deploy unattended
+    #[clap(long, conflicts_with = "dry_run")]
+    pub review_first: bool,
+
+    #[clap(flatten)]
+    pub diff_view: DiffViewArgs,
+}
+
+#[derive(Args, Clone, Debug, Default)]
+pub struct DiffViewArgs {
+    /// Print a per-resource summary of the diff instead of the raw helm diff
+    #[clap(long)]
+    pub summary: bool,
+
+    /// Print the full diff after the summary (Secret and ConfigMap data stay hidden)
+    #[clap(long, requires = "summary")]
+    pub full_diff: bool,
+}
+
+impl From<&DiffViewArgs> for crate::util::diff::DiffView {
+    fn from(args: &DiffViewArgs) -> Self {
+        Self {
+            summary: args.summary,
+            full_diff: args.full_diff,
+        }
+    }
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct DiffArgs {
+    /// Only diff the specified charts (glob patterns allowed)
+    pub charts: Option<Vec<String>>,
+
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
+
+    #[clap(flatten)]
+    pub diff_view: DiffViewArgs,
 }
 
 #[derive(Args, Clone, Debug)]
 pub struct SyncArgs {
-    /// Only sync the specified charts
+    /// Only sync the specified charts (glob patterns allowed)
     pub charts: Option<Vec<String>>,
 
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
+
+    /// Also select the charts the selected charts depend on (transitively)
+    #[clap(long)]
+    pub with_dependencies: bool,
+
+    /// Also select the charts depending on the selected charts (transitively)
+    #[clap(long)]
+    pub with_dependents: bool,
+
     /// Force sync (ignore skip conditions)
     #[clap(long)]
     pub ignore_skip: bool,
@@ -78,9 +206,13 @@ pub struct SyncArgs {
 
 #[derive(Args, Clone, Debug)]
 pub struct UninstallArgs {
-    /// The name of the chart to uninstall
+    /// The name (or glob pattern) of the chart to uninstall
     pub name: Option<String>,
 
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
+
     /// Skip interactive confirmation
     #[clap(long)]
     pub no_interactive: bool,
@@ -96,8 +228,12 @@ pub struct CheckUpdatesArgs {
     #[clap(long)]
     pub apply_sync: bool,
 
-    /// Only check specified charts
+    /// Only check specified charts (glob patterns allowed)
     pub charts: Option<Vec<String>>,
+
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -109,3 +245,203 @@ pub struct DeleteArgs {
     #[clap(long)]
     pub no_interactive: bool,
 }
+
+#[derive(Args, Clone, Debug)]
+pub struct RollbackArgs {
+    /// The name of the chart to roll back
+    pub name: Option<String>,
+
+    /// Revision to roll back to (defaults to the previous revision)
+    #[clap(long)]
+    pub to_revision: Option<u32>,
+
+    /// Also roll back the charts depending on this chart, in reverse dependency order
+    #[clap(long)]
+    pub with_dependents: bool,
+
+    /// Skip interactive confirmation
+    #[clap(long)]
+    pub no_interactive: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct StatusArgs {
+    /// Only report the specified charts
+    pub charts: Option<Vec<String>>,
+
+    /// Output format
+    #[clap(long, short = 'o', value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct HistoryArgs {
+    /// Only show deployments of this chart
+    pub chart: Option<String>,
+
+    /// Maximum number of records to show (most recent ones)
+    #[clap(long, short = 'n', default_value_t = 20)]
+    pub limit: usize,
+
+    /// Output format
+    #[clap(long, short = 'o', value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ValidateArgs {
+    /// Also report values keys the chart's default values.yaml does not define
+    #[clap(long)]
+    pub values: bool,
+
+    /// Also render every chart with helm template and check the policies
+    #[clap(long)]
+    pub render: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ValuesArgs {
+    /// Chart (release name) to render the values of
+    pub chart: String,
+
+    /// Print values coming from secrets instead of masking them
+    #[clap(long)]
+    pub show_secrets: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct TemplateArgs {
+    /// Only render the specified charts (glob patterns allowed)
+    pub charts: Option<Vec<String>>,
+
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
+
+    /// Directory receiving one sub-directory of manifests per release
+    #[clap(long)]
+    pub output_dir: PathBuf,
+}
+
+/// GitOps tool targeted by `export`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Argo CD `Application` per chart
+    Argocd,
+    /// Flux `HelmRelease` per chart, with its `HelmRepository`/`GitRepository` sources
+    Flux,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ExportArgs {
+    /// Only export the specified charts (glob patterns allowed)
+    pub charts: Option<Vec<String>>,
+
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
+
+    /// GitOps tool to export to
+    #[clap(long, value_enum)]
+    pub format: ExportFormat,
+
+    /// Directory receiving one manifest per chart
+    #[clap(long)]
+    pub output_dir: PathBuf,
+
+    /// Namespace of the generated resources (default: `argocd` or `flux-system`)
+    #[clap(long)]
+    pub namespace: Option<String>,
+}
+
+/// Output format of `images`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImagesFormat {
+    /// Images grouped by chart
+    #[default]
+    Text,
+    /// Array of `{chart, namespace, images}` objects
+    Json,
+    /// Shell script copying every image to `$REGISTRY` with skopeo
+    Mirror,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ImagesArgs {
+    /// Only list the images of the specified charts (glob patterns allowed)
+    pub charts: Option<Vec<String>>,
+
+    /// Only select charts matching this label selector (e.g. `tier=infra,env!=prod`)
+    #[clap(long, short = 'l')]
+    pub selector: Option<Selector>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value_t)]
+    pub format: ImagesFormat,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ImportArgs {
+    #[command(subcommand)]
+    pub command: ImportCommands,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ImportCommands {
+    /// Translate a helmfile.yaml into vesshelm.yaml
+    Helmfile(ImportHelmfileArgs),
+    /// Add the helm releases deployed in the cluster to vesshelm.yaml
+    Releases(ImportReleasesArgs),
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ImportHelmfileArgs {
+    /// Path of the helmfile to import
+    pub path: PathBuf,
+
+    /// Overwrite an existing vesshelm.yaml
+    #[clap(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct BundleArgs {
+    #[command(subcommand)]
+    pub command: BundleCommands,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum BundleCommands {
+    /// Pack the configuration, lockfile, synced charts and values into a tarball
+    Create(BundleCreateArgs),
+    /// Unpack a bundle after verifying its digests
+    Import(BundleImportArgs),
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct BundleCreateArgs {
+    /// Path of the bundle to write (e.g. `bundle.tar.gz`)
+    pub output: PathBuf,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct BundleImportArgs {
+    /// Path of the bundle to unpack
+    pub path: PathBuf,
+
+    /// Overwrite an existing vesshelm.yaml
+    #[clap(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+#[group(required = true, multiple = false)]
+pub struct ImportReleasesArgs {
+    /// Only import the releases of this namespace
+    #[clap(long, short = 'n')]
+    pub namespace: Option<String>,
+
+    /// Import the releases of every namespace
+    #[clap(long, short = 'A')]
+    pub all_namespaces: bool,
+}
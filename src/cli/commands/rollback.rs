@@ -0,0 +1,481 @@
+use super::RollbackArgs;
+use crate::clients::helm::{HelmClient, RealHelmClient, ReleaseRevision};
+use crate::config::{Chart, Config};
+use crate::util::dag;
+use crate::util::interaction::UserInteraction;
+use anyhow::{Context, Result, anyhow};
+use console::style;
+use std::path::Path;
+
+pub async fn run(
+    args: RollbackArgs,
+    config_path: &Path,
+    interaction: &impl UserInteraction,
+) -> Result<()> {
+    let client = RealHelmClient::new();
+    rollback(args, config_path, interaction, &client)
+}
+
+/// A release to roll back, with the revision to return to (`None` means the previous one).
+struct RollbackStep<'a> {
+    chart: &'a Chart,
+    revision: Option<u32>,
+}
+
+fn rollback(
+    args: RollbackArgs,
+    config_path: &Path,
+    interaction: &impl UserInteraction,
+    client: &impl HelmClient,
+) -> Result<()> {
+    println!("{} Rolling back chart...\n", style("==>").bold().green());
+
+    let config = Config::load_from_path(config_path)?;
+
+    // Select Chart
+    let chart = if let Some(name) = &args.name {
+        config
+            .charts
+            .iter()
+            .find(|c| c.release_name() == name)
+            .ok_or_else(|| anyhow!("Chart '{}' not found in vesshelm.yaml", name))?
+    } else {
+        if config.charts.is_empty() {
+            println!("No charts found in configuration.");
+            return Ok(());
+        }
+
+        let mut sorted_charts: Vec<_> = config.charts.iter().collect();
+        sorted_charts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let items: Vec<String> = sorted_charts
+            .iter()
+            .map(|c| format!("{} ({})", c.release_name(), c.namespace))
+            .collect();
+
+        let selection = interaction
+            .fuzzy_select("Select chart to roll back", &items, 0)
+            .context("Failed to read selection")?;
+
+        sorted_charts[selection]
+    };
+
+    // Show release history
+    let history = client
+        .history(chart.release_name(), &chart.namespace)
+        .with_context(|| {
+            format!(
+                "Failed to read history of release '{}'",
+                chart.release_name()
+            )
+        })?;
+    if history.is_empty() {
+        anyhow::bail!(
+            "Release '{}' has no history in namespace '{}'",
+            chart.release_name(),
+            chart.namespace
+        );
+    }
+    print_history(chart, &history);
+
+    let revision = select_revision(&args, &history, interaction)?;
+
+    // Dependents are rolled back first, in reverse deployment order
+    let mut steps = Vec::new();
+    if args.with_dependents {
+        for dependent in collect_dependents(&config.charts, chart.release_name())? {
+            steps.push(RollbackStep {
+                chart: dependent,
+                revision: None,
+            });
+        }
+    }
+    steps.push(RollbackStep {
+        chart,
+        revision: Some(revision),
+    });
+
+    println!("\n{}", style("Rollback plan:").bold());
+    for step in &steps {
+        let target = match step.revision {
+            Some(revision) => format!("revision {}", revision),
+            None => "previous revision".to_string(),
+        };
+        println!(
+            "  - {} ({}) -> {}",
+            style(step.chart.release_name()).bold(),
+            step.chart.namespace,
+            style(target).cyan()
+        );
+    }
+    println!();
+
+    let confirmation = if args.no_interactive {
+        true
+    } else {
+        interaction
+            .confirm("Do you want to continue?", false)
+            .context("Failed to read user confirmation")?
+    };
+
+    if !confirmation {
+        println!(" {} Rollback aborted.", style("[ABORT]").dim());
+        return Ok(());
+    }
+
+    for step in &steps {
+        match client.rollback(
+            step.chart.release_name(),
+            &step.chart.namespace,
+            step.revision,
+        ) {
+            Ok(_) => println!(
+                " {} Rolled back {}.",
+                style("[OK]").green(),
+                style(step.chart.release_name()).bold()
+            ),
+            Err(e) => {
+                eprintln!(
+                    " {} Failed to roll back {}: {}",
+                    style("[FAIL]").red(),
+                    step.chart.release_name(),
+                    e
+                );
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_history(chart: &Chart, history: &[ReleaseRevision]) {
+    println!(
+        "History of {} ({}):",
+        style(chart.release_name()).bold(),
+        chart.namespace
+    );
+    println!(
+        "  {:<10} {:<32} {:<16} {:<30} DESCRIPTION",
+        "REVISION", "UPDATED", "STATUS", "CHART"
+    );
+    for entry in history {
+        println!(
+            "  {:<10} {:<32} {:<16} {:<30} {}",
+            entry.revision, entry.updated, entry.status, entry.chart, entry.description
+        );
+    }
+}
+
+/// Resolves the revision to roll back to from `--to-revision`, an interactive selection,
+/// or the revision preceding the current one.
+fn select_revision(
+    args: &RollbackArgs,
+    history: &[ReleaseRevision],
+    interaction: &impl UserInteraction,
+) -> Result<u32> {
+    if let Some(revision) = args.to_revision {
+        if !history.iter().any(|h| h.revision == revision) {
+            anyhow::bail!("Revision {} not found in release history", revision);
+        }
+        return Ok(revision);
+    }
+
+    let current = history.iter().map(|h| h.revision).max().unwrap_or(0);
+    let mut candidates: Vec<&ReleaseRevision> =
+        history.iter().filter(|h| h.revision != current).collect();
+    candidates.sort_by(|a, b| b.revision.cmp(&a.revision));
+
+    if candidates.is_empty() {
+        anyhow::bail!("No previous revision to roll back to");
+    }
+
+    if args.no_interactive {
+        return Ok(candidates[0].revision);
+    }
+
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|h| format!("{} - {} ({}, {})", h.revision, h.chart, h.status, h.updated))
+        .collect();
+    let selection = interaction
+        .select("Select revision to roll back to", &items, 0)
+        .context("Failed to read selection")?;
+
+    Ok(candidates[selection].revision)
+}
+
+/// Collects the charts depending (directly or transitively) on `target`, ordered so that
+/// the most dependent charts come first (reverse deployment order).
+fn collect_dependents<'a>(charts: &'a [Chart], target: &str) -> Result<Vec<&'a Chart>> {
+    let mut found: Vec<&str> = Vec::new();
+    let mut queue = vec![target.to_string()];
+
+    while let Some(current) = queue.pop() {
+        for dependent in dag::get_dependents(charts, &current)? {
+            if !found.contains(&dependent.name.as_str()) {
+                found.push(&dependent.name);
+                queue.push(dependent.name.clone());
+            }
+        }
+    }
+
+    let mut ordered: Vec<&Chart> = dag::sort_charts(charts)?
+        .into_iter()
+        .filter(|c| found.contains(&c.release_name()))
+        .collect();
+    ordered.reverse();
+    Ok(ordered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::helm::MockHelmClient;
+    use mockall::Sequence;
+    use mockall::predicate::*;
+    use std::sync::Mutex;
+
+    /// Replays canned answers; panics if the command prompts more than expected.
+    #[derive(Default)]
+    struct MockInteraction {
+        selects: Mutex<Vec<usize>>,
+        confirms: Mutex<Vec<bool>>,
+    }
+
+    impl MockInteraction {
+        fn new(selects: Vec<usize>, confirms: Vec<bool>) -> Self {
+            Self {
+                selects: Mutex::new(selects),
+                confirms: Mutex::new(confirms),
+            }
+        }
+    }
+
+    impl UserInteraction for MockInteraction {
+        fn confirm(&self, _prompt: &str, _default: bool) -> Result<bool> {
+            Ok(self.confirms.lock().unwrap().remove(0))
+        }
+
+        fn input(&self, _prompt: &str, _default: Option<&str>) -> Result<String> {
+            unreachable!("rollback never asks for free input")
+        }
+
+        fn select(&self, _prompt: &str, _items: &[String], _default: usize) -> Result<usize> {
+            Ok(self.selects.lock().unwrap().remove(0))
+        }
+
+        fn fuzzy_select(&self, _prompt: &str, _items: &[String], _default: usize) -> Result<usize> {
+            Ok(self.selects.lock().unwrap().remove(0))
+        }
+    }
+
+    const CONFIG: &str = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+charts:
+  - name: postgres
+    repo_name: stable
+    version: 1.0.0
+    namespace: db
+  - name: api
+    repo_name: stable
+    version: 1.0.0
+    namespace: apps
+    depends:
+      - postgres
+  - name: frontend
+    repo_name: stable
+    version: 1.0.0
+    namespace: apps
+    depends:
+      - api
+destinations:
+  - name: default
+    path: ./charts
+"#;
+
+    fn revision(revision: u32, status: &str) -> ReleaseRevision {
+        ReleaseRevision {
+            revision,
+            updated: "2024-01-01 00:00:00".to_string(),
+            status: status.to_string(),
+            chart: format!("postgres-1.0.{}", revision),
+            app_version: "1.0".to_string(),
+            description: "Upgrade complete".to_string(),
+        }
+    }
+
+    fn write_config() -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vesshelm.yaml");
+        std::fs::write(&path, CONFIG).unwrap();
+        (dir, path)
+    }
+
+    fn args(name: &str, to_revision: Option<u32>, with_dependents: bool) -> RollbackArgs {
+        RollbackArgs {
+            name: Some(name.to_string()),
+            to_revision,
+            with_dependents,
+            no_interactive: true,
+        }
+    }
+
+    #[test]
+    fn test_rollback_to_revision() {
+        let (_dir, config_path) = write_config();
+        let interaction = MockInteraction::default();
+        let mut client = MockHelmClient::new();
+
+        client
+            .expect_history()
+            .with(eq("postgres"), eq("db"))
+            .returning(|_, _| {
+                Ok(vec![
+                    revision(1, "superseded"),
+                    revision(2, "superseded"),
+                    revision(3, "deployed"),
+                ])
+            });
+        client
+            .expect_rollback()
+            .with(eq("postgres"), eq("db"), eq(Some(1)))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let res = rollback(
+            args("postgres", Some(1), false),
+            &config_path,
+            &interaction,
+            &client,
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_rollback_defaults_to_previous_revision() {
+        let (_dir, config_path) = write_config();
+        let interaction = MockInteraction::default();
+        let mut client = MockHelmClient::new();
+
+        client
+            .expect_history()
+            .returning(|_, _| Ok(vec![revision(4, "superseded"), revision(5, "deployed")]));
+        client
+            .expect_rollback()
+            .with(eq("postgres"), eq("db"), eq(Some(4)))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let res = rollback(
+            args("postgres", None, false),
+            &config_path,
+            &interaction,
+            &client,
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_rollback_unknown_revision() {
+        let (_dir, config_path) = write_config();
+        let interaction = MockInteraction::default();
+        let mut client = MockHelmClient::new();
+
+        client
+            .expect_history()
+            .returning(|_, _| Ok(vec![revision(1, "deployed")]));
+        client.expect_rollback().never();
+
+        let err = rollback(
+            args("postgres", Some(7), false),
+            &config_path,
+            &interaction,
+            &client,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Revision 7 not found"));
+    }
+
+    #[test]
+    fn test_rollback_interactive_selection() {
+        let (_dir, config_path) = write_config();
+        // Candidates are listed newest first: [2, 1]
+        let interaction = MockInteraction::new(vec![1], vec![true]);
+        let mut client = MockHelmClient::new();
+
+        client.expect_history().returning(|_, _| {
+            Ok(vec![
+                revision(1, "superseded"),
+                revision(2, "superseded"),
+                revision(3, "deployed"),
+            ])
+        });
+        client
+            .expect_rollback()
+            .with(eq("postgres"), eq("db"), eq(Some(1)))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let mut rollback_args = args("postgres", None, false);
+        rollback_args.no_interactive = false;
+        let res = rollback(rollback_args, &config_path, &interaction, &client);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_rollback_with_dependents_in_reverse_order() {
+        let (_dir, config_path) = write_config();
+        let interaction = MockInteraction::default();
+        let mut client = MockHelmClient::new();
+        let mut seq = Sequence::new();
+
+        client
+            .expect_history()
+            .returning(|_, _| Ok(vec![revision(1, "superseded"), revision(2, "deployed")]));
+        client
+            .expect_rollback()
+            .with(eq("frontend"), eq("apps"), eq(None))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _, _| Ok(()));
+        client
+            .expect_rollback()
+            .with(eq("api"), eq("apps"), eq(None))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _, _| Ok(()));
+        client
+            .expect_rollback()
+            .with(eq("postgres"), eq("db"), eq(Some(1)))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _, _| Ok(()));
+
+        let res = rollback(
+            args("postgres", None, true),
+            &config_path,
+            &interaction,
+            &client,
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_rollback_aborted_by_user() {
+        let (_dir, config_path) = write_config();
+        let interaction = MockInteraction::new(vec![0], vec![false]);
+        let mut client = MockHelmClient::new();
+
+        client
+            .expect_history()
+            .returning(|_, _| Ok(vec![revision(1, "superseded"), revision(2, "deployed")]));
+        client.expect_rollback().never();
+
+        let mut rollback_args = args("postgres", None, false);
+        rollback_args.no_interactive = false;
+        assert!(rollback(rollback_args, &config_path, &interaction, &client).is_ok());
+    }
+}
@@ -0,0 +1,339 @@
+use super::{OutputFormat, StatusArgs};
+use crate::clients::helm::{HelmClient, HelmRelease, RealHelmClient};
+use crate::config::{Chart, Config};
+use anyhow::{Context, Result};
+use console::style;
+use serde::Serialize;
+use std::path::Path;
+
+pub async fn run(args: StatusArgs, config_path: &Path) -> Result<()> {
+    let client = RealHelmClient::new();
+    status(args, config_path, &client)
+}
+
+/// State of a configured chart compared to what is deployed in the cluster.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartState {
+    InSync,
+    VersionMismatch,
+    NotDeployed,
+    Pending,
+    Failed,
+    /// `no_deploy` charts are reported but never count as drift.
+    Ignored,
+}
+
+impl ChartState {
+    fn is_drift(self) -> bool {
+        !matches!(self, ChartState::InSync | ChartState::Ignored)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ChartState::InSync => "in sync",
+            ChartState::VersionMismatch => "version mismatch",
+            ChartState::NotDeployed => "not deployed",
+            ChartState::Pending => "pending",
+            ChartState::Failed => "failed",
+            ChartState::Ignored => "ignored",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChartStatus {
+    pub name: String,
+    pub namespace: String,
+    pub desired_version: Option<String>,
+    pub deployed_version: Option<String>,
+    pub revision: Option<String>,
+    pub release_status: Option<String>,
+    pub state: ChartState,
+}
+
+fn status(args: StatusArgs, config_path: &Path, client: &impl HelmClient) -> Result<()> {
+    let config = Config::load_from_path(config_path)?;
+
+    if args.output == OutputFormat::Table {
+        println!(
+            "{} Checking release status...\n",
+            style("==>").bold().green()
+        );
+    }
+
+    let selected =
+        crate::util::filter::select_charts(&config.charts, args.charts.as_deref(), None)?;
+
+    let releases = client
+        .list_releases()
+        .context("Failed to list releases from the cluster")?;
+
+    let statuses: Vec<ChartStatus> = config
+        .charts
+        .iter()
+        .filter(|c| {
+            selected
+                .as_ref()
+                .is_none_or(|names| names.iter().any(|n| n == c.release_name()))
+        })
+        .map(|c| chart_status(c, &releases))
+        .collect();
+
+    match args.output {
+        OutputFormat::Table => print_table(&statuses),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&statuses).context("Failed to serialize status")?
+        ),
+    }
+
+    let drifted = statuses.iter().filter(|s| s.state.is_drift()).count();
+    if drifted > 0 {
+        anyhow::bail!(
+            "{} chart(s) are not in the desired state in the cluster",
+            drifted
+        );
+    }
+
+    if args.output == OutputFormat::Table {
+        println!(
+            "\n {} All charts are deployed as desired.",
+            style("[OK]").green()
+        );
+    }
+    Ok(())
+}
+
+/// Compares a chart with its installed release (matched on name and namespace).
+pub fn chart_status(chart: &Chart, releases: &[HelmRelease]) -> ChartStatus {
+    let release = releases.iter().find(|r| {
+        r.name == chart.release_name() && r.namespace == chart.namespace && r.is_installed()
+    });
+
+    let deployed_version = release.and_then(|r| r.chart_version()).map(String::from);
+
+    let state = if chart.no_deploy {
+        ChartState::Ignored
+    } else {
+        match release {
+            None => ChartState::NotDeployed,
+            Some(r) if r.status.starts_with("pending") => ChartState::Pending,
+            Some(r) if r.status != "deployed" => ChartState::Failed,
+            Some(_) => match (&chart.version, &deployed_version) {
+                (Some(desired), Some(deployed)) if !same_version(desired, deployed) => {
+                    ChartState::VersionMismatch
+                }
+                _ => ChartState::InSync,
+            },
+        }
+    };
+
+    ChartStatus {
+        name: chart.release_name().to_string(),
+        namespace: chart.namespace.clone(),
+        desired_version: chart.version.clone(),
+        deployed_version,
+        revision: release.map(|r| r.revision.clone()),
+        release_status: release.map(|r| r.status.clone()),
+        state,
+    }
+}
+
+fn same_version(a: &str, b: &str) -> bool {
+    a.trim_start_matches('v') == b.trim_start_matches('v')
+}
+
+fn print_table(statuses: &[ChartStatus]) {
+    if statuses.is_empty() {
+        println!("No charts found in configuration.");
+        return;
+    }
+
+    let headers = [
+        "CHART",
+        "NAMESPACE",
+        "DESIRED",
+        "DEPLOYED",
+        "STATUS",
+        "STATE",
+    ];
+    let rows: Vec<[String; 6]> = statuses
+        .iter()
+        .map(|s| {
+            [
+                s.name.clone(),
+                s.namespace.clone(),
+                s.desired_version.clone().unwrap_or_else(|| "-".to_string()),
+                s.deployed_version
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+                s.release_status.clone().unwrap_or_else(|| "-".to_string()),
+                s.state.label().to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header_line: Vec<String> = headers
+        .iter()
+        .zip(widths)
+        .map(|(h, w)| format!("{:<w$}", h))
+        .collect();
+    println!("{}", style(header_line.join("  ").trim_end()).bold());
+
+    for (row, status) in rows.iter().zip(statuses) {
+        let cells: Vec<String> = row[..5]
+            .iter()
+            .zip(widths)
+            .map(|(cell, w)| format!("{:<w$}", cell))
+            .collect();
+        let state = match status.state {
+            ChartState::InSync => style(&row[5]).green(),
+            ChartState::Ignored => style(&row[5]).dim(),
+            ChartState::VersionMismatch | ChartState::Pending => style(&row[5]).yellow(),
+            ChartState::NotDeployed | ChartState::Failed => style(&row[5]).red(),
+        };
+        println!("{}  {}", cells.join("  "), state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::helm::MockHelmClient;
+
+    fn chart(name: &str, version: Option<&str>) -> Chart {
+        Chart {
+            name: name.to_string(),
+            repo_name: Some("stable".to_string()),
+            version: version.map(String::from),
+            namespace: "default".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn release(name: &str, chart: &str, status: &str) -> HelmRelease {
+        HelmRelease {
+            name: name.to_string(),
+            namespace: "default".to_string(),
+            revision: "3".to_string(),
+            status: status.to_string(),
+            chart: chart.to_string(),
+            app_version: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_chart_status_states() {
+        let releases = vec![
+            release("ok", "ok-1.0.0", "deployed"),
+            release("old", "old-1.0.0", "deployed"),
+            release("stuck", "stuck-1.0.0", "pending-upgrade"),
+            release("broken", "broken-1.0.0", "failed"),
+            release("tagged", "tagged-v2.0.0", "deployed"),
+            release("removed", "removed-1.0.0", "uninstalled"),
+            release("replaced", "replaced-1.0.0", "superseded"),
+        ];
+
+        let state = |c: Chart| chart_status(&c, &releases).state;
+        assert_eq!(state(chart("ok", Some("1.0.0"))), ChartState::InSync);
+        assert_eq!(
+            state(chart("old", Some("1.1.0"))),
+            ChartState::VersionMismatch
+        );
+        assert_eq!(state(chart("stuck", Some("1.0.0"))), ChartState::Pending);
+        assert_eq!(state(chart("broken", Some("1.0.0"))), ChartState::Failed);
+        assert_eq!(
+            state(chart("missing", Some("1.0.0"))),
+            ChartState::NotDeployed
+        );
+        assert_eq!(state(chart("tagged", Some("2.0.0"))), ChartState::InSync);
+        // Kept with `helm uninstall --keep-history`
+        assert_eq!(
+            state(chart("removed", Some("1.0.0"))),
+            ChartState::NotDeployed
+        );
+        assert_eq!(
+            state(chart("replaced", Some("1.0.0"))),
+            ChartState::NotDeployed
+        );
+        // Local charts without a pinned version only need a healthy release
+        assert_eq!(state(chart("ok", None)), ChartState::InSync);
+
+        let mut ignored = chart("missing", Some("1.0.0"));
+        ignored.no_deploy = true;
+        assert_eq!(state(ignored), ChartState::Ignored);
+    }
+
+    #[test]
+    fn test_chart_status_matches_namespace() {
+        let mut other_ns = release("ok", "ok-1.0.0", "deployed");
+        other_ns.namespace = "other".to_string();
+
+        let status = chart_status(&chart("ok", Some("1.0.0")), &[other_ns]);
+        assert_eq!(status.state, ChartState::NotDeployed);
+        assert_eq!(status.deployed_version, None);
+    }
+
+    #[test]
+    fn test_status_fails_on_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("vesshelm.yaml");
+        std::fs::write(
+            &config_path,
+            r#"
+repositories:
+  - name: stable
+    url: https://example.com
+destinations:
+  - name: default
+    path: ./charts
+charts:
+  - name: ok
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: missing
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+"#,
+        )
+        .unwrap();
+
+        let mut client = MockHelmClient::new();
+        client
+            .expect_list_releases()
+            .returning(|| Ok(vec![release("ok", "ok-1.0.0", "deployed")]));
+
+        let args = StatusArgs {
+            charts: None,
+            output: OutputFormat::Json,
+        };
+        let err = status(args, &config_path, &client).unwrap_err();
+        assert!(err.to_string().contains("1 chart(s)"));
+
+        let args = StatusArgs {
+            charts: Some(vec!["ok".to_string()]),
+            output: OutputFormat::Json,
+        };
+        assert!(status(args, &config_path, &client).is_ok());
+
+        let args = StatusArgs {
+            charts: Some(vec!["unknown".to_string()]),
+            output: OutputFormat::Json,
+        };
+        let err = status(args, &config_path, &client).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("The following specified charts do not exist: unknown")
+        );
+    }
+}
@@ -4,7 +4,9 @@ use crate::clients::helm::RealHelmClient;
 use crate::config::Config;
 use crate::engine::sync::{SyncEngine, SyncEvent, SyncOptions};
 use crate::lock::Lockfile;
+use crate::util::dag;
 use crate::util::progress::ProgressTracker;
+use crate::util::state::state_dir;
 use anyhow::{Context, Result};
 use console::style;
 use std::path::Path;
@@ -12,22 +14,28 @@ use std::path::Path;
 pub async fn run(args: SyncArgs, no_progress: bool, config_path: &Path) -> Result<()> {
     let config = Config::load_from_path(config_path)?;
 
-    // Validate positional charts arguments
-    if let Some(charts) = &args.charts {
-        let available_names: Vec<_> = config.charts.iter().map(|c| c.name.as_str()).collect();
-        crate::util::filter::validate_chart_args(&available_names, charts)?;
+    // Resolve positional charts arguments and label selector
+    let selected = crate::util::filter::select_charts(
+        &config.charts,
+        args.charts.as_deref(),
+        args.selector.as_ref(),
+    )?
+    .map(|names| {
+        dag::expand_selection(
+            &config.charts,
+            &names,
+            args.with_dependencies,
+            args.with_dependents,
+        )
+    })
+    .transpose()?;
+    if let Some(charts) = &selected {
+        crate::util::filter::warn_excluded_dependencies(&config.charts, charts);
     }
 
-    // We need to count total charts first for the progress bar
-    // This logic duplicates filter logic slightly but is needed for UI
-    let total_charts = if let Some(charts) = &args.charts {
-        config
-            .charts
-            .iter()
-            .filter(|c| charts.contains(&c.name))
-            .count() as u64
-    } else {
-        config.charts.len() as u64
+    let total_charts = match &selected {
+        Some(charts) => charts.len() as u64,
+        None => config.charts.len() as u64,
     };
 
     println!(
@@ -40,13 +48,13 @@ pub async fn run(args: SyncArgs, no_progress: bool, config_path: &Path) -> Resul
         .context("Failed to initialize progress tracker")?;
     let mut lockfile = Lockfile::load().unwrap_or_default();
 
-    let helm_client = RealHelmClient::new();
+    let helm_client = RealHelmClient::with_state_dir(&state_dir(config_path));
     let git_client = RealGitClient::new();
     let engine = SyncEngine::new(helm_client, git_client);
 
     let options = SyncOptions {
         ignore_skip: args.ignore_skip,
-        charts: args.charts,
+        charts: selected,
     };
 
     let stats = engine.sync(config, &mut lockfile, options, |event| match event {
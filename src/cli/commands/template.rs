@@ -0,0 +1,134 @@
+use super::TemplateArgs;
+use crate::config::Config;
+use crate::engine::release::{load_variable_context, prepare_release, to_template_args};
+use crate::util::progress::ProgressTracker;
+use crate::util::{dag, filter};
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use console::style;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Renders the manifests of every selected chart with `helm template`, one directory per release.
+pub async fn run(args: TemplateArgs, no_progress: bool, config_path: &Path) -> Result<()> {
+    let config = Config::load_from_path(config_path)?;
+
+    let helm_config = config
+        .vesshelm
+        .as_ref()
+        .ok_or_else(|| anyhow!("No vesshelm configuration found in vesshelm.yaml"))?;
+
+    // Load variables and secrets
+    let variable_context = load_variable_context(&config, config_path)?;
+
+    println!(
+        "{} Rendering manifests to {}...",
+        style("==>").bold().green(),
+        args.output_dir.display()
+    );
+
+    let mut sorted_charts =
+        dag::sort_charts(&config.charts).context("Failed to resolve chart dependencies")?;
+
+    if let Some(charts) = filter::select_charts(
+        &config.charts,
+        args.charts.as_deref(),
+        args.selector.as_ref(),
+    )? {
+        sorted_charts.retain(|chart| charts.iter().any(|n| n == chart.release_name()));
+    }
+
+    let tracker = ProgressTracker::new(sorted_charts.len() as u64, no_progress)
+        .context("Failed to initialize progress tracker")?;
+
+    let mut rendered_count = 0;
+    let mut skipped_count = 0;
+    let mut failed = Vec::new();
+
+    for chart in sorted_charts {
+        if chart.no_deploy {
+            tracker.println(&format!(
+                " {} {} (no_deploy=true)",
+                "⏭ ".yellow(),
+                chart.release_name()
+            ));
+            skipped_count += 1;
+            tracker.inc();
+            continue;
+        }
+
+        tracker.set_message(format!("Rendering {}...", chart.release_name()));
+
+        // Releases sharing their name across namespaces must not overwrite each other
+        let release_dir = args
+            .output_dir
+            .join(crate::engine::export::resource_name(chart, &config.charts));
+        let result = async {
+            let prepared = prepare_release(
+                chart,
+                &config.destinations,
+                helm_config,
+                &variable_context,
+                &tracker,
+            )?;
+            let template_args = to_template_args(&prepared.args)?;
+
+            // Stale manifests of templates removed from the chart must not survive a new render
+            if release_dir.exists() {
+                std::fs::remove_dir_all(&release_dir)
+                    .with_context(|| format!("Failed to clean {}", release_dir.display()))?;
+            }
+
+            let output = Command::new("helm")
+                .args(template_args.split_whitespace())
+                .arg("--output-dir")
+                .arg(&release_dir)
+                .output()
+                .await
+                .context("Failed to execute helm template")?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "helm template failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                tracker.println(&format!(
+                    " {} {} -> {}",
+                    style("[OK]").green(),
+                    chart.release_name().bold(),
+                    release_dir.display()
+                ));
+                rendered_count += 1;
+            }
+            Err(e) => {
+                tracker.println(&format!(
+                    " {} {}: {:#}",
+                    style("[FAILED]").red(),
+                    chart.release_name().bold(),
+                    e
+                ));
+                failed.push(chart.release_name().to_string());
+            }
+        }
+        tracker.inc();
+    }
+
+    tracker.finish_with_message("Rendering ended");
+
+    println!("\n\n{}", style("Summary:").bold());
+    println!("  Rendered: {}", style(rendered_count).green());
+    println!("  Failed:   {}", style(failed.len()).red());
+    println!("  Skipped:  {}", style(skipped_count).dim());
+
+    if !failed.is_empty() {
+        return Err(anyhow!("Failed to render: {}", failed.join(", ")));
+    }
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use crate::cli::commands::UninstallArgs;
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, bail};
 // use colored::*; // Unused
 use crate::clients::{HelmClient, helm::RealHelmClient};
-use crate::config::Config;
+use crate::config::{Chart, Config};
+use crate::util::filter;
 use crate::util::interaction::UserInteraction;
 use console::style;
 
@@ -16,13 +17,24 @@ pub async fn run(
     // Load configuration
     let config = Config::load_from_path(config_path)?;
 
-    // Select Chart
-    let chart = if let Some(name) = args.name {
-        config
-            .charts
-            .iter()
-            .find(|c| c.name == name)
-            .ok_or_else(|| anyhow!("Chart '{}' not found in vesshelm.yaml", name))?
+    // Select charts
+    let charts: Vec<&Chart> = if args.name.is_some() || args.selector.is_some() {
+        if let Some(name) = &args.name
+            && !filter::is_glob(name)
+            && !config.charts.iter().any(|c| c.release_name() == *name)
+        {
+            bail!("Chart '{}' not found in vesshelm.yaml", name);
+        }
+        let names = args.name.clone().map(|name| vec![name]);
+        let selected =
+            filter::select_charts(&config.charts, names.as_deref(), args.selector.as_ref())?
+                .unwrap_or_default();
+        // Dependents go before their dependencies
+        let mut charts = crate::util::dag::sort_charts(&config.charts)
+            .context("Failed to resolve chart dependencies")?;
+        charts.retain(|c| selected.iter().any(|n| n == c.release_name()));
+        charts.reverse();
+        charts
     } else {
         // Interactive selection
         if config.charts.is_empty() {
@@ -31,39 +43,54 @@ pub async fn run(
         }
 
         let mut sorted_charts: Vec<_> = config.charts.iter().collect();
-        sorted_charts.sort_by(|a, b| a.name.cmp(&b.name));
+        sorted_charts.sort_by(|a, b| a.release_name().cmp(b.release_name()));
 
         let items: Vec<String> = sorted_charts
             .iter()
-            .map(|c| format!("{} ({})", c.name, c.namespace))
+            .map(|c| format!("{} ({})", c.release_name(), c.namespace))
             .collect();
 
         let selection = interaction
             .fuzzy_select("Select chart to uninstall", &items, 0)
             .context("Failed to read selection")?;
 
-        sorted_charts[selection]
+        vec![sorted_charts[selection]]
     };
 
+    let client = RealHelmClient::new();
+    for chart in charts {
+        uninstall_chart(chart, &config, &args, interaction, &client)?;
+    }
+
+    Ok(())
+}
+
+fn uninstall_chart(
+    chart: &Chart,
+    config: &Config,
+    args: &UninstallArgs,
+    interaction: &impl UserInteraction,
+    client: &impl HelmClient,
+) -> Result<()> {
     // Check for dependents
-    let dependents = crate::util::dag::get_dependents(&config.charts, &chart.name)
+    let dependents = crate::util::dag::get_dependents(&config.charts, chart.release_name())
         .context("Failed to check chart dependencies")?;
 
     if !dependents.is_empty() {
         println!(
             " {} The following charts depend on '{}':",
             style("WARN:").yellow(),
-            style(&chart.name).bold()
+            style(chart.release_name()).bold()
         );
         for dep in &dependents {
-            println!("  - {}", style(&dep.name).bold());
+            println!("  - {}", style(dep.release_name()).bold());
         }
         println!();
     } else {
         println!(
             " {} No other charts depend on '{}'. It is safe to remove regarding dependencies.",
             style("[OK]").green(),
-            style(&chart.name).bold()
+            style(chart.release_name()).bold()
         );
     }
 
@@ -71,7 +98,7 @@ pub async fn run(
     println!(
         " {} You are about to uninstall the chart '{}' from namespace '{}'.",
         style("WARN:").yellow(),
-        style(&chart.name).bold(),
+        style(chart.release_name()).bold(),
         style(&chart.namespace).bold()
     );
 
@@ -85,27 +112,30 @@ pub async fn run(
     };
 
     if !confirmation {
-        println!(" {} Uninstallation aborted.", style("[ABORT]").dim());
+        println!(
+            " {} Uninstallation of {} aborted.",
+            style("[ABORT]").dim(),
+            chart.release_name()
+        );
         return Ok(());
     }
 
-    println!("Uninstalling {}...", chart.name);
+    println!("Uninstalling {}...", chart.release_name());
 
     // Run helm uninstall
-    let client = RealHelmClient::new();
-    match client.uninstall(&chart.name, &chart.namespace) {
+    match client.uninstall(chart.release_name(), &chart.namespace) {
         Ok(_) => {
             println!(
                 " {} Successfully uninstalled {}.",
                 style("[OK]").green(),
-                style(&chart.name).bold()
+                style(chart.release_name()).bold()
             );
         }
         Err(e) => {
             eprintln!(
                 " {} Failed to uninstall {}: {}",
                 style("[FAIL]").red(),
-                chart.name,
+                chart.release_name(),
                 e
             );
             return Err(e);
@@ -1,4 +1,9 @@
-use crate::config::Config;
+use super::ValidateArgs;
+use crate::config::{Chart, Config, PolicyLevel, VesshelmConfig};
+use crate::engine::policy;
+use crate::engine::release::{load_variable_context, prepare_release, render_manifest};
+use crate::engine::values::{SCHEMA_FILE, chart_directory, schema_violations, unknown_keys};
+use crate::util::progress::ProgressTracker;
 use anyhow::{Context, Result};
 use colored::*;
 use console::style;
@@ -6,7 +11,7 @@ use std::fs;
 use std::path::Path;
 use validator::Validate; // Needed for trait method
 
-pub async fn run(config_path: &Path) -> Result<()> {
+pub async fn run(args: ValidateArgs, config_path: &Path) -> Result<()> {
     println!("{} Validating configuration", style("==>").bold().green());
 
     let content = fs::read_to_string(config_path)
@@ -54,6 +59,160 @@ pub async fn run(config_path: &Path) -> Result<()> {
         eprintln!();
     }
 
+    validate_charts(&config, config_path, &args).await?;
+
     println!("{}", "Configuration is valid".green());
     Ok(())
 }
+
+/// Validates the merged values of every chart against its synced `values.schema.json`.
+///
+/// With `--values`, also reports the values keys its default `values.yaml` does not define, and
+/// with `--render`, renders the chart and checks the manifests against the `policies`. Variables
+/// are only loaded when a chart needs its values, and a chart that fails to be checked is
+/// reported like any other validation error.
+async fn validate_charts(config: &Config, config_path: &Path, args: &ValidateArgs) -> Result<()> {
+    let Some(helm_config) = &config.vesshelm else {
+        return Ok(());
+    };
+    let tracker = ProgressTracker::new(0, true)?;
+
+    let mut valid = true;
+    let mut checked = Vec::new();
+    for chart in &config.charts {
+        if args.values || args.render {
+            checked.push(chart);
+            continue;
+        }
+        match chart_directory(chart, &config.destinations) {
+            Ok(dir) if dir.join(SCHEMA_FILE).exists() => checked.push(chart),
+            Ok(_) => {}
+            Err(e) => {
+                valid = false;
+                report_failure(chart, &e);
+            }
+        }
+    }
+
+    if !checked.is_empty() {
+        match load_variable_context(config, config_path) {
+            Ok(variable_context) => {
+                for chart in checked {
+                    let result = validate_chart(
+                        config,
+                        helm_config,
+                        chart,
+                        &variable_context,
+                        args,
+                        &tracker,
+                    )
+                    .await;
+                    match result {
+                        Ok(chart_valid) => valid &= chart_valid,
+                        Err(e) => {
+                            valid = false;
+                            report_failure(chart, &e);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                valid = false;
+                eprintln!("  - Variables: {:#}", e);
+            }
+        }
+    }
+
+    if !valid {
+        anyhow::bail!("Validation failed");
+    }
+    Ok(())
+}
+
+fn report_failure(chart: &Chart, e: &anyhow::Error) {
+    eprintln!(
+        "  - Chart '{}': {:#}",
+        style(chart.release_name()).yellow(),
+        e
+    );
+}
+
+/// Checks the values, and with `--render` the policies, of one chart; returns whether it is valid.
+async fn validate_chart(
+    config: &Config,
+    helm_config: &VesshelmConfig,
+    chart: &Chart,
+    variable_context: &serde_yaml_ng::Value,
+    args: &ValidateArgs,
+    tracker: &ProgressTracker,
+) -> Result<bool> {
+    let prepared = prepare_release(
+        chart,
+        &config.destinations,
+        helm_config,
+        variable_context,
+        tracker,
+    )?;
+
+    let mut valid = true;
+    let violations = schema_violations(chart, &config.destinations, &prepared)
+        .context("Failed to validate values")?;
+    if !violations.is_empty() {
+        valid = false;
+        eprintln!(
+            "  - Chart '{}': values do not match {}",
+            style(chart.release_name()).yellow(),
+            SCHEMA_FILE
+        );
+        for violation in &violations {
+            eprintln!("    {} {}", style("➜").red(), violation);
+        }
+    }
+
+    if args.values {
+        match unknown_keys(chart, &config.destinations, &prepared)
+            .context("Failed to check values")?
+        {
+            None => println!(
+                "  {} {}: no default values.yaml, run `vesshelm sync` to check its values",
+                "⏭ ".yellow(),
+                chart.release_name()
+            ),
+            Some(unknown) if !unknown.is_empty() => {
+                valid = false;
+                eprintln!(
+                    "  - Chart '{}': unknown values keys",
+                    style(chart.release_name()).yellow()
+                );
+                for key in &unknown {
+                    eprintln!("    {} {}", style("➜").red(), key);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    if args.render && !chart.no_deploy {
+        let manifest = render_manifest(chart, &prepared).await?;
+        let Some(policies) = &config.policies else {
+            return Ok(valid);
+        };
+        let violations = policy::evaluate(policies, &manifest, &chart.namespace)?;
+        if !violations.is_empty() {
+            eprintln!(
+                "  - Chart '{}': policy violations",
+                style(chart.release_name()).yellow()
+            );
+        }
+        for violation in &violations {
+            if violation.level == PolicyLevel::Error {
+                valid = false;
+                eprintln!("    {} {}", style("➜").red(), violation);
+            } else {
+                eprintln!("    {} {}", "⚠️ ".yellow(), violation);
+            }
+        }
+    }
+
+    Ok(valid)
+}
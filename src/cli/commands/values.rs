@@ -0,0 +1,230 @@
+use super::ValuesArgs;
+use crate::config::Config;
+use crate::engine::release::{INLINE_VALUES_SOURCE, load_variable_context, prepare_release};
+use crate::engine::values::{ValuesNode, merge_layers, values_layers};
+use crate::util::encryption::is_sops_encrypted;
+use crate::util::progress::ProgressTracker;
+use anyhow::{Context, Result, anyhow};
+use serde_yaml_ng::Value;
+use std::path::Path;
+
+const MASK: &str = "********";
+
+pub async fn run(args: ValuesArgs, config_path: &Path) -> Result<()> {
+    let config = Config::load_from_path(config_path)?;
+    let chart = config
+        .charts
+        .iter()
+        .find(|c| c.release_name() == args.chart)
+        .ok_or_else(|| anyhow!("Chart '{}' not found in vesshelm.yaml", args.chart))?;
+
+    let global_helm_config = config
+        .vesshelm
+        .as_ref()
+        .ok_or_else(|| anyhow!("No vesshelm configuration found in vesshelm.yaml"))?;
+
+    let variable_context = load_variable_context(&config, config_path)?;
+    let secrets = if args.show_secrets {
+        Vec::new()
+    } else {
+        secret_values(&config, config_path, &variable_context)?
+    };
+
+    let tracker = ProgressTracker::new(0, true)?;
+    let prepared = prepare_release(
+        chart,
+        &config.destinations,
+        global_helm_config,
+        &variable_context,
+        &tracker,
+    )?;
+
+    let layers = values_layers(chart, &config.destinations, &prepared)?;
+    let merged = merge_layers(&layers)?;
+    let masked_sources: Vec<&str> = if args.show_secrets {
+        Vec::new()
+    } else {
+        layers
+            .iter()
+            .map(|(_, source)| source.as_str())
+            .filter(|source| {
+                *source != INLINE_VALUES_SOURCE
+                    && std::fs::read_to_string(source).is_ok_and(|raw| is_sops_encrypted(&raw))
+            })
+            .collect()
+    };
+
+    let mut output = String::new();
+    render(&merged, 0, &secrets, &masked_sources, &mut output)?;
+    if output.is_empty() {
+        output.push_str("{}\n");
+    }
+    print!("{}", output);
+    Ok(())
+}
+
+/// Values contributed by the secrets files, i.e. those not coming from the variables files alone.
+fn secret_values(config: &Config, config_path: &Path, context: &Value) -> Result<Vec<Value>> {
+    if config.secrets_files.as_ref().is_none_or(|f| f.is_empty()) {
+        return Ok(Vec::new());
+    }
+    let variables = match &config.variables_files {
+        Some(files) if !files.is_empty() => {
+            let base_path = config_path.parent().unwrap_or(Path::new("."));
+            crate::util::variables::load_variables(files, base_path)
+                .context("Failed to load variables")?
+        }
+        _ => Value::Null,
+    };
+
+    let mut secrets = Vec::new();
+    collect_overridden(context, Some(&variables), &mut secrets);
+    Ok(secrets)
+}
+
+fn collect_overridden(value: &Value, base: Option<&Value>, out: &mut Vec<Value>) {
+    match value {
+        Value::Mapping(map) => {
+            for (key, child) in map {
+                collect_overridden(child, base.and_then(|b| b.get(key)), out);
+            }
+        }
+        Value::Sequence(items) => {
+            for (i, child) in items.iter().enumerate() {
+                collect_overridden(child, base.and_then(|b| b.get(i)), out);
+            }
+        }
+        Value::Null => {}
+        scalar if base != Some(scalar) => out.push(scalar.clone()),
+        _ => {}
+    }
+}
+
+fn is_secret(value: &Value, secrets: &[Value]) -> bool {
+    match value {
+        Value::Mapping(map) => map.values().any(|v| is_secret(v, secrets)),
+        Value::Sequence(items) => items.iter().any(|v| is_secret(v, secrets)),
+        Value::String(s) => secrets.iter().any(|secret| match secret {
+            Value::String(secret) => !secret.is_empty() && s.contains(secret.as_str()),
+            other => other == value,
+        }),
+        other => secrets.contains(other),
+    }
+}
+
+/// Renders the merged values as YAML, annotating each leaf with its source.
+///
+/// Leaves from `masked_sources` (encrypted values files) or holding a secret are masked.
+fn render(
+    node: &ValuesNode,
+    indent: usize,
+    secrets: &[Value],
+    masked_sources: &[&str],
+    out: &mut String,
+) -> Result<()> {
+    let ValuesNode::Map(entries) = node else {
+        return Ok(());
+    };
+    let pad = " ".repeat(indent);
+    for (key, node) in entries {
+        let key = scalar_to_string(key)?;
+        match node {
+            ValuesNode::Map(_) => {
+                out.push_str(&format!("{}{}:\n", pad, key));
+                render(node, indent + 2, secrets, masked_sources, out)?;
+            }
+            ValuesNode::Leaf { value, source } => {
+                let value =
+                    if masked_sources.contains(&source.as_str()) || is_secret(value, secrets) {
+                        Value::String(MASK.to_string())
+                    } else {
+                        value.clone()
+                    };
+                match &value {
+                    Value::Sequence(items) if !items.is_empty() => {
+                        out.push_str(&format!("{}{}:  # {}\n", pad, key, source));
+                        for line in serde_yaml_ng::to_string(&value)?.lines() {
+                            out.push_str(&format!("{}  {}\n", pad, line));
+                        }
+                    }
+                    _ => out.push_str(&format!(
+                        "{}{}: {}  # {}\n",
+                        pad,
+                        key,
+                        scalar_to_string(&value)?,
+                        source
+                    )),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scalar_to_string(value: &Value) -> Result<String> {
+    Ok(serde_yaml_ng::to_string(value)?.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(layers: &[(&str, &str)]) -> ValuesNode {
+        let mut node = ValuesNode::Map(Vec::new());
+        for (source, content) in layers {
+            let value: Value = serde_yaml_ng::from_str(content).unwrap();
+            node.merge(&value, source);
+        }
+        node
+    }
+
+    #[test]
+    fn test_merge_tracks_sources() {
+        let node = merged(&[
+            (
+                "values.yaml",
+                "replicas: 1\nimage:\n  repository: nginx\n  tag: '1.0'\nports: [80]\nextra: x",
+            ),
+            (
+                "prod.yaml",
+                "replicas: 3\nimage:\n  tag: '2.0'\nports: [443]\nextra: null",
+            ),
+        ]);
+
+        let mut out = String::new();
+        render(&node, 0, &[], &[], &mut out).unwrap();
+        assert_eq!(
+            out,
+            "replicas: 3  # prod.yaml\n\
+             image:\n  repository: nginx  # values.yaml\n  tag: '2.0'  # prod.yaml\n\
+             ports:  # prod.yaml\n  - 443\n"
+        );
+    }
+
+    #[test]
+    fn test_render_masks_secrets() {
+        let node = merged(&[(
+            "values.yaml",
+            "db:\n  user: admin\n  password: s3cr3t\n  url: postgres://admin:s3cr3t@db\n",
+        )]);
+        let secrets = vec![Value::String("s3cr3t".into())];
+
+        let mut out = String::new();
+        render(&node, 0, &secrets, &[], &mut out).unwrap();
+        assert!(out.contains("user: admin"));
+        assert!(out.contains("password: '********'"));
+        assert!(out.contains("url: '********'"));
+        assert!(!out.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn test_collect_overridden_keeps_secret_values_only() {
+        let variables: Value = serde_yaml_ng::from_str("env: prod\ndb:\n  host: db").unwrap();
+        let context: Value =
+            serde_yaml_ng::from_str("env: prod\ndb:\n  host: db\n  password: s3cr3t").unwrap();
+
+        let mut secrets = Vec::new();
+        collect_overridden(&context, Some(&variables), &mut secrets);
+        assert_eq!(secrets, vec![Value::String("s3cr3t".into())]);
+    }
+}
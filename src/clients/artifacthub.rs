@@ -15,6 +15,12 @@ pub struct Repository {
     pub url: String,
 }
 
+#[derive(Deserialize, Debug)]
+struct SearchResults {
+    #[serde(default)]
+    packages: Vec<Package>,
+}
+
 pub struct ArtifactHubClient {
     client: Client,
     base_url: String,
@@ -78,6 +84,34 @@ impl ArtifactHubClient {
             .context("Failed to parse Artifact Hub response")?;
         Ok(package)
     }
+
+    /// Searches the Helm charts whose name matches `query`.
+    pub async fn search_packages(&self, query: &str) -> Result<Vec<Package>> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/packages/search", self.base_url),
+            &[("ts_query_web", query), ("kind", "0"), ("limit", "20")],
+        )
+        .context("Invalid Artifact Hub URL")?;
+
+        let resp = self
+            .client
+            .get(url)
+            .header("User-Agent", "vesshelm")
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .context("Failed to query Artifact Hub API")?;
+
+        if !resp.status().is_success() {
+            anyhow::bail!("Artifact Hub API error: {}", resp.status());
+        }
+
+        let results: SearchResults = resp
+            .json()
+            .await
+            .context("Failed to parse Artifact Hub response")?;
+        Ok(results.packages)
+    }
 }
 
 #[cfg(test)]
@@ -130,6 +164,44 @@ mod tests {
         assert_eq!(pkg.repository.url, "https://charts.example.com");
     }
 
+    #[tokio::test]
+    async fn test_search_packages() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = thread::spawn(move || {
+            let mut stream = listener.accept().unwrap().0;
+            let mut buffer = [0; 1024];
+            let n = stream.read(&mut buffer).unwrap();
+            let request = String::from_utf8_lossy(&buffer[..n]).to_string();
+
+            let response_body = r#"{"packages": [{
+                "name": "redis",
+                "version": "19.0.0",
+                "repository": {"name": "bitnami", "url": "https://charts.bitnami.com/bitnami"}
+            }]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                response_body.len(),
+                response_body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+            request
+        });
+
+        let client = ArtifactHubClient::with_base_url(&format!("http://127.0.0.1:{}", port));
+        let packages = client.search_packages("redis").await.unwrap();
+
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].repository.name, "bitnami");
+        assert!(
+            handle
+                .join()
+                .unwrap()
+                .starts_with("GET /packages/search?ts_query_web=redis&kind=0&limit=20 ")
+        );
+    }
+
     #[tokio::test]
     async fn test_get_package_details_not_found() {
         let listener = TcpListener::bind("127.0.0.1:0").unwrap();
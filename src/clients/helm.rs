@@ -1,20 +1,116 @@
 use anyhow::{Context, Result};
 #[cfg(test)]
 use mockall::automock;
-use std::path::Path;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 #[cfg_attr(test, automock)]
 pub trait HelmClient {
     fn repo_add(&self, name: &str, url: &str) -> Result<()>;
-    fn repo_update(&self) -> Result<()>;
+    fn repo_update(&self, names: &[String]) -> Result<()>;
     fn pull(&self, repo: &str, chart: &str, version: &str, dest_dir: &Path) -> Result<()>;
     fn is_plugin_installed(&self, plugin_name: &str) -> Result<bool>;
     fn install_plugin(&self, plugin_name: &str, url: &str, verify: bool) -> Result<()>;
     fn uninstall(&self, name: &str, namespace: &str) -> Result<()>;
+    fn history(&self, name: &str, namespace: &str) -> Result<Vec<ReleaseRevision>>;
+    fn rollback(&self, name: &str, namespace: &str, revision: Option<u32>) -> Result<()>;
+    fn list_releases(&self) -> Result<Vec<HelmRelease>>;
+    fn get_values(&self, name: &str, namespace: &str) -> Result<serde_yaml_ng::Value>;
+    fn search_repo(&self, keyword: &str) -> Result<Vec<ChartSearchResult>>;
 }
 
-pub struct RealHelmClient;
+/// A single entry of `helm history`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ReleaseRevision {
+    pub revision: u32,
+    #[serde(default)]
+    pub updated: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub chart: String,
+    #[serde(default)]
+    pub app_version: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// A release as reported by `helm list`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HelmRelease {
+    pub name: String,
+    pub namespace: String,
+    #[serde(default)]
+    pub revision: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub chart: String,
+    #[serde(default)]
+    pub app_version: String,
+}
+
+impl HelmRelease {
+    /// Chart version extracted from the `<chart>-<version>` reference reported by helm.
+    pub fn chart_version(&self) -> Option<&str> {
+        chart_version_from_ref(&self.chart)
+    }
+
+    /// Whether the release is installed: `helm list --all` also returns releases uninstalled
+    /// with `--keep-history` and superseded ones.
+    pub fn is_installed(&self) -> bool {
+        matches!(self.status.as_str(), "deployed" | "failed") || self.status.starts_with("pending")
+    }
+
+    /// Chart name extracted from the `<chart>-<version>` reference reported by helm.
+    pub fn chart_name(&self) -> Option<&str> {
+        let version = self.chart_version()?;
+        Some(&self.chart[..self.chart.len() - version.len() - 1])
+    }
+}
+
+/// A chart version as reported by `helm search repo`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ChartSearchResult {
+    /// `<repository>/<chart>`
+    pub name: String,
+    pub version: String,
+}
+
+/// Splits a `<chart>-<version>` reference on the first dash followed by a version
+/// (a digit, optionally prefixed with `v`).
+fn chart_version_from_ref(chart_ref: &str) -> Option<&str> {
+    chart_ref
+        .match_indices('-')
+        .map(|(i, _)| &chart_ref[i + 1..])
+        .find(|rest| {
+            let rest = rest.strip_prefix('v').unwrap_or(rest);
+            rest.starts_with(|c: char| c.is_ascii_digit())
+        })
+}
+
+/// Helm client backed by the local `helm` binary.
+///
+/// When built with [`RealHelmClient::with_state_dir`], helm runs with a project-local
+/// `HELM_REPOSITORY_CONFIG` and `HELM_REPOSITORY_CACHE`, so repositories registered by the
+/// user globally never leak into vesshelm operations.
+pub struct RealHelmClient {
+    repository_config: Option<PathBuf>,
+    repository_cache: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct RepositoryFile {
+    #[serde(default)]
+    repositories: Option<Vec<RepositoryEntry>>,
+}
+
+#[derive(Deserialize)]
+struct RepositoryEntry {
+    name: String,
+    url: String,
+}
 
 impl Default for RealHelmClient {
     fn default() -> Self {
@@ -24,13 +120,73 @@ impl Default for RealHelmClient {
 
 impl RealHelmClient {
     pub fn new() -> Self {
-        Self
+        Self {
+            repository_config: None,
+            repository_cache: None,
+        }
+    }
+
+    /// Creates a client using the repository configuration and cache stored in `state_dir`.
+    pub fn with_state_dir(state_dir: &Path) -> Self {
+        Self {
+            repository_config: Some(state_dir.join("repositories.yaml")),
+            repository_cache: Some(state_dir.join("repository-cache")),
+        }
+    }
+
+    /// Returns a `helm` command with the project-local repository environment applied.
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new("helm");
+        if let Some(config) = &self.repository_config {
+            cmd.env("HELM_REPOSITORY_CONFIG", config);
+        }
+        if let Some(cache) = &self.repository_cache {
+            cmd.env("HELM_REPOSITORY_CACHE", cache);
+        }
+        cmd
+    }
+
+    /// Looks up the URL of a repository already registered in the project-local configuration.
+    fn registered_repository_url(&self, name: &str) -> Result<Option<String>> {
+        let path = match &self.repository_config {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read helm repository config {:?}", path))?;
+        let file: RepositoryFile = serde_yaml_ng::from_str(&content)
+            .with_context(|| format!("Failed to parse helm repository config {:?}", path))?;
+
+        Ok(file
+            .repositories
+            .unwrap_or_default()
+            .into_iter()
+            .find(|r| r.name == name)
+            .map(|r| r.url))
     }
 }
 
+fn same_repository_url(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
 impl HelmClient for RealHelmClient {
     fn repo_add(&self, name: &str, url: &str) -> Result<()> {
-        let output = Command::new("helm")
+        if let Some(registered_url) = self.registered_repository_url(name)? {
+            if same_repository_url(&registered_url, url) {
+                return Ok(());
+            }
+            anyhow::bail!(
+                "Helm repository '{}' is already registered with URL {} but vesshelm.yaml declares {}",
+                name,
+                registered_url,
+                url
+            );
+        }
+
+        let output = self
+            .command()
             .arg("repo")
             .arg("add")
             .arg(name)
@@ -39,20 +195,25 @@ impl HelmClient for RealHelmClient {
             .context("Failed to execute helm repo add")?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            if stderr.contains("already exists") {
-                return Ok(());
-            } else {
-                anyhow::bail!("Failed to add helm repo {}: {}", name, stderr);
-            }
+            anyhow::bail!(
+                "Failed to add helm repo {}: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr)
+            );
         }
         Ok(())
     }
 
-    fn repo_update(&self) -> Result<()> {
-        let output = Command::new("helm")
+    fn repo_update(&self, names: &[String]) -> Result<()> {
+        if names.is_empty() {
+            return Ok(());
+        }
+
+        let output = self
+            .command()
             .arg("repo")
             .arg("update")
+            .args(names)
             .output()
             .context("Failed to execute helm repo update")?;
 
@@ -66,7 +227,7 @@ impl HelmClient for RealHelmClient {
     }
 
     fn pull(&self, repo: &str, chart: &str, version: &str, dest_dir: &Path) -> Result<()> {
-        let mut cmd = Command::new("helm");
+        let mut cmd = self.command();
         cmd.arg("pull")
             .arg(format!("{}/{}", repo, chart))
             .arg("--version")
@@ -87,7 +248,8 @@ impl HelmClient for RealHelmClient {
     }
 
     fn is_plugin_installed(&self, plugin_name: &str) -> Result<bool> {
-        let output = Command::new("helm")
+        let output = self
+            .command()
             .arg("plugin")
             .arg("list")
             .output()
@@ -106,7 +268,7 @@ impl HelmClient for RealHelmClient {
     }
 
     fn install_plugin(&self, plugin_name: &str, url: &str, verify: bool) -> Result<()> {
-        let mut cmd = Command::new("helm");
+        let mut cmd = self.command();
         cmd.arg("plugin").arg("install");
 
         if !verify {
@@ -130,7 +292,8 @@ impl HelmClient for RealHelmClient {
     }
 
     fn uninstall(&self, name: &str, namespace: &str) -> Result<()> {
-        let output = Command::new("helm")
+        let output = self
+            .command()
             .arg("uninstall")
             .arg(name)
             .arg("-n")
@@ -147,4 +310,189 @@ impl HelmClient for RealHelmClient {
         }
         Ok(())
     }
+
+    fn history(&self, name: &str, namespace: &str) -> Result<Vec<ReleaseRevision>> {
+        let output = self
+            .command()
+            .arg("history")
+            .arg(name)
+            .arg("-n")
+            .arg(namespace)
+            .arg("--output")
+            .arg("yaml")
+            .output()
+            .context("Failed to execute helm history")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to get history of release {}: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        serde_yaml_ng::from_slice(&output.stdout).context("Failed to parse helm history output")
+    }
+
+    fn rollback(&self, name: &str, namespace: &str, revision: Option<u32>) -> Result<()> {
+        let mut cmd = self.command();
+        cmd.arg("rollback").arg(name);
+        if let Some(revision) = revision {
+            cmd.arg(revision.to_string());
+        }
+        cmd.arg("-n").arg(namespace);
+
+        let output = cmd.output().context("Failed to execute helm rollback")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to roll back release {}: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(())
+    }
+
+    fn list_releases(&self) -> Result<Vec<HelmRelease>> {
+        let output = self
+            .command()
+            .arg("list")
+            .arg("--all-namespaces")
+            .arg("--all")
+            // Without it helm stops at its default page size
+            .arg("--max")
+            .arg("0")
+            .arg("--output")
+            .arg("yaml")
+            .output()
+            .context("Failed to execute helm list")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to list helm releases: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        serde_yaml_ng::from_slice(&output.stdout).context("Failed to parse helm list output")
+    }
+
+    fn get_values(&self, name: &str, namespace: &str) -> Result<serde_yaml_ng::Value> {
+        let output = self
+            .command()
+            .arg("get")
+            .arg("values")
+            .arg(name)
+            .arg("-n")
+            .arg(namespace)
+            .arg("--output")
+            .arg("yaml")
+            .output()
+            .context("Failed to execute helm get values")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to get values of release {}: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        serde_yaml_ng::from_slice(&output.stdout).context("Failed to parse helm get values output")
+    }
+
+    fn search_repo(&self, keyword: &str) -> Result<Vec<ChartSearchResult>> {
+        let output = self
+            .command()
+            .arg("search")
+            .arg("repo")
+            .arg(keyword)
+            .arg("--versions")
+            .arg("--output")
+            .arg("yaml")
+            .output()
+            .context("Failed to execute helm search")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to search helm repositories: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        serde_yaml_ng::from_slice(&output.stdout).context("Failed to parse helm search output")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_chart_name() {
+        let release = HelmRelease {
+            name: "certs".to_string(),
+            namespace: "cert-manager".to_string(),
+            revision: "1".to_string(),
+            status: "deployed".to_string(),
+            chart: "cert-manager-v1.14.0".to_string(),
+            app_version: "v1.14.0".to_string(),
+        };
+        assert_eq!(release.chart_name(), Some("cert-manager"));
+        assert_eq!(release.chart_version(), Some("v1.14.0"));
+    }
+
+    #[test]
+    fn test_chart_version_from_ref() {
+        assert_eq!(chart_version_from_ref("nginx-1.2.3"), Some("1.2.3"));
+        assert_eq!(
+            chart_version_from_ref("cert-manager-v1.14.0"),
+            Some("v1.14.0")
+        );
+        assert_eq!(
+            chart_version_from_ref("kube-prometheus-stack-56.0.0-rc.1"),
+            Some("56.0.0-rc.1")
+        );
+        assert_eq!(chart_version_from_ref("nginx"), None);
+    }
+
+    #[test]
+    fn test_registered_repository_url() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("repositories.yaml"),
+            "apiVersion: \"\"\nrepositories:\n- name: bitnami\n  url: https://mirror.example.com/bitnami\n",
+        )
+        .unwrap();
+
+        let client = RealHelmClient::with_state_dir(dir.path());
+        assert_eq!(
+            client.registered_repository_url("bitnami").unwrap(),
+            Some("https://mirror.example.com/bitnami".to_string())
+        );
+        assert_eq!(client.registered_repository_url("stable").unwrap(), None);
+    }
+
+    #[test]
+    fn test_repo_add_url_mismatch_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("repositories.yaml"),
+            "repositories:\n- name: bitnami\n  url: https://mirror.example.com/bitnami\n",
+        )
+        .unwrap();
+
+        let client = RealHelmClient::with_state_dir(dir.path());
+        // Same URL (modulo trailing slash) is already registered: nothing to do
+        assert!(
+            client
+                .repo_add("bitnami", "https://mirror.example.com/bitnami/")
+                .is_ok()
+        );
+
+        let err = client
+            .repo_add("bitnami", "https://charts.bitnami.com/bitnami")
+            .unwrap_err();
+        assert!(err.to_string().contains("already registered"));
+    }
 }
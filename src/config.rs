@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 use serde_yaml_ng::Value;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::{borrow::Cow, collections::HashSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet},
+};
 use validator::{Validate, ValidationError};
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
@@ -16,25 +19,50 @@ pub struct Config {
     #[validate(nested)]
     pub destinations: Vec<Destination>,
     pub vesshelm: Option<VesshelmConfig>,
-    #[serde(alias = "variable_files")]
+    #[serde(alias = "variable_files", skip_serializing_if = "Option::is_none")]
     pub variables_files: Option<Vec<String>>,
-    #[serde(alias = "secret_files")]
+    #[serde(alias = "secret_files", skip_serializing_if = "Option::is_none")]
     pub secrets_files: Option<Vec<String>>,
+    /// Guardrails checked on the rendered manifests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policies: Option<Policies>,
 }
 
 impl Config {
     pub fn load_from_path(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read configuration file: {:?}", path))?;
-        let config: Config = serde_yaml_ng::from_str(&content)?;
+        let mut config: Config = serde_yaml_ng::from_str(&content)?;
 
         // validate config on load
         config
             .validate()
             .context("Configuration validation failed")?;
+        config.assign_chart_dirs();
         Ok(config)
     }
 
+    /// Gives a version-specific directory to remote charts configured at several versions in
+    /// the same destination, so the instances do not overwrite each other.
+    pub fn assign_chart_dirs(&mut self) {
+        let versioned: Vec<bool> = self
+            .charts
+            .iter()
+            .map(|chart| {
+                chart.repo_name.is_some()
+                    && self.charts.iter().any(|other| {
+                        other.name == chart.name
+                            && other.repo_name == chart.repo_name
+                            && other.dest == chart.dest
+                            && other.version != chart.version
+                    })
+            })
+            .collect();
+        for (chart, versioned) in self.charts.iter_mut().zip(versioned) {
+            chart.versioned_dir = versioned;
+        }
+    }
+
     pub fn resolve_chart_destination(&self, chart: &Chart) -> Result<PathBuf> {
         match &chart.dest {
             Some(dest_val) => {
@@ -65,16 +93,117 @@ pub struct VesshelmConfig {
     pub helm_args: String,
     #[serde(default = "default_true")]
     pub diff_enabled: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub diff_args: Option<String>,
     #[serde(default = "default_true")]
     pub deploy_debug_pause: bool,
+    #[serde(default)]
+    pub diff_engine: DiffEngine,
+    /// Default hooks for charts not defining their own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Hooks>,
+}
+
+/// Shell commands run around the deployment of a chart.
+///
+/// Each phase defined on a chart replaces the global phase of `vesshelm.hooks`.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Hooks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_deploy: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_deploy: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<Vec<String>>,
+}
+
+/// Engine computing the changes of a release before deployment.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffEngine {
+    /// `helm diff` provided by the helm-diff plugin (uses `diff_args`).
+    #[default]
+    Plugin,
+    /// Built-in comparison of `helm template` with `helm get manifest`.
+    Native,
+}
+
+/// Rules the rendered manifests of every chart must follow.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Policies {
+    #[serde(default)]
+    pub builtin: BuiltinPolicies,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PolicyRule>,
+}
+
+/// Level of each built-in rule, all enabled as errors by default.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct BuiltinPolicies {
+    /// Container images must be pinned to a tag other than `latest` (or a digest).
+    #[serde(default)]
+    pub no_latest_tag: PolicyLevel,
+    /// Containers must set CPU and memory limits.
+    #[serde(default)]
+    pub require_limits: PolicyLevel,
+    /// Containers must not run privileged.
+    #[serde(default)]
+    pub no_privileged: PolicyLevel,
+    /// Pods must not mount `hostPath` volumes.
+    #[serde(default)]
+    pub no_host_path: PolicyLevel,
+}
+
+/// What a violated rule does: `error` blocks the deployment, `warn` only reports.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyLevel {
+    #[default]
+    Error,
+    Warn,
+    Off,
+}
+
+/// User rule asserting `<path> <operator> <value>` on every matching object.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PolicyRule {
+    pub name: String,
+    /// Object kinds the rule applies to, all kinds when omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kinds: Option<Vec<String>>,
+    /// Dotted path in the object, `[*]` iterating over a list (`spec.template.spec.containers[*].image`).
+    pub path: String,
+    pub operator: PolicyOperator,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(default)]
+    pub level: PolicyLevel,
+    /// Reported instead of the generated description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyOperator {
+    Equals,
+    NotEquals,
+    Matches,
+    NotMatches,
+    Exists,
+    NotExists,
+    GreaterThan,
+    LessThan,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
 fn validate_config(config: &Config) -> Result<(), ValidationError> {
     let repo_names: HashSet<&String> = config.repositories.iter().map(|r| &r.name).collect();
     let dest_names: HashSet<&String> = config.destinations.iter().map(|d| &d.name).collect();
@@ -109,14 +238,35 @@ fn validate_config(config: &Config) -> Result<(), ValidationError> {
         }
     }
 
+    // check policy rules are complete
+    for rule in config.policies.iter().flat_map(|p| &p.rules) {
+        let needs_value = !matches!(
+            rule.operator,
+            PolicyOperator::Exists | PolicyOperator::NotExists
+        );
+        let valid = match (&rule.operator, &rule.value) {
+            (PolicyOperator::Matches | PolicyOperator::NotMatches, Some(Value::String(re))) => {
+                regex::Regex::new(re).is_ok()
+            }
+            (PolicyOperator::Matches | PolicyOperator::NotMatches, _) => false,
+            (PolicyOperator::GreaterThan | PolicyOperator::LessThan, Some(v)) => v.is_number(),
+            (_, value) => value.is_some() == needs_value,
+        };
+        if !valid {
+            let mut err = ValidationError::new("invalid_policy_rule");
+            err.add_param(Cow::from("name"), &rule.name);
+            return Err(err);
+        }
+    }
+
     // check duplicates charts
     let mut seen_charts = HashSet::new();
 
     for chart in &config.charts {
-        let chart_key = (&chart.name, &chart.namespace);
+        let chart_key = (chart.release_name(), &chart.namespace);
         if !seen_charts.insert(chart_key) {
             let mut err = ValidationError::new("duplicate_chart_name_namespace");
-            err.add_param(Cow::from("name"), &chart.name);
+            err.add_param(Cow::from("name"), &chart.release_name());
             err.add_param(Cow::from("namespace"), &chart.namespace);
             return Err(err);
         }
@@ -165,7 +315,7 @@ pub struct Repository {
     pub name: String,
     #[validate(custom(function = "validate_url_scheme"))]
     pub url: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "RepoType::is_helm")]
     pub r#type: RepoType,
 }
 
@@ -178,28 +328,78 @@ pub enum RepoType {
     Oci,
 }
 
-#[derive(Debug, Deserialize, Serialize, Validate)]
+impl RepoType {
+    fn is_helm(&self) -> bool {
+        *self == RepoType::Helm
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Validate)]
 pub struct Chart {
     #[validate(length(min = 1))]
     pub name: String,
+    /// Helm release name, defaults to `name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub repo_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
     pub namespace: String,
-    #[serde(alias = "destination_override")]
+    #[serde(
+        alias = "destination_override",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub dest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub chart_path: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub no_sync: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub no_deploy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub values_files: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub helm_args_append: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub helm_args_override: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub values: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub depends: Option<Vec<String>>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub no_interpolation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Hooks>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub test_after_deploy: bool,
+    /// Free-form labels used by `--selector`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<BTreeMap<String, String>>,
+    /// Key paths of free-form maps whose keys `validate --values` does not check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_form_values: Option<Vec<String>>,
+    /// Set on load when other instances of the chart use other versions in the same destination.
+    #[serde(skip)]
+    pub versioned_dir: bool,
+}
+
+impl Chart {
+    /// Name of the helm release, which also identifies the chart in `depends` and on the command line.
+    pub fn release_name(&self) -> &str {
+        self.release.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Directory of the chart under its destination: `<name>`, or `<name>-<version>` when
+    /// several versions of the chart share the destination.
+    pub fn dir_name(&self) -> String {
+        match (&self.version, self.versioned_dir) {
+            (Some(version), true) => format!("{}-{}", self.name, version),
+            _ => self.name.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
@@ -0,0 +1,190 @@
+//! Local audit log of deployments, stored as JSON lines in the state directory.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const AUDIT_LOG_FILE: &str = "audit.jsonl";
+
+/// A deployment of a chart, as recorded in the audit log.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct AuditRecord {
+    /// RFC 3339 UTC timestamp of the end of the deployment.
+    pub timestamp: String,
+    pub user: String,
+    pub hostname: String,
+    /// Commit checked out in the repository holding the configuration, if any.
+    pub git_head: Option<String>,
+    /// Whether tracked files of that repository had uncommitted changes.
+    pub git_dirty: bool,
+    pub chart: String,
+    pub version: Option<String>,
+    pub namespace: String,
+    /// SHA-256 of the final values files passed to helm, in order.
+    pub values_hash: Option<String>,
+    /// `deployed`, `skipped`, `ignored` or `failed`.
+    pub status: String,
+    pub duration_ms: u128,
+    pub error: Option<String>,
+}
+
+/// Information about the environment shared by every record of a run.
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub user: String,
+    pub hostname: String,
+    pub git_head: Option<String>,
+    pub git_dirty: bool,
+}
+
+impl AuditContext {
+    /// Collects the current user, host and git state of the repository containing `config_path`.
+    pub fn collect(config_path: &Path) -> Self {
+        let config_dir = config_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let (git_head, git_dirty) = git_state(config_dir).unwrap_or((None, false));
+
+        Self {
+            user: whoami::username(),
+            hostname: whoami::fallible::hostname().unwrap_or_else(|_| "unknown".to_string()),
+            git_head,
+            git_dirty,
+        }
+    }
+}
+
+fn git_state(dir: &Path) -> Option<(Option<String>, bool)> {
+    let repo = git2::Repository::discover(dir).ok()?;
+    let head = repo
+        .head()
+        .ok()
+        .and_then(|h| h.peel_to_commit().ok())
+        .map(|c| c.id().to_string());
+
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let dirty = repo
+        .statuses(Some(&mut options))
+        .map(|s| !s.is_empty())
+        .unwrap_or(false);
+
+    Some((head, dirty))
+}
+
+/// Hashes the content of the values files, in the order given to helm.
+pub fn values_hash(files: &[PathBuf]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for file in files {
+        let content = fs::read(file).with_context(|| format!("Failed to read {:?}", file))?;
+        hasher.update(&content);
+        hasher.update([0]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Current time as an RFC 3339 UTC timestamp.
+pub fn now_timestamp() -> String {
+    time::OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default()
+}
+
+fn log_path(state_dir: &Path) -> PathBuf {
+    state_dir.join(AUDIT_LOG_FILE)
+}
+
+/// Appends a record to the audit log.
+pub fn append(state_dir: &Path, record: &AuditRecord) -> Result<()> {
+    fs::create_dir_all(state_dir).with_context(|| format!("Failed to create {:?}", state_dir))?;
+    let path = log_path(state_dir);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    let line = serde_json::to_string(record).context("Failed to serialize audit record")?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Reads every record of the audit log, oldest first.
+pub fn load(state_dir: &Path) -> Result<Vec<AuditRecord>> {
+    let path = log_path(state_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse {:?} line {}", path, i + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(chart: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: now_timestamp(),
+            user: "alice".to_string(),
+            hostname: "laptop".to_string(),
+            git_head: Some("abc123".to_string()),
+            git_dirty: false,
+            chart: chart.to_string(),
+            version: Some("1.0.0".to_string()),
+            namespace: "default".to_string(),
+            values_hash: None,
+            status: "deployed".to_string(),
+            duration_ms: 42,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().join(".vesshelm");
+        assert!(load(&state_dir).unwrap().is_empty());
+
+        append(&state_dir, &record("api")).unwrap();
+        append(&state_dir, &record("web")).unwrap();
+
+        let records = load(&state_dir).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].chart, "api");
+        assert_eq!(records[1].chart, "web");
+    }
+
+    #[test]
+    fn test_values_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.yaml");
+        let b = dir.path().join("b.yaml");
+        fs::write(&a, "replicas: 1\n").unwrap();
+        fs::write(&b, "replicas: 2\n").unwrap();
+
+        let ab = values_hash(&[a.clone(), b.clone()]).unwrap();
+        assert_eq!(ab.len(), 64);
+        assert_eq!(ab, values_hash(&[a.clone(), b.clone()]).unwrap());
+        // Order matters, as with helm
+        assert_ne!(ab, values_hash(&[b, a]).unwrap());
+    }
+
+    #[test]
+    fn test_timestamp_is_rfc3339() {
+        let timestamp = now_timestamp();
+        assert!(timestamp.ends_with('Z'));
+        assert!(timestamp.contains('T'));
+    }
+}
@@ -0,0 +1,545 @@
+//! Air-gap bundles: a gzipped tarball of the configuration, the lockfile, the chart directories
+//! and the values, variables and secrets files, with the digest of every file.
+
+use crate::config::{Chart, Config};
+use anyhow::{Context, Result, anyhow, bail};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Manifest of the bundle, stored at its root.
+const MANIFEST_FILE: &str = "bundle.yaml";
+/// Name of the configuration file in the bundle.
+const CONFIG_FILE: &str = "vesshelm.yaml";
+const LOCK_FILE: &str = "vesshelm.lock";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BundleManifest {
+    pub created: String,
+    pub charts: Vec<BundledChart>,
+    pub files: Vec<BundledFile>,
+}
+
+/// Chart instance whose directory is in the bundle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundledChart {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+    pub namespace: String,
+    /// Repository the chart was synced from, unset for local charts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_name: Option<String>,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundledFile {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Packs the project found under `base` (the directory the paths of the configuration are
+/// relative to) into `output`.
+///
+/// Every remote chart must be synced, except the `no_sync` ones which are bundled when present.
+/// Files are bundled as they are on disk, so SOPS-encrypted files stay encrypted.
+pub fn create(
+    config: &Config,
+    config_path: &Path,
+    base: &Path,
+    output: &Path,
+) -> Result<BundleManifest> {
+    // Path in the bundle -> file on disk
+    let mut sources: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+    sources.insert(PathBuf::from(CONFIG_FILE), config_path.to_path_buf());
+    if base.join(LOCK_FILE).exists() {
+        sources.insert(PathBuf::from(LOCK_FILE), base.join(LOCK_FILE));
+    }
+
+    let mut charts = Vec::new();
+    for chart in &config.charts {
+        let Some(dir) = chart_directory(config, chart)? else {
+            continue;
+        };
+        let bundled = bundle_path(&dir)?;
+        let source = base.join(&bundled);
+        if !source.is_dir() {
+            match (&chart.repo_name, chart.no_sync) {
+                (Some(_), true) => continue,
+                (Some(_), false) => bail!(
+                    "Chart '{}' is not synced ({} not found), run `vesshelm sync` first",
+                    chart.release_name(),
+                    dir
+                ),
+                (None, _) => bail!(
+                    "Chart directory {} of '{}' not found",
+                    dir,
+                    chart.release_name()
+                ),
+            }
+        }
+        for entry in walkdir::WalkDir::new(&source).sort_by_file_name() {
+            let entry = entry?;
+            if entry.path().is_file() {
+                let relative = entry.path().strip_prefix(base)?;
+                sources.insert(relative.to_path_buf(), entry.path().to_path_buf());
+            }
+        }
+        charts.push(BundledChart {
+            name: chart.name.clone(),
+            release: chart.release.clone(),
+            namespace: chart.namespace.clone(),
+            repo_name: chart.repo_name.clone(),
+            path: path_string(&bundled),
+        });
+    }
+
+    let files = config
+        .charts
+        .iter()
+        .flat_map(|chart| chart.values_files.iter().flatten())
+        .chain(config.variables_files.iter().flatten())
+        .chain(config.secrets_files.iter().flatten());
+    for file in files {
+        let bundled = bundle_path(file)?;
+        sources.insert(bundled.clone(), base.join(bundled));
+    }
+
+    let mut manifest = BundleManifest {
+        created: crate::engine::audit::now_timestamp(),
+        charts,
+        files: Vec::new(),
+    };
+    for (bundled, source) in &sources {
+        manifest.files.push(BundledFile {
+            path: path_string(bundled),
+            sha256: file_digest(source)?,
+        });
+    }
+
+    let archive = fs::File::create(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(archive, Compression::default()));
+
+    let manifest_content =
+        serde_yaml_ng::to_string(&manifest).context("Failed to serialize the bundle manifest")?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_FILE, manifest_content.as_bytes())?;
+
+    for (bundled, source) in &sources {
+        builder
+            .append_path_with_name(source, bundled)
+            .with_context(|| format!("Failed to add {} to the bundle", source.display()))?;
+    }
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    Ok(manifest)
+}
+
+/// Unpacks `archive` under `base` after checking the digest of every file, then marks the
+/// bundled remote charts `no_sync` so `sync` does not reach their repositories.
+///
+/// The configuration is written to `config_path`, which must not exist unless `force` is set.
+/// Chart directories are replaced; nothing is written when a check fails.
+pub fn import(
+    archive: &Path,
+    config_path: &Path,
+    base: &Path,
+    force: bool,
+) -> Result<BundleManifest> {
+    if config_path.exists() && !force {
+        bail!(
+            "Configuration file {:?} already exists, use --force to overwrite it",
+            config_path
+        );
+    }
+
+    let staging = tempfile::Builder::new()
+        .prefix("vesshelm-bundle-")
+        .tempdir()
+        .context("Failed to create temporary directory for the bundle")?;
+    unpack(archive, staging.path())?;
+
+    let manifest = verify(staging.path())?;
+
+    for chart in &manifest.charts {
+        let dir = base.join(bundle_path(&chart.path)?);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to remove {}", dir.display()))?;
+        }
+    }
+    for file in &manifest.files {
+        let target = match file.path.as_str() {
+            CONFIG_FILE => config_path.to_path_buf(),
+            path => base.join(bundle_path(path)?),
+        };
+        if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::copy(staging.path().join(&file.path), &target)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+    }
+
+    for chart in manifest.charts.iter().filter(|c| c.repo_name.is_some()) {
+        crate::util::config_updater::ConfigUpdater::set_chart_no_sync(
+            config_path,
+            &chart.name,
+            chart.release.as_deref(),
+            &chart.namespace,
+        )?;
+    }
+    Ok(manifest)
+}
+
+/// Unpacks `archive` into `dir`, rejecting any entry but regular files and directories so a
+/// link can never point outside of it.
+fn unpack(archive: &Path, dir: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let mut tarball = tar::Archive::new(GzDecoder::new(file));
+    let entries = tarball
+        .entries()
+        .with_context(|| format!("Failed to unpack {}", archive.display()))?;
+    for entry in entries {
+        let mut entry = entry.with_context(|| format!("Failed to unpack {}", archive.display()))?;
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            bail!(
+                "Bundle contains {} which is not a regular file",
+                entry.path()?.display()
+            );
+        }
+        entry
+            .unpack_in(dir)
+            .with_context(|| format!("Failed to unpack {}", archive.display()))?;
+    }
+    Ok(())
+}
+
+/// Reads the manifest of an unpacked bundle and checks it lists exactly its files, with their
+/// digests. Symlinks are rejected rather than followed.
+fn verify(root: &Path) -> Result<BundleManifest> {
+    let content = fs::read_to_string(root.join(MANIFEST_FILE))
+        .map_err(|_| anyhow!("Not a vesshelm bundle: {} is missing", MANIFEST_FILE))?;
+    let manifest: BundleManifest =
+        serde_yaml_ng::from_str(&content).context("Failed to parse the bundle manifest")?;
+
+    let mut listed = std::collections::HashSet::new();
+    for file in &manifest.files {
+        let path = root.join(bundle_path(&file.path)?);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {}
+            Ok(_) => bail!("Bundle contains {} which is not a regular file", file.path),
+            Err(_) => bail!("Bundle is missing {}", file.path),
+        }
+        if file_digest(&path)? != file.sha256 {
+            bail!("Digest mismatch for {}", file.path);
+        }
+        listed.insert(path);
+    }
+    for entry in walkdir::WalkDir::new(root) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() && !entry.file_type().is_dir() {
+            bail!(
+                "Bundle contains {} which is not a regular file",
+                path.strip_prefix(root)?.display()
+            );
+        }
+        if entry.file_type().is_file() && path != root.join(MANIFEST_FILE) && !listed.contains(path)
+        {
+            bail!(
+                "Bundle contains {} which is not in its manifest",
+                path.strip_prefix(root)?.display()
+            );
+        }
+    }
+    Ok(manifest)
+}
+
+/// Directory of a chart relative to the project: its synced directory for remote charts,
+/// `chart_path` for local charts.
+fn chart_directory(config: &Config, chart: &Chart) -> Result<Option<String>> {
+    if chart.repo_name.is_some() {
+        let dest = config.resolve_chart_destination(chart)?;
+        return Ok(Some(path_string(&dest.join(chart.dir_name()))));
+    }
+    Ok(chart.chart_path.clone())
+}
+
+/// Path of a project file in the bundle, which must stay inside the project.
+fn bundle_path(path: &str) -> Result<PathBuf> {
+    let mut bundled = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => bundled.push(part),
+            Component::CurDir => {}
+            _ => bail!(
+                "Cannot bundle {}: paths must be relative to the project and stay inside it",
+                path
+            ),
+        }
+    }
+    if bundled.as_os_str().is_empty() {
+        bail!("Cannot bundle the project directory itself");
+    }
+    Ok(bundled)
+}
+
+/// Bundle paths always use `/`, whatever the platform.
+fn path_string(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn file_digest(path: &Path) -> Result<String> {
+    let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(&content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CONFIG: &str = r#"repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+charts:
+  - name: nginx
+    repo_name: stable
+    version: 1.0.0
+    namespace: web
+    values_files:
+      - ./values/nginx.yaml
+  - name: local-tool
+    chart_path: local/tool
+    namespace: tools
+destinations:
+  - name: default
+    path: ./charts
+variables_files:
+  - vars.yaml
+secrets_files:
+  - secrets.yaml
+"#;
+
+    fn project() -> (TempDir, Config) {
+        let dir = TempDir::new().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("vesshelm.yaml", CONFIG);
+        write("vesshelm.lock", "charts: []\n");
+        write("charts/nginx/Chart.yaml", "name: nginx\nversion: 1.0.0\n");
+        write(
+            "charts/nginx/templates/deployment.yaml",
+            "kind: Deployment\n",
+        );
+        write("local/tool/Chart.yaml", "name: tool\n");
+        write("values/nginx.yaml", "replicas: 2\n");
+        write("vars.yaml", "env: prod\n");
+        write(
+            "secrets.yaml",
+            "password: ENC[AES256_GCM,data:abc]\nsops:\n  version: 3.8.1\n",
+        );
+        let config = serde_yaml_ng::from_str(CONFIG).unwrap();
+        (dir, config)
+    }
+
+    fn paths(manifest: &BundleManifest) -> Vec<&str> {
+        manifest.files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_create_and_import() {
+        let (project, config) = project();
+        let output = project.path().join("out.tar.gz");
+
+        let created = create(
+            &config,
+            &project.path().join("vesshelm.yaml"),
+            project.path(),
+            &output,
+        )
+        .unwrap();
+        assert_eq!(
+            paths(&created),
+            vec![
+                "charts/nginx/Chart.yaml",
+                "charts/nginx/templates/deployment.yaml",
+                "local/tool/Chart.yaml",
+                "secrets.yaml",
+                "values/nginx.yaml",
+                "vars.yaml",
+                "vesshelm.lock",
+                "vesshelm.yaml",
+            ]
+        );
+
+        let site = TempDir::new().unwrap();
+        fs::create_dir_all(site.path().join("charts/nginx/templates")).unwrap();
+        fs::write(site.path().join("charts/nginx/templates/stale.yaml"), "").unwrap();
+
+        let config_path = site.path().join("vesshelm.yaml");
+        let imported = import(&output, &config_path, site.path(), false).unwrap();
+        assert_eq!(imported.files, created.files);
+
+        // Secrets are copied as they are, still encrypted
+        assert_eq!(
+            fs::read_to_string(site.path().join("secrets.yaml")).unwrap(),
+            fs::read_to_string(project.path().join("secrets.yaml")).unwrap()
+        );
+        assert!(
+            site.path()
+                .join("charts/nginx/templates/deployment.yaml")
+                .exists()
+        );
+        assert!(
+            !site
+                .path()
+                .join("charts/nginx/templates/stale.yaml")
+                .exists()
+        );
+
+        // Only the remote chart stops syncing
+        let config: Config =
+            serde_yaml_ng::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert!(config.charts[0].no_sync);
+        assert!(!config.charts[1].no_sync);
+
+        let err = import(&output, &config_path, site.path(), false).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+    }
+
+    #[test]
+    fn test_create_requires_synced_charts() {
+        let (project, config) = project();
+        fs::remove_dir_all(project.path().join("charts/nginx")).unwrap();
+
+        let err = create(
+            &config,
+            &project.path().join("vesshelm.yaml"),
+            project.path(),
+            &project.path().join("out.tar.gz"),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("'nginx' is not synced"));
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_files() {
+        let (project, config) = project();
+        create(
+            &config,
+            &project.path().join("vesshelm.yaml"),
+            project.path(),
+            &project.path().join("out.tar.gz"),
+        )
+        .unwrap();
+
+        let unpacked = TempDir::new().unwrap();
+        unpack(&project.path().join("out.tar.gz"), unpacked.path()).unwrap();
+        assert!(verify(unpacked.path()).is_ok());
+
+        fs::write(unpacked.path().join("extra.yaml"), "").unwrap();
+        let err = verify(unpacked.path()).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("extra.yaml which is not in its manifest")
+        );
+
+        fs::write(unpacked.path().join("values/nginx.yaml"), "replicas: 20\n").unwrap();
+        let err = verify(unpacked.path()).unwrap_err();
+        assert_eq!(err.to_string(), "Digest mismatch for values/nginx.yaml");
+    }
+
+    #[test]
+    fn test_bundle_rejects_symlinks() {
+        let (project, config) = project();
+        create(
+            &config,
+            &project.path().join("vesshelm.yaml"),
+            project.path(),
+            &project.path().join("out.tar.gz"),
+        )
+        .unwrap();
+
+        // A listed file replaced by a link to an identical file outside of the bundle
+        let unpacked = TempDir::new().unwrap();
+        unpack(&project.path().join("out.tar.gz"), unpacked.path()).unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("nginx.yaml"), "replicas: 2\n").unwrap();
+        fs::remove_file(unpacked.path().join("values/nginx.yaml")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("nginx.yaml"),
+            unpacked.path().join("values/nginx.yaml"),
+        )
+        .unwrap();
+        let err = verify(unpacked.path()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Bundle contains values/nginx.yaml which is not a regular file"
+        );
+
+        let archive = project.path().join("link.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            fs::File::create(&archive).unwrap(),
+            Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "values", "/etc").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let site = TempDir::new().unwrap();
+        let err = import(
+            &archive,
+            &site.path().join("vesshelm.yaml"),
+            site.path(),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Bundle contains values which is not a regular file"
+        );
+        assert!(!site.path().join("vesshelm.yaml").exists());
+    }
+
+    #[test]
+    fn test_bundle_path() {
+        assert_eq!(
+            bundle_path("./values/../x.yaml").unwrap_err().to_string(),
+            "Cannot bundle ./values/../x.yaml: paths must be relative to the project and stay inside it"
+        );
+        assert!(bundle_path("/etc/passwd").is_err());
+        assert_eq!(
+            bundle_path("./values/prod.yaml").unwrap(),
+            PathBuf::from("values/prod.yaml")
+        );
+    }
+}
@@ -0,0 +1,592 @@
+//! Conversion of charts to GitOps resources (Argo CD `Application`, Flux `HelmRelease`).
+
+use crate::config::{Chart, Config, RepoType, Repository};
+use crate::util::encryption::is_sops_encrypted;
+use crate::util::helm::merge_yaml_values;
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+use serde_yaml_ng::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Stands for every secret value while rendering values files, so secrets are never inlined.
+const SECRET_PLACEHOLDER: &str = "__vesshelm_secret__";
+
+/// Kubernetes resource with the usual layout.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Resource<S> {
+    pub api_version: &'static str,
+    pub kind: &'static str,
+    pub metadata: Metadata,
+    pub spec: S,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Metadata {
+    pub name: String,
+    pub namespace: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    fn new(name: &str, namespace: &str) -> Self {
+        Metadata {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            annotations: BTreeMap::new(),
+        }
+    }
+}
+
+/// Values of a chart as exported: inlined values and references to secret values files.
+#[derive(Debug, Default, PartialEq)]
+pub struct ExportedValues {
+    /// `values_files` and inline `values` merged, without secrets.
+    pub values: Option<Value>,
+    /// Values files left as references: SOPS-encrypted or using secret variables.
+    pub secret_files: Vec<String>,
+    /// Whether a secret file comes after a plain values file. The references are applied before
+    /// the merged values, so the secret file no longer overrides that plain file.
+    pub secrets_reordered: bool,
+}
+
+/// Builds the context used to render values files, with every secret replaced by a placeholder.
+///
+/// The secrets files are not decrypted: SOPS only encrypts values, keys stay readable.
+pub fn placeholder_context(config: &Config, config_path: &Path) -> Result<Value> {
+    let base_path = config_path.parent().unwrap_or(Path::new("."));
+    let mut context = match &config.variables_files {
+        Some(files) if !files.is_empty() => {
+            crate::util::variables::load_variables(files, base_path)
+                .context("Failed to load variables")?
+        }
+        _ => Value::Null,
+    };
+
+    for file in config.secrets_files.iter().flatten() {
+        let path = base_path.join(file);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read secrets file: {:?}", path))?;
+        let mut secrets: Value = serde_yaml_ng::from_str(&content)
+            .with_context(|| format!("Failed to parse secrets file: {:?}", path))?;
+        if let Value::Mapping(map) = &mut secrets {
+            map.remove("sops");
+        }
+        replace_leaves(&mut secrets);
+        if context.is_null() {
+            context = secrets;
+        } else {
+            merge_yaml_values(&mut context, &secrets)?;
+        }
+    }
+    Ok(context)
+}
+
+fn replace_leaves(value: &mut Value) {
+    match value {
+        Value::Mapping(map) => map.values_mut().for_each(replace_leaves),
+        Value::Sequence(items) => items.iter_mut().for_each(replace_leaves),
+        leaf => *leaf = Value::String(SECRET_PLACEHOLDER.to_string()),
+    }
+}
+
+/// Merges the values files and inline values of a chart, leaving secret files as references.
+pub fn chart_values(chart: &Chart, context: &Value) -> Result<ExportedValues> {
+    let mut exported = ExportedValues::default();
+    let mut layers = Vec::new();
+
+    for file in chart.values_files.iter().flatten() {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read values file {}", file))?;
+        if is_sops_encrypted(&content) {
+            exported.secret_files.push(file.clone());
+            exported.secrets_reordered |= !layers.is_empty();
+            continue;
+        }
+        let rendered = if context.is_null() || chart.no_interpolation {
+            content
+        } else {
+            crate::util::variables::render_template(&content, context, file)
+                .with_context(|| format!("Failed to render values file {}", file))?
+        };
+        if rendered.contains(SECRET_PLACEHOLDER) {
+            exported.secret_files.push(file.clone());
+            exported.secrets_reordered |= !layers.is_empty();
+            continue;
+        }
+        let value: Value = serde_yaml_ng::from_str(&rendered)
+            .with_context(|| format!("Failed to parse values file {}", file))?;
+        layers.push(value);
+    }
+    layers.extend(chart.values.iter().flatten().cloned());
+
+    for layer in layers.into_iter().filter(|v| !v.is_null()) {
+        match &mut exported.values {
+            Some(values) => merge_yaml_values(values, &layer)?,
+            None => exported.values = Some(layer),
+        }
+    }
+    Ok(exported)
+}
+
+/// Name of the resources of a chart: its release name, prefixed with its namespace when the
+/// release name is used in several namespaces.
+pub fn resource_name(chart: &Chart, charts: &[Chart]) -> String {
+    let shared = charts
+        .iter()
+        .filter(|c| c.release_name() == chart.release_name())
+        .count()
+        > 1;
+    if shared {
+        format!("{}-{}", chart.namespace, chart.release_name())
+    } else {
+        chart.release_name().to_string()
+    }
+}
+
+/// Depth of every chart in the dependency graph: 0 without dependencies, else one more than
+/// its deepest dependency. `sorted` must be topologically sorted.
+pub fn dependency_depths(sorted: &[&Chart]) -> HashMap<String, usize> {
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    for chart in sorted {
+        let depth = chart
+            .depends
+            .iter()
+            .flatten()
+            .filter_map(|dep| depths.get(dep))
+            .map(|d| d + 1)
+            .max()
+            .unwrap_or(0);
+        depths.insert(chart.release_name().to_string(), depth);
+    }
+    depths
+}
+
+fn oci_reference(url: &str) -> &str {
+    url.strip_prefix("oci://").unwrap_or(url)
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSpec {
+    pub project: String,
+    pub source: ApplicationSource,
+    pub destination: ApplicationDestination,
+    pub sync_policy: SyncPolicy,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSource {
+    #[serde(rename = "repoURL")]
+    pub repo_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chart: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub target_revision: String,
+    pub helm: ApplicationHelm,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationHelm {
+    pub release_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values_object: Option<Value>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ApplicationDestination {
+    pub server: String,
+    pub namespace: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPolicy {
+    pub sync_options: Vec<String>,
+}
+
+/// Argo CD `Application` of a chart; dependencies are ordered with sync waves.
+///
+/// Secret values files are not referenced: Argo CD would look for them inside the chart.
+pub fn argocd_application(
+    chart: &Chart,
+    name: &str,
+    repo: &Repository,
+    values: ExportedValues,
+    sync_wave: usize,
+    namespace: &str,
+) -> Result<Resource<ApplicationSpec>> {
+    let version = chart
+        .version
+        .clone()
+        .ok_or_else(|| anyhow!("Chart '{}' has no version", chart.release_name()))?;
+    let (repo_url, chart_name, path) = match repo.r#type {
+        RepoType::Helm => (repo.url.clone(), Some(chart.name.clone()), None),
+        RepoType::Oci => (
+            oci_reference(&repo.url).to_string(),
+            Some(chart.name.clone()),
+            None,
+        ),
+        RepoType::Git => (repo.url.clone(), None, chart.chart_path.clone()),
+    };
+
+    let mut metadata = Metadata::new(name, namespace);
+    metadata.annotations.insert(
+        "argocd.argoproj.io/sync-wave".to_string(),
+        sync_wave.to_string(),
+    );
+
+    Ok(Resource {
+        api_version: "argoproj.io/v1alpha1",
+        kind: "Application",
+        metadata,
+        spec: ApplicationSpec {
+            project: "default".to_string(),
+            source: ApplicationSource {
+                repo_url,
+                chart: chart_name,
+                path,
+                target_revision: version,
+                helm: ApplicationHelm {
+                    release_name: chart.release_name().to_string(),
+                    values_object: values.values,
+                },
+            },
+            destination: ApplicationDestination {
+                server: "https://kubernetes.default.svc".to_string(),
+                namespace: chart.namespace.clone(),
+            },
+            sync_policy: SyncPolicy {
+                sync_options: vec!["CreateNamespace=true".to_string()],
+            },
+        },
+    })
+}
+
+#[derive(Serialize, Debug)]
+pub struct HelmRepositorySpec {
+    pub url: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub repo_type: Option<String>,
+    pub interval: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GitRepositorySpec {
+    pub url: String,
+    #[serde(rename = "ref")]
+    pub reference: GitReference,
+    pub interval: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GitReference {
+    pub tag: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HelmReleaseSpec {
+    pub release_name: String,
+    pub target_namespace: String,
+    pub interval: String,
+    pub chart: HelmChartTemplate,
+    pub install: HelmReleaseInstall,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<DependsOn>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values_from: Vec<ValuesReference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Value>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct HelmChartTemplate {
+    pub spec: HelmChartSpec,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HelmChartSpec {
+    pub chart: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub source_ref: SourceReference,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SourceReference {
+    pub kind: &'static str,
+    pub name: String,
+    pub namespace: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HelmReleaseInstall {
+    pub create_namespace: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DependsOn {
+    pub name: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuesReference {
+    pub kind: &'static str,
+    pub name: String,
+    pub values_key: String,
+}
+
+/// Flux source of a Helm or OCI repository.
+pub fn flux_helm_repository(repo: &Repository, namespace: &str) -> Resource<HelmRepositorySpec> {
+    let (url, repo_type) = match repo.r#type {
+        RepoType::Oci => (
+            format!("oci://{}", oci_reference(&repo.url)),
+            Some("oci".to_string()),
+        ),
+        _ => (repo.url.clone(), None),
+    };
+    Resource {
+        api_version: "source.toolkit.fluxcd.io/v1",
+        kind: "HelmRepository",
+        metadata: Metadata::new(&repo.name, namespace),
+        spec: HelmRepositorySpec {
+            url,
+            repo_type,
+            interval: "1h".to_string(),
+        },
+    }
+}
+
+/// Flux source of a chart taken from a git repository, pinned to the chart version.
+pub fn flux_git_repository(
+    chart: &Chart,
+    name: &str,
+    repo: &Repository,
+    namespace: &str,
+) -> Result<Resource<GitRepositorySpec>> {
+    let version = chart
+        .version
+        .clone()
+        .ok_or_else(|| anyhow!("Chart '{}' has no version", chart.release_name()))?;
+    Ok(Resource {
+        api_version: "source.toolkit.fluxcd.io/v1",
+        kind: "GitRepository",
+        metadata: Metadata::new(name, namespace),
+        spec: GitRepositorySpec {
+            url: repo.url.clone(),
+            reference: GitReference { tag: version },
+            interval: "1h".to_string(),
+        },
+    })
+}
+
+/// Name of the Kubernetes `Secret` expected to hold a secret values file of a release.
+pub fn secret_name(resource_name: &str, file: &str) -> String {
+    let stem = Path::new(file)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("values")
+        .to_lowercase();
+    let stem: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}-{}", resource_name, stem.trim_matches('-'))
+}
+
+/// Flux `HelmRelease` of a chart; secret values files are read from `Secret`s.
+pub fn flux_helm_release(
+    chart: &Chart,
+    name: &str,
+    repo: &Repository,
+    values: ExportedValues,
+    depends_on: Vec<String>,
+    namespace: &str,
+) -> Resource<HelmReleaseSpec> {
+    let (chart_ref, version, source_ref) = match repo.r#type {
+        RepoType::Git => (
+            chart
+                .chart_path
+                .clone()
+                .unwrap_or_else(|| chart.name.clone()),
+            None,
+            SourceReference {
+                kind: "GitRepository",
+                name: name.to_string(),
+                namespace: namespace.to_string(),
+            },
+        ),
+        RepoType::Helm | RepoType::Oci => (
+            chart.name.clone(),
+            chart.version.clone(),
+            SourceReference {
+                kind: "HelmRepository",
+                name: repo.name.clone(),
+                namespace: namespace.to_string(),
+            },
+        ),
+    };
+
+    Resource {
+        api_version: "helm.toolkit.fluxcd.io/v2",
+        kind: "HelmRelease",
+        metadata: Metadata::new(name, namespace),
+        spec: HelmReleaseSpec {
+            release_name: chart.release_name().to_string(),
+            target_namespace: chart.namespace.clone(),
+            interval: "10m".to_string(),
+            chart: HelmChartTemplate {
+                spec: HelmChartSpec {
+                    chart: chart_ref,
+                    version,
+                    source_ref,
+                },
+            },
+            install: HelmReleaseInstall {
+                create_namespace: true,
+            },
+            depends_on: depends_on
+                .into_iter()
+                .map(|name| DependsOn { name })
+                .collect(),
+            values_from: values
+                .secret_files
+                .iter()
+                .map(|file| ValuesReference {
+                    kind: "Secret",
+                    name: secret_name(name, file),
+                    values_key: "values.yaml".to_string(),
+                })
+                .collect(),
+            values: values.values,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(yaml: &str) -> Chart {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_chart_values_keeps_secrets_as_references() {
+        let temp = tempfile::tempdir().unwrap();
+        let plain = temp.path().join("plain.yaml");
+        let templated = temp.path().join("templated.yaml");
+        let encrypted = temp.path().join("encrypted.yaml");
+        std::fs::write(&plain, "replicas: 2\nimage:\n  tag: '{{ tag }}'\n").unwrap();
+        std::fs::write(&templated, "password: '{{ db.password }}'\n").unwrap();
+        std::fs::write(
+            &encrypted,
+            "token: ENC[AES256_GCM,data:abc]\nsops:\n  version: 3.8.1\n  mac: ENC[AES256_GCM,data:def]\n",
+        )
+        .unwrap();
+
+        let chart = chart(&format!(
+            "name: web\nnamespace: web\nvalues_files: [{}, {}, {}]\nvalues:\n  - replicas: 3\n",
+            plain.display(),
+            templated.display(),
+            encrypted.display()
+        ));
+        let context: Value = serde_yaml_ng::from_str(&format!(
+            "tag: '1.0'\ndb:\n  password: {}\n",
+            SECRET_PLACEHOLDER
+        ))
+        .unwrap();
+
+        let exported = chart_values(&chart, &context).unwrap();
+        assert_eq!(
+            exported.values,
+            Some(serde_yaml_ng::from_str("replicas: 3\nimage:\n  tag: '1.0'\n").unwrap())
+        );
+        assert_eq!(
+            exported.secret_files,
+            vec![
+                templated.display().to_string(),
+                encrypted.display().to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chart_values_detects_reordered_secrets() {
+        let temp = tempfile::tempdir().unwrap();
+        let plain = temp.path().join("plain.yaml");
+        let secret = temp.path().join("secret.yaml");
+        std::fs::write(&plain, "replicas: 2\n").unwrap();
+        std::fs::write(&secret, "password: '{{ password }}'\n").unwrap();
+        let context: Value =
+            serde_yaml_ng::from_str(&format!("password: {}\n", SECRET_PLACEHOLDER)).unwrap();
+
+        // Secrets first: the references applied before the values keep the order
+        let secrets_first = chart(&format!(
+            "name: web\nnamespace: web\nvalues_files: [{}, {}]\n",
+            secret.display(),
+            plain.display()
+        ));
+        assert!(
+            !chart_values(&secrets_first, &context)
+                .unwrap()
+                .secrets_reordered
+        );
+
+        let secrets_last = chart(&format!(
+            "name: web\nnamespace: web\nvalues_files: [{}, {}]\n",
+            plain.display(),
+            secret.display()
+        ));
+        assert!(
+            chart_values(&secrets_last, &context)
+                .unwrap()
+                .secrets_reordered
+        );
+    }
+
+    #[test]
+    fn test_dependency_depths() {
+        let charts = [
+            chart("name: db\nnamespace: data\n"),
+            chart("name: api\nnamespace: app\ndepends: [db]\n"),
+            chart("name: web\nnamespace: app\ndepends: [api, db]\n"),
+            chart("name: tools\nnamespace: ops\n"),
+        ];
+        let sorted: Vec<&Chart> = charts.iter().collect();
+
+        let depths = dependency_depths(&sorted);
+        assert_eq!(depths["db"], 0);
+        assert_eq!(depths["api"], 1);
+        assert_eq!(depths["web"], 2);
+        assert_eq!(depths["tools"], 0);
+    }
+
+    #[test]
+    fn test_resource_name_disambiguates_shared_release_names() {
+        let charts = [
+            chart("name: ingress\nnamespace: public\n"),
+            chart("name: ingress\nnamespace: internal\n"),
+            chart("name: web\nnamespace: web\n"),
+        ];
+        assert_eq!(resource_name(&charts[0], &charts), "public-ingress");
+        assert_eq!(resource_name(&charts[2], &charts), "web");
+    }
+
+    #[test]
+    fn test_secret_name() {
+        assert_eq!(
+            secret_name("web", "values/Prod_Secrets.enc.yaml"),
+            "web-prod-secrets-enc"
+        );
+    }
+}
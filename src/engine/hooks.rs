@@ -0,0 +1,150 @@
+//! Chart deployment hooks (`pre_deploy`, `post_deploy`, `on_failure`).
+
+use crate::config::{Chart, Hooks, VesshelmConfig};
+use crate::engine::release::interpolate_variables;
+use anyhow::{Context, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPhase {
+    PreDeploy,
+    PostDeploy,
+    OnFailure,
+}
+
+impl HookPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookPhase::PreDeploy => "pre_deploy",
+            HookPhase::PostDeploy => "post_deploy",
+            HookPhase::OnFailure => "on_failure",
+        }
+    }
+
+    fn select(self, hooks: &Hooks) -> Option<&Vec<String>> {
+        match self {
+            HookPhase::PreDeploy => hooks.pre_deploy.as_ref(),
+            HookPhase::PostDeploy => hooks.post_deploy.as_ref(),
+            HookPhase::OnFailure => hooks.on_failure.as_ref(),
+        }
+    }
+}
+
+/// Commands of a phase for a chart, falling back to the global `vesshelm.hooks`.
+pub fn hooks_for<'a>(
+    chart: &'a Chart,
+    global_helm_config: &'a VesshelmConfig,
+    phase: HookPhase,
+) -> &'a [String] {
+    chart
+        .hooks
+        .as_ref()
+        .and_then(|h| phase.select(h))
+        .or_else(|| {
+            global_helm_config
+                .hooks
+                .as_ref()
+                .and_then(|h| phase.select(h))
+        })
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Renders a hook command with the chart placeholders and the variables context.
+pub fn render_hook(
+    command: &str,
+    chart: &Chart,
+    destination: &str,
+    variable_context: &serde_yaml_ng::Value,
+) -> Result<String> {
+    let command = interpolate_variables(command, chart, destination)?;
+    if variable_context.is_null() || chart.no_interpolation {
+        return Ok(command);
+    }
+
+    crate::util::variables::render_template(&command, variable_context, "hook")
+        .with_context(|| format!("Failed to render hook command: {}", command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(hooks: Option<Hooks>) -> Chart {
+        Chart {
+            name: "api".to_string(),
+            repo_name: Some("stable".to_string()),
+            version: Some("1.0.0".to_string()),
+            namespace: "backend".to_string(),
+            hooks,
+            ..Default::default()
+        }
+    }
+
+    fn global(hooks: Option<Hooks>) -> VesshelmConfig {
+        VesshelmConfig {
+            helm_args: "upgrade".to_string(),
+            diff_enabled: false,
+            diff_args: None,
+            deploy_debug_pause: false,
+            diff_engine: Default::default(),
+            hooks,
+        }
+    }
+
+    #[test]
+    fn test_hooks_for_falls_back_to_global() {
+        let global = global(Some(Hooks {
+            pre_deploy: Some(vec!["global-pre".to_string()]),
+            post_deploy: Some(vec!["global-post".to_string()]),
+            on_failure: None,
+        }));
+        let chart = chart(Some(Hooks {
+            pre_deploy: Some(vec!["chart-pre".to_string()]),
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            hooks_for(&chart, &global, HookPhase::PreDeploy),
+            ["chart-pre".to_string()]
+        );
+        assert_eq!(
+            hooks_for(&chart, &global, HookPhase::PostDeploy),
+            ["global-post".to_string()]
+        );
+        assert!(hooks_for(&chart, &global, HookPhase::OnFailure).is_empty());
+    }
+
+    #[test]
+    fn test_empty_chart_phase_disables_global() {
+        let global = global(Some(Hooks {
+            pre_deploy: Some(vec!["global-pre".to_string()]),
+            ..Default::default()
+        }));
+        let chart = chart(Some(Hooks {
+            pre_deploy: Some(vec![]),
+            ..Default::default()
+        }));
+
+        assert!(hooks_for(&chart, &global, HookPhase::PreDeploy).is_empty());
+    }
+
+    #[test]
+    fn test_render_hook() {
+        let context: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("db:\n  host: postgres.local\n").unwrap();
+        let rendered = render_hook(
+            "./migrate.sh {{ name }} -n {{ namespace }} --host {{ db.host }}",
+            &chart(None),
+            "./charts",
+            &context,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "./migrate.sh api -n backend --host postgres.local"
+        );
+
+        let err = render_hook("echo {{ missing }}", &chart(None), "./charts", &context);
+        assert!(err.is_err());
+    }
+}
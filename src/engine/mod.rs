@@ -1 +1,9 @@
+pub mod audit;
+pub mod bundle;
+pub mod export;
+pub mod hooks;
+pub mod policy;
+pub mod release;
+pub mod run_state;
 pub mod sync;
+pub mod values;
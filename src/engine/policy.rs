@@ -0,0 +1,440 @@
+//! Evaluation of the `policies` guardrails on the rendered manifests of a release.
+
+use crate::config::{Policies, PolicyLevel, PolicyOperator, PolicyRule};
+use crate::util::manifest::{parse_manifest, pod_spec};
+use anyhow::Result;
+use serde_yaml_ng::Value;
+
+/// Rule broken by an object of a rendered manifest.
+#[derive(Debug, PartialEq)]
+pub struct PolicyViolation {
+    pub rule: String,
+    pub level: PolicyLevel,
+    /// `Kind/name` of the offending object.
+    pub object: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.rule, self.object, self.message)
+    }
+}
+
+/// Evaluates the built-in and user rules on every object of a multi-document manifest.
+pub fn evaluate(
+    policies: &Policies,
+    manifest: &str,
+    namespace: &str,
+) -> Result<Vec<PolicyViolation>> {
+    let builtin: [(&str, PolicyLevel, BuiltinCheck); 4] = [
+        (
+            "no_latest_tag",
+            policies.builtin.no_latest_tag,
+            check_latest_tag,
+        ),
+        (
+            "require_limits",
+            policies.builtin.require_limits,
+            check_limits,
+        ),
+        (
+            "no_privileged",
+            policies.builtin.no_privileged,
+            check_privileged,
+        ),
+        (
+            "no_host_path",
+            policies.builtin.no_host_path,
+            check_host_path,
+        ),
+    ];
+
+    let mut violations = Vec::new();
+    for (key, object) in parse_manifest(manifest, namespace)? {
+        let name = format!("{}/{}", key.kind, key.name);
+        let mut report = |rule: &str, level: PolicyLevel, message: String| {
+            violations.push(PolicyViolation {
+                rule: rule.to_string(),
+                level,
+                object: name.clone(),
+                message,
+            })
+        };
+
+        if let Some(pod) = pod_spec(&key.kind, &object) {
+            for (rule, level, check) in &builtin {
+                if *level != PolicyLevel::Off {
+                    for message in check(pod) {
+                        report(rule, *level, message);
+                    }
+                }
+            }
+        }
+
+        for rule in &policies.rules {
+            if rule.level == PolicyLevel::Off
+                || rule
+                    .kinds
+                    .as_ref()
+                    .is_some_and(|kinds| !kinds.contains(&key.kind))
+            {
+                continue;
+            }
+            if !rule_holds(rule, &object) {
+                let message = rule.message.clone().unwrap_or_else(|| describe(rule));
+                report(&rule.name, rule.level, message);
+            }
+        }
+    }
+    Ok(violations)
+}
+
+type BuiltinCheck = fn(&Value) -> Vec<String>;
+
+fn containers(pod: &Value, lists: &[&str]) -> Vec<(String, Value)> {
+    lists
+        .iter()
+        .filter_map(|list| pod.get(*list).and_then(Value::as_sequence))
+        .flatten()
+        .map(|container| {
+            let name = container
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            (name, container.clone())
+        })
+        .collect()
+}
+
+fn check_latest_tag(pod: &Value) -> Vec<String> {
+    containers(pod, &["initContainers", "containers"])
+        .into_iter()
+        .filter_map(|(name, container)| {
+            let image = container.get("image")?.as_str()?;
+            uses_latest(image).then(|| format!("container '{}' uses image '{}'", name, image))
+        })
+        .collect()
+}
+
+/// Whether an image reference resolves to the moving `latest` tag.
+fn uses_latest(image: &str) -> bool {
+    if image.contains('@') {
+        return false;
+    }
+    let last_segment = image.rsplit('/').next().unwrap_or(image);
+    match last_segment.split_once(':') {
+        Some((_, tag)) => tag == "latest",
+        None => true,
+    }
+}
+
+fn check_limits(pod: &Value) -> Vec<String> {
+    containers(pod, &["containers"])
+        .into_iter()
+        .filter_map(|(name, container)| {
+            let limits = container.get("resources").and_then(|r| r.get("limits"));
+            let missing: Vec<&str> = ["cpu", "memory"]
+                .into_iter()
+                .filter(|resource| limits.and_then(|l| l.get(*resource)).is_none())
+                .collect();
+            (!missing.is_empty()).then(|| {
+                format!(
+                    "container '{}' has no {} limit",
+                    name,
+                    missing.join(" and ")
+                )
+            })
+        })
+        .collect()
+}
+
+fn check_privileged(pod: &Value) -> Vec<String> {
+    containers(pod, &["initContainers", "containers"])
+        .into_iter()
+        .filter(|(_, container)| {
+            container
+                .get("securityContext")
+                .and_then(|s| s.get("privileged"))
+                .and_then(Value::as_bool)
+                == Some(true)
+        })
+        .map(|(name, _)| format!("container '{}' runs privileged", name))
+        .collect()
+}
+
+fn check_host_path(pod: &Value) -> Vec<String> {
+    pod.get("volumes")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter_map(|volume| {
+            let path = volume.get("hostPath")?.get("path")?.as_str()?;
+            let name = volume
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            Some(format!("volume '{}' mounts host path '{}'", name, path))
+        })
+        .collect()
+}
+
+/// Values at a dotted path, `[*]` iterating over a list and `[N]` selecting one item.
+fn resolve<'a>(object: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut current = vec![object];
+    for segment in split_path(path) {
+        let (key, indexes) = match segment.find('[') {
+            Some(i) => (&segment[..i], &segment[i..]),
+            None => (segment.as_str(), ""),
+        };
+        if !key.is_empty() {
+            current = current.into_iter().filter_map(|v| v.get(key)).collect();
+        }
+        for index in indexes
+            .split_terminator(']')
+            .map(|i| i.trim_start_matches('['))
+        {
+            current = current
+                .into_iter()
+                .filter_map(Value::as_sequence)
+                .flat_map(|items| match index {
+                    "*" => items.iter().collect::<Vec<_>>(),
+                    _ => index
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| items.get(i))
+                        .into_iter()
+                        .collect(),
+                })
+                .collect();
+        }
+    }
+    current
+}
+
+/// Splits a path on dots, `\.` escaping a dot inside a key (`metadata.labels.app\.kubernetes\.io/name`).
+fn split_path(path: &str) -> Vec<String> {
+    let mut segments = vec![String::new()];
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'.') => {
+                chars.next();
+                segments.last_mut().unwrap().push('.');
+            }
+            '.' => segments.push(String::new()),
+            c => segments.last_mut().unwrap().push(c),
+        }
+    }
+    segments
+}
+
+fn rule_holds(rule: &PolicyRule, object: &Value) -> bool {
+    let found = resolve(object, &rule.path);
+    let expected = rule.value.as_ref().unwrap_or(&Value::Null);
+    match rule.operator {
+        PolicyOperator::Exists => !found.is_empty(),
+        PolicyOperator::NotExists => found.is_empty(),
+        // Comparisons only constrain the values present; combine with `exists` to require them
+        PolicyOperator::Equals => found.iter().all(|v| scalar_eq(v, expected)),
+        PolicyOperator::NotEquals => found.iter().all(|v| !scalar_eq(v, expected)),
+        PolicyOperator::Matches | PolicyOperator::NotMatches => {
+            let Some(re) = expected.as_str().and_then(|re| regex::Regex::new(re).ok()) else {
+                return false;
+            };
+            let should_match = rule.operator == PolicyOperator::Matches;
+            found
+                .iter()
+                .all(|v| scalar_string(v).is_some_and(|s| re.is_match(&s) == should_match))
+        }
+        PolicyOperator::GreaterThan | PolicyOperator::LessThan => {
+            let Some(limit) = expected.as_f64() else {
+                return false;
+            };
+            found.iter().all(|v| {
+                v.as_f64().is_some_and(|n| match rule.operator {
+                    PolicyOperator::GreaterThan => n > limit,
+                    _ => n < limit,
+                })
+            })
+        }
+    }
+}
+
+/// Equality ignoring the YAML type of scalars (`"1"` equals `1`), as templates often quote values.
+fn scalar_eq(found: &Value, expected: &Value) -> bool {
+    found == expected
+        || matches!((scalar_string(found), scalar_string(expected)), (Some(a), Some(b)) if a == b)
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn describe(rule: &PolicyRule) -> String {
+    let value = rule
+        .value
+        .as_ref()
+        .and_then(scalar_string)
+        .unwrap_or_default();
+    let expectation = match rule.operator {
+        PolicyOperator::Equals => format!("equal '{}'", value),
+        PolicyOperator::NotEquals => format!("not equal '{}'", value),
+        PolicyOperator::Matches => format!("match '{}'", value),
+        PolicyOperator::NotMatches => format!("not match '{}'", value),
+        PolicyOperator::Exists => "exist".to_string(),
+        PolicyOperator::NotExists => "not exist".to_string(),
+        PolicyOperator::GreaterThan => format!("be greater than {}", value),
+        PolicyOperator::LessThan => format!("be less than {}", value),
+    };
+    format!("{} must {}", rule.path, expectation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 1
+  template:
+    spec:
+      initContainers:
+        - name: init
+          image: busybox
+          securityContext:
+            privileged: true
+      containers:
+        - name: web
+          image: registry:5000/nginx:1.25
+          resources:
+            limits:
+              cpu: 500m
+        - name: sidecar
+          image: envoy@sha256:abc
+          resources:
+            limits:
+              cpu: 100m
+              memory: 64Mi
+      volumes:
+        - name: logs
+          hostPath:
+            path: /var/log
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: web
+spec:
+  type: NodePort
+"#;
+
+    fn messages(violations: &[PolicyViolation]) -> Vec<String> {
+        violations.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let violations = evaluate(&Policies::default(), MANIFEST, "default").unwrap();
+
+        assert_eq!(
+            messages(&violations),
+            vec![
+                "[no_latest_tag] Deployment/web: container 'init' uses image 'busybox'",
+                "[require_limits] Deployment/web: container 'web' has no memory limit",
+                "[no_privileged] Deployment/web: container 'init' runs privileged",
+                "[no_host_path] Deployment/web: volume 'logs' mounts host path '/var/log'",
+            ]
+        );
+        assert!(violations.iter().all(|v| v.level == PolicyLevel::Error));
+    }
+
+    #[test]
+    fn test_builtin_levels() {
+        let mut policies = Policies::default();
+        policies.builtin.no_latest_tag = PolicyLevel::Off;
+        policies.builtin.require_limits = PolicyLevel::Off;
+        policies.builtin.no_privileged = PolicyLevel::Off;
+        policies.builtin.no_host_path = PolicyLevel::Warn;
+
+        let violations = evaluate(&policies, MANIFEST, "default").unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].level, PolicyLevel::Warn);
+    }
+
+    #[test]
+    fn test_user_rules() {
+        let policies: Policies = serde_yaml_ng::from_str(
+            r#"
+builtin:
+  no_latest_tag: off
+  require_limits: off
+  no_privileged: off
+  no_host_path: off
+rules:
+  - name: min-replicas
+    kinds: [Deployment]
+    path: spec.replicas
+    operator: greater_than
+    value: 1
+  - name: no-node-port
+    kinds: [Service]
+    path: spec.type
+    operator: not_equals
+    value: NodePort
+    level: warn
+    message: NodePort services are exposed on every node
+  - name: trusted-registry
+    path: spec.template.spec.containers[*].image
+    operator: matches
+    value: "^registry:5000/"
+  - name: team-label
+    kinds: [Deployment]
+    path: metadata.labels.app\.kubernetes\.io/part-of
+    operator: exists
+"#,
+        )
+        .unwrap();
+
+        let violations = evaluate(&policies, MANIFEST, "default").unwrap();
+        assert_eq!(
+            messages(&violations),
+            vec![
+                "[min-replicas] Deployment/web: spec.replicas must be greater than 1",
+                "[trusted-registry] Deployment/web: spec.template.spec.containers[*].image must match '^registry:5000/'",
+                r"[team-label] Deployment/web: metadata.labels.app\.kubernetes\.io/part-of must exist",
+                "[no-node-port] Service/web: NodePort services are exposed on every node",
+            ]
+        );
+        assert_eq!(violations[3].level, PolicyLevel::Warn);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let object: Value = serde_yaml_ng::from_str(
+            "metadata:\n  labels:\n    app.kubernetes.io/name: web\nitems:\n  - a: 1\n  - a: 2\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve(&object, r"metadata.labels.app\.kubernetes\.io/name"),
+            vec![&Value::from("web")]
+        );
+        assert_eq!(
+            resolve(&object, "items[*].a"),
+            vec![&Value::from(1), &Value::from(2)]
+        );
+        assert_eq!(resolve(&object, "items[1].a"), vec![&Value::from(2)]);
+        assert!(resolve(&object, "items[5].a").is_empty());
+    }
+}
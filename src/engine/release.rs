@@ -0,0 +1,540 @@
+//! Preparation of the helm invocation for a chart release, shared by `deploy` and `diff`.
+
+use crate::config::{Chart, Config, Destination, DiffEngine, VesshelmConfig};
+use crate::util::progress::ProgressTracker;
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Default `helm diff` invocation used when `vesshelm.diff_args` is not set.
+pub const DEFAULT_DIFF_ARGS: &str = "diff upgrade --suppress-secrets --allow-unreleased {{ name }} {{ destination }} -n {{ namespace }}";
+
+/// Source label of the chart's inline `values`.
+pub const INLINE_VALUES_SOURCE: &str = "inline values";
+
+/// Loads the variables and secrets files used to render values files.
+pub fn load_variable_context(config: &Config, config_path: &Path) -> Result<serde_yaml_ng::Value> {
+    let mut all_variable_files = Vec::new();
+    if let Some(files) = &config.variables_files {
+        all_variable_files.extend(files.clone());
+    }
+    if let Some(files) = &config.secrets_files {
+        all_variable_files.extend(files.clone());
+    }
+
+    if all_variable_files.is_empty() {
+        return Ok(serde_yaml_ng::Value::Null);
+    }
+
+    let base_path = config_path.parent().unwrap_or(Path::new("."));
+    crate::util::variables::load_variables(&all_variable_files, base_path)
+        .context("Failed to load variables/secrets")
+}
+
+/// Helm arguments of a chart release, with its values files rendered to a temporary directory.
+///
+/// The rendered files live as long as this value, so it must be kept until helm has run.
+pub struct PreparedRelease {
+    /// Interpolated `helm_args` including the values flags.
+    pub args: String,
+    /// ` -f <file>` flags for every values source of the chart.
+    pub values_flags: String,
+    /// Values files passed to helm, in order.
+    pub values_files: Vec<PathBuf>,
+    /// Origin of each entry of `values_files` as written in the configuration.
+    pub values_sources: Vec<String>,
+    /// Resolved destination (chart directory parent, or chart path for local charts).
+    pub destination: String,
+    _rendered_temp_dir: tempfile::TempDir,
+}
+
+impl PreparedRelease {
+    /// Interpolated `helm diff` arguments including the values flags.
+    pub fn diff_args(&self, chart: &Chart, global_helm_config: &VesshelmConfig) -> Result<String> {
+        let diff_template = global_helm_config
+            .diff_args
+            .as_deref()
+            .unwrap_or(DEFAULT_DIFF_ARGS);
+
+        let mut diff_args = interpolate_variables(diff_template, chart, &self.destination)?;
+        diff_args.push_str(&self.values_flags);
+        Ok(diff_args)
+    }
+}
+
+/// Resolves the destination, renders the values files and builds the helm arguments of a chart.
+pub fn prepare_release(
+    chart: &Chart,
+    destinations: &[Destination],
+    global_helm_config: &VesshelmConfig,
+    variable_context: &serde_yaml_ng::Value,
+    tracker: &ProgressTracker,
+) -> Result<PreparedRelease> {
+    // Determine destination path
+    let dest_path = get_destination_path(chart, destinations)?;
+
+    // Construct Helm arguments
+    let mut args_str = construct_helm_args(chart, global_helm_config)?;
+
+    // Prepare values flags
+    let mut values_flags = String::new();
+    let mut values_files = Vec::new();
+    let mut values_sources = Vec::new();
+    // Use TempDir to persist files as long as the prepared release lives (Drop cleans up)
+    let _rendered_temp_dir = tempfile::Builder::new()
+        .prefix("vesshelm-values-")
+        .tempdir()
+        .context("Failed to create temporary directory for values files")?;
+    // Handle local 'values.yaml' interpolation (Implicit default values)
+    if !variable_context.is_null()
+        && !chart.no_interpolation
+        && chart.repo_name.is_none()
+        && let Some(chart_src_path) = &chart.chart_path
+    {
+        let values_path = std::path::Path::new(chart_src_path).join("values.yaml");
+        if values_path.exists() {
+            match crate::util::variables::render_values_file(&values_path, variable_context) {
+                Ok(rendered) => {
+                    let tmp_path = _rendered_temp_dir.path().join("local-values.yaml");
+                    if let Err(e) = std::fs::write(&tmp_path, rendered) {
+                        tracker.println(&format!(
+                            "{} Failed to write temp values: {}",
+                            "⚠️ ".yellow(),
+                            e
+                        ));
+                    } else if let Some(path_str) = tmp_path.to_str() {
+                        values_flags.push_str(" -f ");
+                        values_flags.push_str(path_str);
+                        values_files.push(tmp_path.clone());
+                        values_sources.push(values_path.display().to_string());
+                    }
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Failed to render local values.yaml: {}", e));
+                }
+            }
+        }
+    }
+
+    if let Some(files) = &chart.values_files {
+        for (i, file_path_str) in files.iter().enumerate() {
+            let path = std::path::Path::new(file_path_str);
+            let file_arg =
+                if !variable_context.is_null() && !chart.no_interpolation && path.exists() {
+                    let rendered =
+                        crate::util::variables::render_values_file(path, variable_context)
+                            .with_context(|| format!("Failed to render values file {:?}", path))?;
+                    let tmp_path = _rendered_temp_dir.path().join(format!("values-{}.yaml", i));
+                    std::fs::write(&tmp_path, rendered)?;
+                    tmp_path
+                        .to_str()
+                        .ok_or_else(|| anyhow!("Invalid temp path"))?
+                        .to_string()
+                } else {
+                    file_path_str.clone()
+                };
+            values_flags.push_str(" -f ");
+            values_flags.push_str(&file_arg);
+            values_files.push(PathBuf::from(file_arg));
+            values_sources.push(file_path_str.clone());
+        }
+    }
+
+    // Handle inline values
+    if let Some(values) = &chart.values {
+        let content = crate::util::helm::merge_values(values)?;
+        let tmp_path = _rendered_temp_dir.path().join("inline-values.yaml");
+        std::fs::write(&tmp_path, content)?;
+        values_flags.push_str(" -f ");
+        values_flags.push_str(tmp_path.to_str().ok_or_else(|| anyhow!("Invalid path"))?);
+        values_files.push(tmp_path);
+        values_sources.push(INLINE_VALUES_SOURCE.to_string());
+    }
+
+    // Append values to args
+    args_str.push_str(&values_flags);
+
+    // Interpolate variables
+    let args = interpolate_variables(&args_str, chart, &dest_path)?;
+
+    Ok(PreparedRelease {
+        args,
+        values_flags,
+        values_files,
+        values_sources,
+        destination: dest_path,
+        _rendered_temp_dir,
+    })
+}
+
+/// Flags of `helm upgrade` that `helm template` does not accept.
+const UPGRADE_ONLY_FLAGS: &[&str] = &[
+    "--install",
+    "-i",
+    "--force",
+    "--reuse-values",
+    "--reset-values",
+    "--reset-then-reuse-values",
+    "--cleanup-on-fail",
+];
+
+/// Flags of `helm upgrade` that `helm template` does not accept, followed by a value.
+const UPGRADE_ONLY_VALUE_FLAGS: &[&str] = &["--history-max"];
+
+/// Turns the `helm upgrade`/`helm install` arguments of a release into `helm template` arguments.
+pub fn to_template_args(args: &str) -> Result<String> {
+    let mut tokens = args.split_whitespace();
+    match tokens.next() {
+        Some("upgrade" | "install") => {}
+        _ => {
+            return Err(anyhow!(
+                "Cannot render '{}' with helm template: helm args must start with 'upgrade' or 'install'",
+                args
+            ));
+        }
+    }
+
+    let mut template_args = vec!["template"];
+    while let Some(token) = tokens.next() {
+        let flag = token.split('=').next().unwrap_or(token);
+        if UPGRADE_ONLY_FLAGS.contains(&flag) {
+            continue;
+        }
+        if UPGRADE_ONLY_VALUE_FLAGS.contains(&flag) {
+            if !token.contains('=') {
+                tokens.next();
+            }
+            continue;
+        }
+        template_args.push(token);
+    }
+    Ok(template_args.join(" "))
+}
+
+pub fn get_destination_path(chart: &Chart, destinations: &[Destination]) -> Result<String> {
+    // 1. Check for destination override in chart
+    if let Some(override_path) = &chart.dest {
+        if let Some(d) = destinations.iter().find(|d| d.name == *override_path) {
+            return Ok(d.path.clone());
+        }
+        // Use as direct path
+        return Ok(override_path.clone());
+    }
+
+    // 2. Local chart support
+    if chart.repo_name.is_none()
+        && let Some(path) = &chart.chart_path
+    {
+        return Ok(path.clone());
+    }
+
+    // Default destination
+    if let Some(default_dest) = destinations.iter().find(|d| d.name == "default") {
+        return Ok(default_dest.path.clone());
+    }
+
+    Err(anyhow!(
+        "Could not determine destination path for chart {}",
+        chart.name
+    ))
+}
+
+pub fn construct_helm_args(chart: &Chart, global_helm_config: &VesshelmConfig) -> Result<String> {
+    if let Some(override_args) = &chart.helm_args_override {
+        return Ok(override_args.clone());
+    }
+
+    let mut args = global_helm_config.helm_args.clone();
+
+    if let Some(append_args) = &chart.helm_args_append {
+        args.push(' ');
+        args.push_str(append_args);
+    }
+
+    Ok(args)
+}
+
+pub fn interpolate_variables(
+    args_template: &str,
+    chart: &Chart,
+    destination: &str,
+) -> Result<String> {
+    let mut result = args_template.to_string();
+
+    // Calculate full chart path for robust replacement
+    let full_chart_path = if chart.repo_name.is_none() {
+        // For local charts, destination IS the chart path in our logic above
+        destination.to_string()
+    } else {
+        // For remote charts, destination is parent dir
+        format!("{}/{}", destination, chart.dir_name())
+    };
+
+    // Smart replacement: Handle the common pattern "{{ destination }}/{{ name }}" first
+    // This prevents "./my-chart/my-chart" issue for local charts
+    result = result.replace("{{ destination }}/{{ name }}", &full_chart_path);
+    result = result.replace("{{destination}}/{{name}}", &full_chart_path);
+
+    result = result.replace("{{ name }}", chart.release_name());
+    result = result.replace("{{ release }}", chart.release_name());
+    result = result.replace("{{ chart }}", &chart.name);
+    result = result.replace("{{ destination }}", destination);
+    result = result.replace("{{ namespace }}", &chart.namespace);
+    result = result.replace("{{ version }}", chart.version.as_deref().unwrap_or(""));
+    result = result.replace("{{ chart_path }}", &full_chart_path);
+
+    // Also support {{name}} without spaces just in case
+    result = result.replace("{{name}}", chart.release_name());
+    result = result.replace("{{release}}", chart.release_name());
+    result = result.replace("{{chart}}", &chart.name);
+    result = result.replace("{{destination}}", destination);
+    result = result.replace("{{namespace}}", &chart.namespace);
+    result = result.replace("{{version}}", chart.version.as_deref().unwrap_or(""));
+    result = result.replace("{{chart_path}}", &full_chart_path);
+
+    Ok(result)
+}
+
+/// Renders the manifests of a release with `helm template` and its `helm_args`.
+///
+/// Arguments that cannot be turned into `helm template` ones, such as a `helm_args_override`
+/// not starting with `upgrade` or `install`, fall back to the chart and values alone.
+pub async fn render_manifest(chart: &Chart, prepared: &PreparedRelease) -> Result<String> {
+    let template_args = match to_template_args(&prepared.args) {
+        Ok(args) => args,
+        Err(_) => chart_template_args(chart, prepared)?,
+    };
+    let output = Command::new("helm")
+        .args(template_args.split_whitespace())
+        .output()
+        .await
+        .context("Failed to execute helm template")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "helm template failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Computes the pending changes of a release with the configured diff engine.
+///
+/// Returns the diff in helm-diff format; an empty (or whitespace-only) output means no changes.
+pub async fn compute_diff(
+    chart: &Chart,
+    prepared: &PreparedRelease,
+    global_helm_config: &VesshelmConfig,
+    tracker: &ProgressTracker,
+) -> Result<String> {
+    match global_helm_config.diff_engine {
+        DiffEngine::Plugin => {
+            let diff_args = prepared.diff_args(chart, global_helm_config)?;
+            let output = execute_helm_diff(&diff_args, tracker).await?;
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        DiffEngine::Native => execute_native_diff(chart, prepared, tracker).await,
+    }
+}
+
+/// `helm template` arguments built from the chart location and values only.
+fn chart_template_args(chart: &Chart, prepared: &PreparedRelease) -> Result<String> {
+    let chart_path =
+        interpolate_variables("{{ destination }}/{{ name }}", chart, &prepared.destination)?;
+    Ok(format!(
+        "template {} {} -n {}{}",
+        chart.release_name(),
+        chart_path,
+        chart.namespace,
+        prepared.values_flags
+    ))
+}
+
+/// Compares `helm template` of the chart with `helm get manifest` of the deployed release.
+async fn execute_native_diff(
+    chart: &Chart,
+    prepared: &PreparedRelease,
+    tracker: &ProgressTracker,
+) -> Result<String> {
+    let template_args = chart_template_args(chart, prepared)?;
+    tracker.println(&format!(
+        "{} helm {}",
+        "🔎 ".dimmed(),
+        template_args.dimmed()
+    ));
+
+    let output = Command::new("helm")
+        .args(template_args.split_whitespace())
+        .output()
+        .await
+        .context("Failed to execute helm template")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Helm template failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let desired = String::from_utf8_lossy(&output.stdout).into_owned();
+
+    let output = Command::new("helm")
+        .args([
+            "get",
+            "manifest",
+            chart.release_name(),
+            "-n",
+            &chart.namespace,
+        ])
+        .output()
+        .await
+        .context("Failed to execute helm get manifest")?;
+    let current = if output.status.success() {
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Not installed yet: everything is added
+        if !stderr.contains("not found") {
+            return Err(anyhow!(
+                "Failed to get manifest of release {}: {}",
+                chart.release_name(),
+                stderr.trim()
+            ));
+        }
+        String::new()
+    };
+
+    crate::util::manifest::diff_manifests(&current, &desired, &chart.namespace)
+}
+
+async fn execute_helm_diff(args: &str, tracker: &ProgressTracker) -> Result<std::process::Output> {
+    tracker.println(&format!("{} helm {}", "🔎 ".dimmed(), args.dimmed()));
+
+    let parts: Vec<&str> = args.split_whitespace().collect();
+
+    // Helm diff outputs to stdout mostly.
+    let output = Command::new("helm")
+        .args(&parts)
+        .env("HELM_DIFF_COLOR", "true")
+        .output()
+        .await
+        .context("Failed to execute helm diff command")?;
+
+    if !output.status.success() {
+        // Print stderr if failed
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        tracker.println(&stderr);
+        return Err(anyhow!(
+            "Helm diff command failed with status: {}",
+            output.status
+        ));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Chart, VesshelmConfig};
+
+    #[test]
+    fn test_interpolate_variables() -> Result<()> {
+        let chart = Chart {
+            name: "my-chart".to_string(),
+            repo_name: Some("stable".to_string()),
+            version: Some("1.0.0".to_string()),
+            namespace: "my-ns".to_string(),
+            ..Default::default()
+        };
+        let dest = "./charts/my-chart";
+        let template =
+            "upgrade {{ name }} {{ destination }} -n {{ namespace }} --version {{ version }}";
+
+        let result = interpolate_variables(template, &chart, dest)?;
+        assert_eq!(
+            result,
+            "upgrade my-chart ./charts/my-chart -n my-ns --version 1.0.0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_interpolate_release_name() -> Result<()> {
+        let chart: Chart = serde_yaml_ng::from_str(
+            "name: ingress-nginx\nrelease: ingress-internal\nrepo_name: stable\nversion: 4.0.0\nnamespace: ingress\n",
+        )?;
+        let template = "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }} --description {{ chart }}/{{ release }}";
+
+        let result = interpolate_variables(template, &chart, "./charts")?;
+        assert_eq!(
+            result,
+            "upgrade --install ingress-internal ./charts/ingress-nginx -n ingress --description ingress-nginx/ingress-internal"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_template_args() -> Result<()> {
+        let args = to_template_args(
+            "upgrade --install web ./charts/web -n web --wait --history-max 5 --cleanup-on-fail --force=true -f values.yaml",
+        )?;
+        assert_eq!(
+            args,
+            "template web ./charts/web -n web --wait -f values.yaml"
+        );
+
+        let args = to_template_args("install web ./charts/web --history-max=3")?;
+        assert_eq!(args, "template web ./charts/web");
+
+        assert!(to_template_args("--debug upgrade web ./charts/web").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_construct_helm_args_override() -> Result<()> {
+        let chart = Chart {
+            name: "test".to_string(),
+            repo_name: Some("test".to_string()),
+            version: Some("1.0.0".to_string()),
+            namespace: "default".to_string(),
+            helm_args_append: Some("--ignore-this".to_string()),
+            helm_args_override: Some("override".to_string()),
+            ..Default::default()
+        };
+        let global = VesshelmConfig {
+            helm_args: "default".to_string(),
+            diff_enabled: false,
+            diff_args: None,
+            deploy_debug_pause: true,
+            diff_engine: Default::default(),
+            hooks: None,
+        };
+
+        let result = construct_helm_args(&chart, &global).unwrap();
+        assert_eq!(result, "override");
+        Ok(())
+    }
+
+    #[test]
+    fn test_construct_helm_args_append() {
+        let chart = Chart {
+            name: "test".to_string(),
+            repo_name: Some("test".to_string()),
+            version: Some("1.0.0".to_string()),
+            namespace: "default".to_string(),
+            helm_args_append: Some("--extra".to_string()),
+            ..Default::default()
+        };
+        let global = VesshelmConfig {
+            helm_args: "default".to_string(),
+            diff_enabled: false,
+            diff_args: None,
+            deploy_debug_pause: true,
+            diff_engine: Default::default(),
+            hooks: None,
+        };
+
+        let result = construct_helm_args(&chart, &global).unwrap();
+        assert_eq!(result, "default --extra");
+    }
+}
@@ -0,0 +1,93 @@
+//! State of the last `deploy` run, used by `deploy --resume`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const RUN_STATE_FILE: &str = "deploy-run.yaml";
+
+/// Progress of a deploy run, stored in the project-local state directory.
+///
+/// The file is removed once a run completes successfully.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
+pub struct DeployRunState {
+    /// Charts given on the command line, reused when resuming.
+    pub charts: Option<Vec<String>>,
+    /// Charts of the run, in deployment order.
+    pub planned: Vec<String>,
+    /// Charts already processed: deployed, or skipped without changes.
+    #[serde(default)]
+    pub completed: Vec<String>,
+    /// Chart whose deployment failed, if any.
+    pub failed: Option<String>,
+}
+
+impl DeployRunState {
+    fn path(state_dir: &Path) -> PathBuf {
+        state_dir.join(RUN_STATE_FILE)
+    }
+
+    pub fn load(state_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(state_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        serde_yaml_ng::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {:?}", path))
+    }
+
+    pub fn save(&self, state_dir: &Path) -> Result<()> {
+        fs::create_dir_all(state_dir)
+            .with_context(|| format!("Failed to create {:?}", state_dir))?;
+        let content = serde_yaml_ng::to_string(self).context("Failed to serialize run state")?;
+        let path = Self::path(state_dir);
+        fs::write(&path, content).with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    pub fn clear(state_dir: &Path) -> Result<()> {
+        let path = Self::path(state_dir);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// Planned charts not processed yet, in deployment order.
+    pub fn remaining(&self) -> Vec<&str> {
+        self.planned
+            .iter()
+            .filter(|name| !self.completed.contains(name))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().join(".vesshelm");
+        assert_eq!(DeployRunState::load(&state_dir).unwrap(), None);
+
+        let state = DeployRunState {
+            charts: Some(vec!["api".to_string()]),
+            planned: vec!["db".to_string(), "api".to_string(), "web".to_string()],
+            completed: vec!["db".to_string()],
+            failed: Some("api".to_string()),
+        };
+        state.save(&state_dir).unwrap();
+        let loaded = DeployRunState::load(&state_dir).unwrap().unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.remaining(), vec!["api", "web"]);
+
+        DeployRunState::clear(&state_dir).unwrap();
+        assert_eq!(DeployRunState::load(&state_dir).unwrap(), None);
+    }
+}
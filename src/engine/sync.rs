@@ -1,7 +1,8 @@
 use crate::clients::{GitClient, HelmClient};
-use crate::config::{Config, RepoType};
+use crate::config::{Chart, Config, RepoType};
 use crate::lock::Lockfile;
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
@@ -50,9 +51,13 @@ impl<H: HelmClient, G: GitClient> SyncEngine<H, G> {
     where
         F: Fn(SyncEvent),
     {
+        lockfile.migrate(&config.charts);
+
         // Filter charts based on options.charts
         if let Some(charts) = &options.charts {
-            config.charts.retain(|chart| charts.contains(&chart.name));
+            config
+                .charts
+                .retain(|chart| charts.iter().any(|n| n == chart.release_name()));
         }
 
         let mut stats = SyncStats {
@@ -60,7 +65,7 @@ impl<H: HelmClient, G: GitClient> SyncEngine<H, G> {
             failed: 0,
             skipped: 0,
         };
-        let mut repos_updated = false;
+        let mut pending = Vec::new();
 
         for chart in &config.charts {
             if chart.no_sync {
@@ -102,11 +107,15 @@ impl<H: HelmClient, G: GitClient> SyncEngine<H, G> {
                 fs::create_dir_all(dest_path).context("Failed to create destination directory")?;
             }
 
-            let chart_dest_dir = dest_path.join(&chart.name);
+            let chart_dest_dir = dest_path.join(chart.dir_name());
 
             // Check lockfile
-            match (options.ignore_skip, lockfile.get(&chart.name, repo_name)) {
-                (false, Some(locked)) if locked.version == *version && chart_dest_dir.exists() => {
+            match (options.ignore_skip, lockfile.get(chart)) {
+                (false, Some(locked))
+                    if locked.version == *version
+                        && chart_dest_dir.exists()
+                        && locked.is_synced_to(chart, &chart_dest_dir) =>
+                {
                     observer(SyncEvent::ChartSkipped {
                         name: chart.name.clone(),
                         reason: "up to date".to_string(),
@@ -117,13 +126,20 @@ impl<H: HelmClient, G: GitClient> SyncEngine<H, G> {
                 _ => {}
             }
 
-            if !repos_updated {
-                observer(SyncEvent::RepoUpdateStart);
-                match self.helm_client.repo_update() {
-                    Ok(_) => observer(SyncEvent::RepoUpdateSuccess),
-                    Err(e) => observer(SyncEvent::RepoUpdateFailed(e.to_string())),
-                }
-                repos_updated = true;
+            pending.push((chart, repo, version, dest_path_buf, chart_dest_dir));
+        }
+
+        let charts: Vec<&Chart> = pending.iter().map(|(chart, ..)| *chart).collect();
+        let unregistered = self.prepare_repositories(&config, &charts, &observer);
+
+        for (chart, repo, version, dest_path, chart_dest_dir) in pending {
+            if let Some(error) = unregistered.get(&repo.name) {
+                observer(SyncEvent::ChartSyncFailed {
+                    name: chart.name.clone(),
+                    error: error.clone(),
+                });
+                stats.failed += 1;
+                continue;
             }
 
             observer(SyncEvent::ChartSyncStart {
@@ -136,14 +152,14 @@ impl<H: HelmClient, G: GitClient> SyncEngine<H, G> {
                 RepoType::Oci => "OCI",
             };
 
-            match self.sync_single_chart(repo, chart, version, dest_path, &chart_dest_dir) {
+            match self.sync_single_chart(repo, chart, version, &dest_path, &chart_dest_dir) {
                 Ok(_) => {
                     observer(SyncEvent::ChartSyncSuccess {
                         name: chart.name.clone(),
                         repo_type: repo_type_str.to_string(),
                     });
                     stats.synced += 1;
-                    lockfile.update(chart.name.clone(), repo_name.clone(), version.to_string());
+                    lockfile.update(chart, version.to_string(), &chart_dest_dir);
                 }
                 Err(e) => {
                     observer(SyncEvent::ChartSyncFailed {
@@ -158,6 +174,59 @@ impl<H: HelmClient, G: GitClient> SyncEngine<H, G> {
         Ok(stats)
     }
 
+    /// Registers the Helm repositories used by the charts to sync in the project-local
+    /// repository configuration, then updates only those repositories.
+    ///
+    /// Returns the repositories that could not be registered with their error, so only their
+    /// charts fail.
+    fn prepare_repositories<F>(
+        &self,
+        config: &Config,
+        charts: &[&Chart],
+        observer: &F,
+    ) -> HashMap<String, String>
+    where
+        F: Fn(SyncEvent),
+    {
+        let mut names: Vec<String> = Vec::new();
+        let mut unregistered = HashMap::new();
+        for chart in charts {
+            let Some(repo_name) = &chart.repo_name else {
+                continue;
+            };
+            let Some(repo) = config
+                .repositories
+                .iter()
+                .find(|r| r.name == *repo_name && r.r#type == RepoType::Helm)
+            else {
+                continue;
+            };
+            if names.contains(&repo.name) || unregistered.contains_key(&repo.name) {
+                continue;
+            }
+            match self.helm_client.repo_add(&repo.name, &repo.url) {
+                Ok(_) => names.push(repo.name.clone()),
+                Err(e) => {
+                    unregistered.insert(
+                        repo.name.clone(),
+                        format!("Failed to register repository '{}': {:#}", repo.name, e),
+                    );
+                }
+            }
+        }
+
+        if names.is_empty() {
+            return unregistered;
+        }
+
+        observer(SyncEvent::RepoUpdateStart);
+        match self.helm_client.repo_update(&names) {
+            Ok(_) => observer(SyncEvent::RepoUpdateSuccess),
+            Err(e) => observer(SyncEvent::RepoUpdateFailed(e.to_string())),
+        }
+        unregistered
+    }
+
     fn sync_single_chart(
         &self,
         repo: &crate::config::Repository,
@@ -171,7 +240,6 @@ impl<H: HelmClient, G: GitClient> SyncEngine<H, G> {
 
         match repo.r#type {
             RepoType::Helm => {
-                self.helm_client.repo_add(&repo.name, &repo.url)?;
                 self.helm_client
                     .pull(&repo.name, &chart.name, version, temp_path)?;
             }
@@ -252,7 +320,7 @@ mod tests {
     use super::*;
     use crate::clients::git::MockGitClient;
     use crate::clients::helm::MockHelmClient;
-    use crate::config::{Chart, Destination, Repository};
+    use crate::config::{Destination, Repository};
     use mockall::predicate::*;
 
     #[test]
@@ -260,7 +328,11 @@ mod tests {
         let mut helm_mock = MockHelmClient::new();
         let git_mock = MockGitClient::new();
 
-        helm_mock.expect_repo_update().times(1).returning(|| Ok(()));
+        helm_mock
+            .expect_repo_update()
+            .withf(|names| names == ["stable".to_string()])
+            .times(1)
+            .returning(|_| Ok(()));
 
         helm_mock
             .expect_repo_add()
@@ -293,16 +365,7 @@ mod tests {
                 version: Some("1.0.0".to_string()),
                 namespace: "default".to_string(),
                 dest: Some("default".to_string()),
-                chart_path: None,
-                no_sync: false,
-                no_deploy: false,
-                comment: None,
-                values_files: None,
-                helm_args_append: None,
-                helm_args_override: None,
-                values: None,
-                depends: None,
-                no_interpolation: false,
+                ..Default::default()
             }],
             destinations: vec![Destination {
                 name: "default".to_string(),
@@ -311,6 +374,7 @@ mod tests {
             vesshelm: None,
             variables_files: None,
             secrets_files: None,
+            policies: None,
         };
 
         let mut lockfile = Lockfile::default();
@@ -332,7 +396,11 @@ mod tests {
         let mut helm_mock = MockHelmClient::new();
         let git_mock = MockGitClient::new();
 
-        helm_mock.expect_repo_update().times(1).returning(|| Ok(()));
+        helm_mock
+            .expect_repo_update()
+            .withf(|names| names == ["stable".to_string()])
+            .times(1)
+            .returning(|_| Ok(()));
 
         helm_mock
             .expect_repo_add()
@@ -365,16 +433,7 @@ mod tests {
                 version: Some("1.0.0".to_string()),
                 namespace: "default".to_string(),
                 dest: Some(target_path.to_string()), // Direct path
-                chart_path: None,
-                no_sync: false,
-                no_deploy: false,
-                comment: None,
-                values_files: None,
-                helm_args_append: None,
-                helm_args_override: None,
-                values: None,
-                depends: None,
-                no_interpolation: false,
+                ..Default::default()
             }],
             destinations: vec![Destination {
                 name: "default".to_string(),
@@ -383,6 +442,7 @@ mod tests {
             vesshelm: None,
             variables_files: None,
             secrets_files: None,
+            policies: None,
         };
 
         let mut lockfile = Lockfile::default();
@@ -406,4 +466,80 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(target_path);
     }
+
+    #[test]
+    fn test_sync_registers_only_repositories_of_selected_charts() {
+        let mut helm_mock = MockHelmClient::new();
+        let git_mock = MockGitClient::new();
+        let dest = tempfile::tempdir().unwrap();
+
+        // `broken` is only used by a chart that is not selected
+        helm_mock
+            .expect_repo_add()
+            .with(eq("stable"), always())
+            .times(1)
+            .returning(|_, _| Ok(()));
+        helm_mock
+            .expect_repo_add()
+            .with(eq("flaky"), always())
+            .times(1)
+            .returning(|_, _| Err(anyhow::anyhow!("unreachable")));
+        helm_mock
+            .expect_repo_update()
+            .withf(|names| names == ["stable".to_string()])
+            .times(1)
+            .returning(|_| Ok(()));
+        helm_mock
+            .expect_pull()
+            .with(eq("stable"), eq("web"), eq("1.0.0"), always())
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        let engine = SyncEngine::new(helm_mock, git_mock);
+        let config: Config = serde_yaml_ng::from_str(&format!(
+            r#"
+repositories:
+  - name: stable
+    url: https://charts.example.com/stable
+  - name: flaky
+    url: https://charts.example.com/flaky
+  - name: broken
+    url: https://charts.example.com/broken
+charts:
+  - name: web
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: api
+    repo_name: flaky
+    version: 1.0.0
+    namespace: default
+  - name: db
+    repo_name: broken
+    version: 1.0.0
+    namespace: default
+destinations:
+  - name: default
+    path: {}
+"#,
+            dest.path().display()
+        ))
+        .unwrap();
+
+        let mut lockfile = Lockfile::default();
+        let options = SyncOptions {
+            ignore_skip: false,
+            charts: Some(vec!["web".to_string(), "api".to_string()]),
+        };
+
+        let stats = engine.sync(config, &mut lockfile, options, |_| {}).unwrap();
+        assert_eq!(
+            stats,
+            SyncStats {
+                synced: 1,
+                failed: 1,
+                skipped: 0,
+            }
+        );
+    }
 }
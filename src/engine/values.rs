@@ -0,0 +1,500 @@
+//! Merged values of a release, as helm computes them, with the file each value comes from.
+
+use crate::config::{Chart, Destination};
+use crate::engine::release::{PreparedRelease, get_destination_path};
+use anyhow::{Context, Result};
+use jsonschema::error::ValidationErrorKind;
+use serde_yaml_ng::Value;
+use std::path::{Path, PathBuf};
+
+/// Schema helm validates the values of a chart against.
+pub const SCHEMA_FILE: &str = "values.schema.json";
+
+/// Directory holding the chart sources, where helm reads the default `values.yaml` from.
+pub fn chart_directory(chart: &Chart, destinations: &[Destination]) -> Result<PathBuf> {
+    if chart.repo_name.is_none()
+        && let Some(path) = &chart.chart_path
+    {
+        return Ok(PathBuf::from(path));
+    }
+    let destination = get_destination_path(chart, destinations)?;
+    Ok(Path::new(&destination).join(chart.dir_name()))
+}
+
+/// Values files helm merges for a release, in order, with their source label.
+///
+/// Helm applies the chart defaults first, then every `-f` file in order.
+pub fn values_layers(
+    chart: &Chart,
+    destinations: &[Destination],
+    prepared: &PreparedRelease,
+) -> Result<Vec<(PathBuf, String)>> {
+    let mut layers = Vec::new();
+    let defaults = chart_directory(chart, destinations)?.join("values.yaml");
+    let defaults_label = defaults.display().to_string();
+    if defaults.exists() && prepared.values_sources.first() != Some(&defaults_label) {
+        layers.push((defaults, defaults_label));
+    }
+    layers.extend(
+        prepared
+            .values_files
+            .iter()
+            .cloned()
+            .zip(prepared.values_sources.iter().cloned()),
+    );
+    Ok(layers)
+}
+
+/// Reads and merges the values layers of a release.
+pub fn merge_layers(layers: &[(PathBuf, String)]) -> Result<ValuesNode> {
+    let mut merged = ValuesNode::Map(Vec::new());
+    for (path, source) in layers {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read values file {}", source))?;
+        let value: Value = serde_yaml_ng::from_str(&content)
+            .with_context(|| format!("Failed to parse values file {}", source))?;
+        if !value.is_null() {
+            merged.merge(&value, source);
+        }
+    }
+    Ok(merged)
+}
+
+/// Merged values, remembering which file each leaf comes from.
+pub enum ValuesNode {
+    Map(Vec<(Value, ValuesNode)>),
+    Leaf { value: Value, source: String },
+}
+
+impl ValuesNode {
+    fn from_value(value: &Value, source: &str) -> ValuesNode {
+        match value {
+            Value::Mapping(map) if !map.is_empty() => ValuesNode::Map(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), ValuesNode::from_value(v, source)))
+                    .collect(),
+            ),
+            _ => ValuesNode::Leaf {
+                value: value.clone(),
+                source: source.to_string(),
+            },
+        }
+    }
+
+    /// Merges like helm: maps are merged recursively, other values replace, `null` deletes.
+    pub fn merge(&mut self, value: &Value, source: &str) {
+        let (ValuesNode::Map(entries), Value::Mapping(map)) = (&mut *self, value) else {
+            *self = ValuesNode::from_value(value, source);
+            return;
+        };
+        for (key, child) in map {
+            let position = entries.iter().position(|(k, _)| k == key);
+            match (position, child) {
+                (Some(i), Value::Null) => {
+                    entries.remove(i);
+                }
+                (Some(i), _) => entries[i].1.merge(child, source),
+                (None, Value::Null) => {}
+                (None, _) => entries.push((key.clone(), ValuesNode::from_value(child, source))),
+            }
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            ValuesNode::Map(entries) => Value::Mapping(
+                entries
+                    .iter()
+                    .map(|(k, node)| (k.clone(), node.to_value()))
+                    .collect(),
+            ),
+            ValuesNode::Leaf { value, .. } => value.clone(),
+        }
+    }
+
+    /// Files defining the value at `path`, or its closest existing parent.
+    pub fn sources_at(&self, path: &[String]) -> Vec<&str> {
+        match (self, path.split_first()) {
+            (ValuesNode::Leaf { source, .. }, _) => vec![source.as_str()],
+            (ValuesNode::Map(entries), Some((segment, rest))) => {
+                match entries.iter().find(|(k, _)| key_matches(k, segment)) {
+                    Some((_, child)) => child.sources_at(rest),
+                    None => self.sources_at(&[]),
+                }
+            }
+            (ValuesNode::Map(entries), None) => {
+                let mut sources = Vec::new();
+                for (_, child) in entries {
+                    for source in child.sources_at(&[]) {
+                        if !sources.contains(&source) {
+                            sources.push(source);
+                        }
+                    }
+                }
+                sources
+            }
+        }
+    }
+}
+
+fn key_matches(key: &Value, segment: &str) -> bool {
+    match key {
+        Value::String(s) => s == segment,
+        Value::Number(n) => n.to_string() == segment,
+        Value::Bool(b) => b.to_string() == segment,
+        _ => false,
+    }
+}
+
+/// Merged value not matching the chart's `values.schema.json`.
+#[derive(Debug, PartialEq)]
+pub struct SchemaViolation {
+    /// Dotted key path of the offending value, empty for the root.
+    pub path: String,
+    /// Values files defining the offending value.
+    pub sources: Vec<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "(root)"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)?;
+        if !self.sources.is_empty() {
+            write!(f, " ({})", self.sources.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates the merged values of a release against the `values.schema.json` of its synced chart.
+///
+/// Charts without a schema, or not synced yet, have no violations.
+pub fn schema_violations(
+    chart: &Chart,
+    destinations: &[Destination],
+    prepared: &PreparedRelease,
+) -> Result<Vec<SchemaViolation>> {
+    let schema_path = chart_directory(chart, destinations)?.join(SCHEMA_FILE);
+    if !schema_path.exists() {
+        return Ok(Vec::new());
+    }
+    let schema: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&schema_path)
+            .with_context(|| format!("Failed to read {}", schema_path.display()))?,
+    )
+    .with_context(|| format!("Failed to parse {}", schema_path.display()))?;
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| anyhow::anyhow!("Invalid schema {}: {}", schema_path.display(), e))?;
+
+    let merged = merge_layers(&values_layers(chart, destinations, prepared)?)?;
+    let instance = serde_json::to_value(merged.to_value())
+        .context("Failed to convert the merged values to JSON")?;
+
+    Ok(validator
+        .iter_errors(&instance)
+        .map(|error| {
+            let path = pointer_segments(error.instance_path().as_str());
+            // An unexpected key is blamed on the file introducing it, not on the whole map
+            let mut blamed = path.clone();
+            if let ValidationErrorKind::AdditionalProperties { unexpected } = error.kind()
+                && let [key] = unexpected.as_slice()
+            {
+                blamed.push(key.clone());
+            }
+            SchemaViolation {
+                sources: merged
+                    .sources_at(&blamed)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+                path: path.join("."),
+                // Values may come from secrets, so they are never echoed
+                message: error.masked().to_string(),
+            }
+        })
+        .collect())
+}
+
+/// Values key the chart's default `values.yaml` does not define, most likely a typo.
+#[derive(Debug, PartialEq)]
+pub struct UnknownKey {
+    /// Dotted key path.
+    pub path: String,
+    /// Values file defining the key.
+    pub source: String,
+    /// Closest key defined at the same level.
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.path, self.source)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean '{}'?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists the keys of the values files and inline values of a release missing from the chart defaults.
+///
+/// Returns `None` when the chart has no default `values.yaml` (not synced yet). Keys under `global`,
+/// under a subchart, under a map left empty by the defaults or under `free_form_values` are not checked.
+pub fn unknown_keys(
+    chart: &Chart,
+    destinations: &[Destination],
+    prepared: &PreparedRelease,
+) -> Result<Option<Vec<UnknownKey>>> {
+    let chart_dir = chart_directory(chart, destinations)?;
+    let defaults_path = chart_dir.join("values.yaml");
+    if !defaults_path.exists() {
+        return Ok(None);
+    }
+    let defaults_label = defaults_path.display().to_string();
+    let mut allowed: Vec<String> = chart.free_form_values.clone().unwrap_or_default();
+    allowed.push("global".to_string());
+    allowed.extend(subcharts(&chart_dir)?);
+
+    let mut defaults = Value::Null;
+    let mut unknown = Vec::new();
+    for (path, source) in values_layers(chart, destinations, prepared)? {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read values file {}", source))?;
+        let value: Value = serde_yaml_ng::from_str(&content)
+            .with_context(|| format!("Failed to parse values file {}", source))?;
+        if source == defaults_label {
+            defaults = value;
+            continue;
+        }
+        let checker = KeyChecker {
+            allowed: &allowed,
+            source: &source,
+        };
+        checker.collect(&defaults, &value, &mut Vec::new(), &mut unknown);
+    }
+    Ok(Some(unknown))
+}
+
+/// Names under which the values of the chart dependencies are nested.
+fn subcharts(chart_dir: &Path) -> Result<Vec<String>> {
+    let chart_yaml = chart_dir.join("Chart.yaml");
+    if !chart_yaml.exists() {
+        return Ok(Vec::new());
+    }
+    let metadata: Value = serde_yaml_ng::from_str(
+        &std::fs::read_to_string(&chart_yaml)
+            .with_context(|| format!("Failed to read {}", chart_yaml.display()))?,
+    )
+    .with_context(|| format!("Failed to parse {}", chart_yaml.display()))?;
+    Ok(metadata
+        .get("dependencies")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter_map(|dep| dep.get("alias").or_else(|| dep.get("name")))
+        .filter_map(|name| name.as_str().map(str::to_string))
+        .collect())
+}
+
+struct KeyChecker<'a> {
+    /// Dotted key paths whose content is not checked.
+    allowed: &'a [String],
+    source: &'a str,
+}
+
+impl KeyChecker<'_> {
+    fn collect(
+        &self,
+        defaults: &Value,
+        values: &Value,
+        path: &mut Vec<String>,
+        out: &mut Vec<UnknownKey>,
+    ) {
+        let (Value::Mapping(defaults), Value::Mapping(values)) = (defaults, values) else {
+            return;
+        };
+        // Maps the chart leaves empty (`podAnnotations: {}`) accept any key
+        if defaults.is_empty() {
+            return;
+        }
+        for (key, child) in values {
+            let Some(key) = key_to_string(key) else {
+                continue;
+            };
+            path.push(key.clone());
+            let dotted = path.join(".");
+            if !self.allowed.contains(&dotted) {
+                match defaults.iter().find(|(k, _)| key_matches(k, &key)) {
+                    Some((_, default)) => self.collect(default, child, path, out),
+                    None => out.push(UnknownKey {
+                        path: dotted,
+                        source: self.source.to_string(),
+                        suggestion: closest_key(&key, defaults.keys()),
+                    }),
+                }
+            }
+            path.pop();
+        }
+    }
+}
+
+fn key_to_string(key: &Value) -> Option<String> {
+    match key {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Closest candidate to a mistyped key, if any is similar enough.
+fn closest_key<'a>(key: &str, candidates: impl Iterator<Item = &'a Value>) -> Option<String> {
+    candidates
+        .filter_map(key_to_string)
+        .map(|candidate| {
+            let score = strsim::normalized_damerau_levenshtein(key, &candidate);
+            (score, candidate)
+        })
+        .filter(|(score, _)| *score >= 0.6)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Splits a JSON pointer into its unescaped segments.
+fn pointer_segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(layers: &[(&str, &str)]) -> ValuesNode {
+        let mut node = ValuesNode::Map(Vec::new());
+        for (source, content) in layers {
+            let value: Value = serde_yaml_ng::from_str(content).unwrap();
+            node.merge(&value, source);
+        }
+        node
+    }
+
+    #[test]
+    fn test_merge_like_helm() {
+        let node = merged(&[
+            ("values.yaml", "replicas: 1\nimage:\n  tag: '1.0'\nextra: x"),
+            (
+                "prod.yaml",
+                "replicas: 3\nimage:\n  pullPolicy: Always\nextra: null",
+            ),
+        ]);
+
+        let expected: Value =
+            serde_yaml_ng::from_str("replicas: 3\nimage:\n  tag: '1.0'\n  pullPolicy: Always")
+                .unwrap();
+        assert_eq!(node.to_value(), expected);
+    }
+
+    #[test]
+    fn test_sources_at() {
+        let node = merged(&[
+            ("values.yaml", "replicas: 1\nimage:\n  tag: '1.0'"),
+            ("prod.yaml", "replicas: 3\nimage:\n  pullPolicy: Always"),
+        ]);
+
+        assert_eq!(node.sources_at(&["replicas".into()]), vec!["prod.yaml"]);
+        assert_eq!(
+            node.sources_at(&["image".into()]),
+            vec!["values.yaml", "prod.yaml"]
+        );
+        // Missing keys are reported on their parent
+        assert_eq!(
+            node.sources_at(&["image".into(), "registry".into()]),
+            vec!["values.yaml", "prod.yaml"]
+        );
+    }
+
+    fn unknown(defaults: &str, values: &str, allowed: &[String]) -> Vec<UnknownKey> {
+        let defaults: Value = serde_yaml_ng::from_str(defaults).unwrap();
+        let values: Value = serde_yaml_ng::from_str(values).unwrap();
+        let checker = KeyChecker {
+            allowed,
+            source: "prod.yaml",
+        };
+        let mut out = Vec::new();
+        checker.collect(&defaults, &values, &mut Vec::new(), &mut out);
+        out
+    }
+
+    #[test]
+    fn test_unknown_keys_with_suggestions() {
+        let defaults =
+            "replicaCount: 1\nresources: {}\nimage:\n  repository: nginx\n  tag: latest\n";
+        let out = unknown(
+            defaults,
+            "resouces:\n  limits:\n    cpu: 1\nimage:\n  tga: stable\nextra: true\n",
+            &[],
+        );
+
+        assert_eq!(
+            out,
+            vec![
+                UnknownKey {
+                    path: "resouces".into(),
+                    source: "prod.yaml".into(),
+                    suggestion: Some("resources".into()),
+                },
+                UnknownKey {
+                    path: "image.tga".into(),
+                    source: "prod.yaml".into(),
+                    suggestion: Some("tag".into()),
+                },
+                UnknownKey {
+                    path: "extra".into(),
+                    source: "prod.yaml".into(),
+                    suggestion: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_skips_free_form_maps() {
+        let defaults =
+            "podAnnotations: {}\ningress:\n  annotations:\n    a: b\n  enabled: false\nenv: []\n";
+        let values = "podAnnotations:\n  team: web\ningress:\n  annotations:\n    c: d\nenv:\n  - name: A\nglobal:\n  registry: r\n";
+
+        assert_eq!(
+            unknown(defaults, values, &["global".into()])
+                .iter()
+                .map(|k| k.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ingress.annotations.c"]
+        );
+        assert!(
+            unknown(
+                defaults,
+                values,
+                &["global".into(), "ingress.annotations".into()]
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_pointer_segments() {
+        assert_eq!(pointer_segments(""), Vec::<String>::new());
+        assert_eq!(
+            pointer_segments("/ingress/annotations/a~1b"),
+            vec!["ingress", "annotations", "a/b"]
+        );
+    }
+}
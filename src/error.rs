@@ -23,6 +23,12 @@ pub enum VesshelmError {
     #[error("Helm error: {0}")]
     Helm(String),
 
+    #[error("Changes detected for chart(s): {}", .0.join(", "))]
+    ChangesDetected(Vec<String>),
+
+    #[error("Tests failed for chart {0}: {1}")]
+    HelmTestFailed(String, String),
+
     #[error("Unknown error: {0}")]
     Unknown(#[from] anyhow::Error),
 }
@@ -1,7 +1,8 @@
+use crate::config::Chart;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Lockfile {
@@ -9,11 +10,44 @@ pub struct Lockfile {
     pub charts: Vec<SyncedChart>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Version synced for a chart instance, identified by its release and namespace.
+///
+/// Entries written before instances were tracked have no `release`/`namespace`; they are
+/// attributed to the matching instances by [`Lockfile::migrate`].
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct SyncedChart {
     pub name: String,
     pub repo_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
     pub version: String,
+    /// Directory the chart was synced to. Entries written before it was recorded are trusted
+    /// for the default directory of the chart only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+impl SyncedChart {
+    /// Whether `dir`, the current directory of the chart, is the one this entry was synced to.
+    pub fn is_synced_to(&self, chart: &Chart, dir: &Path) -> bool {
+        match &self.dir {
+            Some(synced) => synced == dir,
+            None => !chart.versioned_dir,
+        }
+    }
+
+    fn is_legacy(&self) -> bool {
+        self.release.is_none() || self.namespace.is_none()
+    }
+
+    fn matches(&self, chart: &Chart) -> bool {
+        chart.repo_name.as_deref() == Some(self.repo_name.as_str())
+            && self.name == chart.name
+            && self.release.as_deref() == Some(chart.release_name())
+            && self.namespace.as_deref() == Some(chart.namespace.as_str())
+    }
 }
 
 impl Lockfile {
@@ -34,30 +68,143 @@ impl Lockfile {
         Ok(())
     }
 
-    pub fn get(&self, name: &str, repo_name: &str) -> Option<&SyncedChart> {
-        self.charts
-            .iter()
-            .find(|c| c.name == name && c.repo_name == repo_name)
+    /// Converts entries keyed by `(name, repo_name)` into one entry per configured instance.
+    ///
+    /// Each instance of the chart inherits the legacy version; legacy entries without any
+    /// instance left in the configuration are dropped.
+    pub fn migrate(&mut self, charts: &[Chart]) {
+        let (legacy, mut current): (Vec<_>, Vec<_>) =
+            self.charts.drain(..).partition(SyncedChart::is_legacy);
+
+        for entry in legacy {
+            for chart in charts {
+                let is_instance = chart.name == entry.name
+                    && chart.repo_name.as_deref() == Some(entry.repo_name.as_str());
+                if is_instance && !current.iter().any(|c| c.matches(chart)) {
+                    current.push(SyncedChart {
+                        name: entry.name.clone(),
+                        repo_name: entry.repo_name.clone(),
+                        release: Some(chart.release_name().to_string()),
+                        namespace: Some(chart.namespace.clone()),
+                        version: entry.version.clone(),
+                        dir: None,
+                    });
+                }
+            }
+        }
+
+        self.charts = current;
     }
 
-    pub fn update(&mut self, name: String, repo_name: String, version: String) {
-        if let Some(existing) = self
-            .charts
-            .iter_mut()
-            .find(|c| c.name == name && c.repo_name == repo_name)
-        {
+    pub fn get(&self, chart: &Chart) -> Option<&SyncedChart> {
+        self.charts.iter().find(|c| c.matches(chart))
+    }
+
+    /// Records the version of a remote chart instance synced to `dir`.
+    pub fn update(&mut self, chart: &Chart, version: String, dir: &Path) {
+        let Some(repo_name) = &chart.repo_name else {
+            return;
+        };
+        if let Some(existing) = self.charts.iter_mut().find(|c| c.matches(chart)) {
             existing.version = version;
+            existing.dir = Some(dir.to_path_buf());
         } else {
             self.charts.push(SyncedChart {
-                name,
-                repo_name,
+                name: chart.name.clone(),
+                repo_name: repo_name.clone(),
+                release: Some(chart.release_name().to_string()),
+                namespace: Some(chart.namespace.clone()),
                 version,
+                dir: Some(dir.to_path_buf()),
             });
         }
     }
 
-    pub fn remove(&mut self, name: &str, repo_name: &str) {
-        self.charts
-            .retain(|c| !(c.name == name && c.repo_name == repo_name));
+    pub fn remove(&mut self, chart: &Chart) {
+        self.charts.retain(|c| !c.matches(chart));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(name: &str, release: Option<&str>, namespace: &str, version: &str) -> Chart {
+        let mut chart: Chart = serde_yaml_ng::from_str(&format!(
+            "name: {}\nrepo_name: bitnami\nversion: {}\nnamespace: {}\n",
+            name, version, namespace
+        ))
+        .unwrap();
+        chart.release = release.map(String::from);
+        chart
+    }
+
+    #[test]
+    fn test_instances_have_separate_entries() {
+        let cache = chart("redis", Some("cache"), "data", "17.0.0");
+        let queue = chart("redis", Some("queue"), "data", "18.0.0");
+
+        let mut lockfile = Lockfile::default();
+        lockfile.update(
+            &cache,
+            "17.0.0".to_string(),
+            Path::new("charts/redis-17.0.0"),
+        );
+        lockfile.update(
+            &queue,
+            "18.0.0".to_string(),
+            Path::new("charts/redis-18.0.0"),
+        );
+        lockfile.update(
+            &queue,
+            "18.1.0".to_string(),
+            Path::new("charts/redis-18.1.0"),
+        );
+
+        assert_eq!(lockfile.charts.len(), 2);
+        assert_eq!(lockfile.get(&cache).unwrap().version, "17.0.0");
+        assert_eq!(lockfile.get(&queue).unwrap().version, "18.1.0");
+
+        assert!(
+            lockfile
+                .get(&queue)
+                .unwrap()
+                .is_synced_to(&queue, Path::new("charts/redis-18.1.0"))
+        );
+        // A directory synced for another version is stale
+        assert!(
+            !lockfile
+                .get(&queue)
+                .unwrap()
+                .is_synced_to(&queue, Path::new("charts/redis"))
+        );
+
+        lockfile.remove(&cache);
+        assert!(lockfile.get(&cache).is_none());
+        assert!(lockfile.get(&queue).is_some());
+    }
+
+    #[test]
+    fn test_migrate_legacy_entries() {
+        let mut lockfile: Lockfile = serde_yaml_ng::from_str(
+            "charts:\n- name: redis\n  repo_name: bitnami\n  version: 17.0.0\n- name: removed\n  repo_name: bitnami\n  version: 1.0.0\n",
+        )
+        .unwrap();
+        let charts = vec![
+            chart("redis", None, "default", "17.0.0"),
+            chart("redis", Some("queue"), "data", "18.0.0"),
+        ];
+
+        lockfile.migrate(&charts);
+
+        assert_eq!(lockfile.charts.len(), 2);
+        assert_eq!(lockfile.get(&charts[0]).unwrap().version, "17.0.0");
+        // The instance keeps the legacy version, so its new version is synced
+        assert_eq!(lockfile.get(&charts[1]).unwrap().version, "17.0.0");
+
+        let saved = serde_yaml_ng::to_string(&lockfile).unwrap();
+        assert!(saved.contains("release: queue"));
+        assert!(saved.contains("namespace: data"));
+        assert!(!saved.contains("removed"));
     }
 }
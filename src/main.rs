@@ -16,7 +16,7 @@ async fn main() {
             Commands::Sync(args) => {
                 commands::sync::run(args.clone(), cli.no_progress, config_path).await
             }
-            Commands::Validate => commands::validate::run(config_path).await,
+            Commands::Validate(args) => commands::validate::run(args.clone(), config_path).await,
             Commands::Deploy(args) => {
                 commands::deploy::run(args.clone(), cli.no_progress, config_path).await
             }
@@ -52,6 +52,27 @@ async fn main() {
                 )
                 .await
             }
+            Commands::Rollback(args) => {
+                commands::rollback::run(
+                    args.clone(),
+                    config_path,
+                    &vesshelm::util::interaction::TerminalInteraction,
+                )
+                .await
+            }
+            Commands::Diff(args) => {
+                commands::diff::run(args.clone(), cli.no_progress, config_path).await
+            }
+            Commands::Status(args) => commands::status::run(args.clone(), config_path).await,
+            Commands::History(args) => commands::history::run(args.clone(), config_path).await,
+            Commands::Values(args) => commands::values::run(args.clone(), config_path).await,
+            Commands::Export(args) => commands::export::run(args.clone(), config_path).await,
+            Commands::Import(args) => commands::import::run(args.clone(), config_path).await,
+            Commands::Images(args) => commands::images::run(args.clone(), config_path).await,
+            Commands::Bundle(args) => commands::bundle::run(args.clone(), config_path),
+            Commands::Template(args) => {
+                commands::template::run(args.clone(), cli.no_progress, config_path).await
+            }
         }
     };
 
@@ -80,6 +101,6 @@ async fn main() {
 
     if let Err(e) = result {
         eprintln!("{}", vesshelm::util::error::format_error(&e));
-        std::process::exit(1);
+        std::process::exit(vesshelm::util::error::exit_code(&e));
     }
 }
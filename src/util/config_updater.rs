@@ -1,10 +1,13 @@
-use crate::config::{RepoType, Repository};
+use crate::config::{Chart, RepoType, Repository};
 use anyhow::{Context, Result, anyhow};
 use regex::Regex;
 use std::path::Path;
 
 pub struct ConfigUpdater;
 
+/// `(key, value)` pairs identifying a list item.
+type ItemFields<'a> = Vec<(&'a str, &'a str)>;
+
 impl ConfigUpdater {
     pub fn update(config_path: &Path, repo: Option<Repository>, chart: ChartConfig) -> Result<()> {
         let mut file_content =
@@ -36,10 +39,16 @@ impl ConfigUpdater {
     }
 
     fn add_chart(content: &mut String, chart: &ChartConfig) {
-        let mut chart_block = format!(
-            "\n  - name: {}\n    repo_name: {}\n    namespace: {}",
-            chart.name, chart.repo_name, chart.namespace
-        );
+        let mut chart_block = format!("\n  - name: {}", chart.name);
+
+        if let Some(release) = &chart.release {
+            chart_block.push_str(&format!("\n    release: {}", release));
+        }
+
+        chart_block.push_str(&format!(
+            "\n    repo_name: {}\n    namespace: {}",
+            chart.repo_name, chart.namespace
+        ));
 
         if let Some(v) = &chart.version {
             chart_block.push_str(&format!("\n    version: {}", v));
@@ -53,6 +62,13 @@ impl ConfigUpdater {
             chart_block.push_str(&format!("\n    comment: {}", comment));
         }
 
+        if let Some(files) = &chart.values_files {
+            chart_block.push_str("\n    values_files:");
+            for file in files {
+                chart_block.push_str(&format!("\n      - {}", file));
+            }
+        }
+
         if let Some(idx) = content.find("charts:") {
             let insert_at = idx + "charts:".len();
             content.insert_str(insert_at, &chart_block);
@@ -64,81 +80,66 @@ impl ConfigUpdater {
 
     pub fn update_chart_version(
         config_path: &Path,
-        chart_name: &str,
+        chart: &Chart,
         new_version: &str,
     ) -> Result<()> {
         let mut file_content =
             std::fs::read_to_string(config_path).context("Failed to read config file")?;
 
-        Self::replace_chart_version_in_text(&mut file_content, chart_name, new_version)?;
+        Self::replace_chart_version_in_text(&mut file_content, chart, new_version)?;
 
         std::fs::write(config_path, file_content).context("Failed to write updated config")?;
         Ok(())
     }
 
+    /// Replaces the `version` of the item of `chart`, matched by name, release and namespace so
+    /// every instance of a chart is updated on its own.
     pub fn replace_chart_version_in_text(
         content: &mut String,
-        chart_name: &str,
+        chart: &Chart,
         new_version: &str,
     ) -> Result<()> {
-        // 1. Find the line that starts the chart block: `name: <chart_name>`
-        let name_regex = Regex::new(&format!(
-            r"(?m)^(\s*-\s*|\s*)name:\s*{}\s*$",
-            regex::escape(chart_name)
-        ))?;
-
-        let mat = name_regex
-            .find(content)
-            .ok_or_else(|| anyhow!("Chart {} not found", chart_name))?;
-        let start_idx = mat.end(); // We start search after the name line
-
-        // 2. Scan lines after the name to find `version:`
-        let version_regex = Regex::new(r"(?m)^(\s*)version:\s*([^\s]+)")?;
-
-        let suffix = &content[start_idx..];
-
-        // Find first version match
-        if let Some(v_cap) = version_regex.captures(suffix) {
-            // Check if there is a "danger marker" before this match.
-            let match_start_in_suffix = v_cap.get(0).unwrap().start();
-            let pre_match_text = &suffix[..match_start_in_suffix];
-
-            // Check if pre_match_text contains a new list item marker `- name:` or `- ` at base level.
-            let danger_regex = Regex::new(r"(?m)^\s*(-\s*)?name:")?;
-            if danger_regex.is_match(pre_match_text) {
-                return Err(anyhow!(
-                    "Found version field but it belongs to another chart (crossed boundary)"
-                ));
-            }
-
-            // Safe to replace
-            let version_val_match = v_cap
-                .get(2) // Group 2 is value
-                .ok_or_else(|| anyhow!("Failed to get version value capture group"))?;
-            let range = version_val_match.range();
-
-            // Calculate absolute range in original content
-            let abs_start = start_idx + range.start;
-            let abs_end = start_idx + range.end;
-
-            content.replace_range(abs_start..abs_end, new_version);
-            return Ok(());
-        }
-
-        Err(anyhow!(
-            "Could not find version field for chart {}",
-            chart_name
-        ))
+        let (fields, accept) =
+            Self::chart_item_matcher(&chart.name, chart.release.as_deref(), &chart.namespace)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let (item_start, item_end) =
+            Self::find_list_item_by_fields(&lines, "charts", &fields, accept)?
+                .ok_or_else(|| anyhow!("Chart {} not found", chart.release_name()))?;
+
+        // Only the value is replaced, a trailing comment is kept
+        let version_re = Regex::new(r"^(\s*(?:- )?version:\s*)([^\s#]+)")?;
+        let idx = (item_start..item_end)
+            .find(|&i| version_re.is_match(lines[i]))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Could not find version field for chart {}",
+                    chart.release_name()
+                )
+            })?;
+
+        let mut new_lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        new_lines[idx] = version_re
+            .replace(lines[idx], |caps: &regex::Captures| {
+                format!("{}{}", &caps[1], new_version)
+            })
+            .into_owned();
+
+        let trailing_newline = content.ends_with('\n');
+        *content = new_lines.join("\n") + if trailing_newline { "\n" } else { "" };
+        Ok(())
     }
-    pub fn remove_chart(config_path: &Path, chart_name: &str, namespace: &str) -> Result<()> {
+    pub fn remove_chart(
+        config_path: &Path,
+        chart_name: &str,
+        release: Option<&str>,
+        namespace: &str,
+    ) -> Result<()> {
         let file_content =
             std::fs::read_to_string(config_path).context("Failed to read config file")?;
 
-        let new_content = Self::remove_list_item_by_fields(
-            &file_content,
-            "charts",
-            &[("name", chart_name), ("namespace", namespace)],
-        )?;
+        let (fields, accept) = Self::chart_item_matcher(chart_name, release, namespace)?;
+        let new_content =
+            Self::remove_matching_list_item(&file_content, "charts", &fields, accept)?;
 
         if file_content != new_content {
             std::fs::write(config_path, new_content).context("Failed to write updated config")?;
@@ -162,22 +163,131 @@ impl ConfigUpdater {
         Ok(())
     }
 
+    /// Marks a chart `no_sync: true`, replacing its `no_sync` field if it has one.
+    pub fn set_chart_no_sync(
+        config_path: &Path,
+        chart_name: &str,
+        release: Option<&str>,
+        namespace: &str,
+    ) -> Result<()> {
+        let file_content =
+            std::fs::read_to_string(config_path).context("Failed to read config file")?;
+
+        let (fields, accept) = Self::chart_item_matcher(chart_name, release, namespace)?;
+        let lines: Vec<&str> = file_content.lines().collect();
+        let (item_start, item_end) =
+            Self::find_list_item_by_fields(&lines, "charts", &fields, accept)?
+                .ok_or_else(|| anyhow!("Chart {} not found", chart_name))?;
+
+        let mut new_lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        // Only the value is replaced, a trailing comment is kept
+        let no_sync_re = Regex::new(r"^(\s*(?:- )?)no_sync:[^#]*?(\s+#.*)?$")?;
+        if let Some(idx) = (item_start..item_end).find(|&i| no_sync_re.is_match(lines[i])) {
+            new_lines[idx] = no_sync_re
+                .replace(lines[idx], "${1}no_sync: true${2}")
+                .into_owned();
+        } else {
+            // Keys of the item are aligned with the text following its dash
+            let first = lines[item_start];
+            let dash = first.find('-').unwrap_or(0);
+            let key_indent =
+                dash + 1 + first[dash + 1..].len() - first[dash + 1..].trim_start().len();
+            new_lines.insert(
+                item_start + 1,
+                format!("{}no_sync: true", " ".repeat(key_indent)),
+            );
+        }
+
+        let new_content = new_lines.join("\n")
+            + if file_content.ends_with('\n') {
+                "\n"
+            } else {
+                ""
+            };
+        std::fs::write(config_path, new_content).context("Failed to write updated config")?;
+        Ok(())
+    }
+
+    /// Fields identifying a chart instance in `charts`, and the check of the rest of its item.
+    ///
+    /// Without `release`, the instance is the one whose release is its name: an item with
+    /// another `release` is a different instance of the same chart.
+    fn chart_item_matcher<'a>(
+        chart_name: &'a str,
+        release: Option<&'a str>,
+        namespace: &'a str,
+    ) -> Result<(ItemFields<'a>, impl Fn(&[&str]) -> bool)> {
+        let mut fields = vec![("name", chart_name), ("namespace", namespace)];
+        if let Some(release) = release {
+            fields.push(("release", release));
+        }
+        let any_release = Regex::new(r"^\s*(- )?release:")?;
+        let default_release = Self::field_regex("release", chart_name)?;
+        let check_release = release.is_none();
+        let accept = move |block: &[&str]| {
+            !check_release
+                || !block.iter().any(|l| any_release.is_match(l))
+                || block.iter().any(|l| default_release.is_match(l))
+        };
+        Ok((fields, accept))
+    }
+
+    /// Regex matching a `key: value` line, with optional quotes and comment.
+    fn field_regex(key: &str, value: &str) -> Result<Regex> {
+        Regex::new(&format!(
+            r"(?m)^\s*(- )?{}:\s*['\x22]?{}['\x22]?\s*(#.*)?$",
+            regex::escape(key),
+            regex::escape(value)
+        ))
+        .map_err(|e| anyhow!("Regex error: {}", e))
+    }
+
     /// Generic helper to remove a list item that matches specific field values.
     fn remove_list_item_by_fields(
         content: &str,
         section_key: &str,
         fields: &[(&str, &str)], // (key, value) pairs that must ALL match
+    ) -> Result<String> {
+        Self::remove_matching_list_item(content, section_key, fields, |_| true)
+    }
+
+    /// Removes the first list item whose fields match and whose lines pass `accept`.
+    fn remove_matching_list_item(
+        content: &str,
+        section_key: &str,
+        fields: &[(&str, &str)], // (key, value) pairs that must ALL match
+        accept: impl Fn(&[&str]) -> bool,
     ) -> Result<String> {
         let lines: Vec<&str> = content.lines().collect();
 
+        match Self::find_list_item_by_fields(&lines, section_key, fields, accept)? {
+            Some((item_start, item_end)) => {
+                // Remove this block [item_start, item_end)
+                let mut new_lines = lines.clone();
+                new_lines.drain(item_start..item_end);
+                // If we removed the last item and left a dangling "charts:", that's technically valid valid YAML (empty list/null), but cleaner to keep it.
+                // Note: This naive join loses specific line endings (\n vs \r\n) if mixed, but std join uses \n.
+                Ok(new_lines.join("\n") + if content.ends_with("\n") { "\n" } else { "" })
+            }
+            None => Ok(content.to_string()),
+        }
+    }
+
+    /// Line range `[start, end)` of the first item of `section_key` whose fields match and
+    /// whose lines pass `accept`.
+    fn find_list_item_by_fields(
+        lines: &[&str],
+        section_key: &str,
+        fields: &[(&str, &str)], // (key, value) pairs that must ALL match
+        accept: impl Fn(&[&str]) -> bool,
+    ) -> Result<Option<(usize, usize)>> {
         // 1. Find section start
-        let section_idx = lines
+        let Some(section_idx) = lines
             .iter()
-            .position(|l| l.trim_start().starts_with(&format!("{}:", section_key)));
-        if section_idx.is_none() {
-            return Ok(content.to_string());
-        }
-        let section_idx = section_idx.unwrap();
+            .position(|l| l.trim_start().starts_with(&format!("{}:", section_key)))
+        else {
+            return Ok(None);
+        };
 
         // 2. Iterate items
         let mut i = section_idx + 1;
@@ -280,12 +390,7 @@ impl ConfigUpdater {
                     // Let's use Regex.
                     // We assume values don't span multiple lines for now (names usually don't).
                     // Regex to match `key: value` with optional quotes and comments
-                    let re = Regex::new(&format!(
-                        r"(?m)^\s*(- )?{}:\s*['\x22]?{}['\x22]?\s*(#.*)?$",
-                        regex::escape(key),
-                        regex::escape(val)
-                    ))
-                    .map_err(|e| anyhow!("Regex error: {}", e))?;
+                    let re = Self::field_regex(key, val)?;
 
                     let found = block_lines.iter().any(|l| re.is_match(l));
                     if !found {
@@ -294,17 +399,8 @@ impl ConfigUpdater {
                     }
                 }
 
-                if all_matched {
-                    // Remove this block [item_start, item_end)
-                    // We construct new string without these lines.
-                    let mut new_lines = lines.clone();
-                    new_lines.drain(item_start..item_end);
-                    // Join with newline.
-                    // If we removed the last item and left a dangling "charts:", that's technically valid valid YAML (empty list/null), but cleaner to keep it.
-                    // Note: This naive join loses specific line endings (\n vs \r\n) if mixed, but std join uses \n.
-                    return Ok(
-                        new_lines.join("\n") + if content.ends_with("\n") { "\n" } else { "" }
-                    );
+                if all_matched && accept(block_lines) {
+                    return Ok(Some((item_start, item_end)));
                 }
 
                 // Move i to next item
@@ -315,17 +411,20 @@ impl ConfigUpdater {
             i += 1;
         }
 
-        Ok(content.to_string())
+        Ok(None)
     }
 }
 
 pub struct ChartConfig {
     pub name: String,
+    /// Helm release name, when it differs from the chart name.
+    pub release: Option<String>,
     pub repo_name: String,
     pub namespace: String,
     pub version: Option<String>,
     pub chart_path: Option<String>,
     pub comment: Option<String>,
+    pub values_files: Option<Vec<String>>,
 }
 
 #[cfg(test)]
@@ -367,11 +466,13 @@ mod tests {
         let mut content = "charts:\n".to_string();
         let chart = ChartConfig {
             name: "my-chart".to_string(),
+            release: None,
             repo_name: "my-repo".to_string(),
             namespace: "default".to_string(),
             version: Some("1.2.3".to_string()),
             chart_path: Some("charts/foo".to_string()),
             comment: None,
+            values_files: None,
         };
         ConfigUpdater::add_chart(&mut content, &chart);
         assert!(content.contains("- name: my-chart"));
@@ -380,6 +481,10 @@ mod tests {
         assert!(content.contains("chart_path: charts/foo"));
     }
 
+    fn chart(yaml: &str) -> Chart {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
     #[test]
     fn test_verify_regex_replacement() {
         let mut yaml_content = r#"
@@ -387,19 +492,24 @@ charts:
   - name: chart1
     repo_name: repo1
     version: 1.0.0 # comment
+    namespace: ns1
     other: field
   - name: chart2
     version: 2.0.0
+    namespace: ns1
     # comment line
     some: value
 "#
         .to_string();
 
-        let chart_name = "chart1";
         let new_version = "1.0.1";
 
-        ConfigUpdater::replace_chart_version_in_text(&mut yaml_content, chart_name, new_version)
-            .expect("replacement failed");
+        ConfigUpdater::replace_chart_version_in_text(
+            &mut yaml_content,
+            &chart("name: chart1\nnamespace: ns1\n"),
+            new_version,
+        )
+        .expect("replacement failed");
 
         // Verify changes
         assert!(yaml_content.contains("version: 1.0.1 # comment"));
@@ -413,17 +523,69 @@ charts:
 charts:
   - name: my-chart
     version: v1.2.3
+    namespace: ns1
   - name: other
     version: 1.0.0
+    namespace: ns1
 "#
         .to_string();
 
-        ConfigUpdater::replace_chart_version_in_text(&mut yaml_content_2, "my-chart", "v1.2.4")
-            .expect("replacement failed");
+        ConfigUpdater::replace_chart_version_in_text(
+            &mut yaml_content_2,
+            &chart("name: my-chart\nnamespace: ns1\n"),
+            "v1.2.4",
+        )
+        .expect("replacement failed");
         assert!(yaml_content_2.contains("version: v1.2.4"));
         assert!(!yaml_content_2.contains("version: v1.2.3"));
     }
 
+    #[test]
+    fn test_replace_chart_version_of_each_instance() {
+        let mut content = r#"charts:
+  - name: redis
+    version: 1.0.0
+    namespace: data
+  - name: redis
+    release: queue
+    version: 1.0.0
+    namespace: data
+  - name: redis
+    version: 1.0.0
+    namespace: cache
+"#
+        .to_string();
+
+        ConfigUpdater::replace_chart_version_in_text(
+            &mut content,
+            &chart("name: redis\nrelease: queue\nnamespace: data\n"),
+            "2.0.0",
+        )
+        .unwrap();
+        ConfigUpdater::replace_chart_version_in_text(
+            &mut content,
+            &chart("name: redis\nnamespace: cache\n"),
+            "3.0.0",
+        )
+        .unwrap();
+
+        assert_eq!(
+            content,
+            r#"charts:
+  - name: redis
+    version: 1.0.0
+    namespace: data
+  - name: redis
+    release: queue
+    version: 2.0.0
+    namespace: data
+  - name: redis
+    version: 3.0.0
+    namespace: cache
+"#
+        );
+    }
+
     #[test]
     fn test_remove_list_item() {
         let content = r#"# start
@@ -560,13 +722,73 @@ charts:
 "#;
         temp.write_all(content.as_bytes()).unwrap();
 
-        ConfigUpdater::remove_chart(temp.path(), "to-remove", "default").unwrap();
+        ConfigUpdater::remove_chart(temp.path(), "to-remove", None, "default").unwrap();
 
         let new_content = std::fs::read_to_string(temp.path()).unwrap();
         assert!(!new_content.contains("name: to-remove"));
         assert!(new_content.contains("name: keep"));
     }
 
+    #[test]
+    fn test_remove_chart_without_release_skips_other_instances() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp = NamedTempFile::new().unwrap();
+        let content = r#"charts:
+  - name: redis
+    release: cache
+    namespace: data
+  - name: redis
+    namespace: data
+"#;
+        temp.write_all(content.as_bytes()).unwrap();
+
+        ConfigUpdater::remove_chart(temp.path(), "redis", None, "data").unwrap();
+
+        let new_content = std::fs::read_to_string(temp.path()).unwrap();
+        assert_eq!(
+            new_content,
+            "charts:\n  - name: redis\n    release: cache\n    namespace: data\n"
+        );
+    }
+
+    #[test]
+    fn test_set_chart_no_sync() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut temp = NamedTempFile::new().unwrap();
+        let content = r#"charts:
+  - name: redis
+    release: cache
+    namespace: data
+  - name: redis
+    release: queue
+    namespace: data
+    no_sync: false # synced on demand
+"#;
+        temp.write_all(content.as_bytes()).unwrap();
+
+        ConfigUpdater::set_chart_no_sync(temp.path(), "redis", Some("cache"), "data").unwrap();
+        ConfigUpdater::set_chart_no_sync(temp.path(), "redis", Some("queue"), "data").unwrap();
+
+        let new_content = std::fs::read_to_string(temp.path()).unwrap();
+        assert_eq!(
+            new_content,
+            r#"charts:
+  - name: redis
+    no_sync: true
+    release: cache
+    namespace: data
+  - name: redis
+    release: queue
+    namespace: data
+    no_sync: true # synced on demand
+"#
+        );
+    }
+
     #[test]
     fn test_remove_repository_io() {
         use std::io::Write;
@@ -1,7 +1,7 @@
 use crate::config::Chart;
 use anyhow::{Result, anyhow};
 use daggy::{Dag, NodeIndex, Walker, WouldCycle};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Sorts charts based on their dependencies using topological sort.
 /// Returns a vector of references to charts in the order they should be deployed.
@@ -12,19 +12,19 @@ pub fn sort_charts(charts: &[Chart]) -> Result<Vec<&Chart>> {
     // 1. Add all nodes to the DAG
     for chart in charts {
         let node_index = dag.add_node(chart);
-        node_map.insert(chart.name.clone(), node_index);
+        node_map.insert(chart.release_name().to_string(), node_index);
     }
 
     // 2. Add edges based on dependencies
     for chart in charts {
         if let Some(deps) = &chart.depends {
-            let child_node = node_map[&chart.name]; // Must exist, we just added it
+            let child_node = node_map[chart.release_name()]; // Must exist, we just added it
 
             for dep_name in deps {
                 let parent_node = node_map.get(dep_name).ok_or_else(|| {
                     anyhow!(
                         "Chart '{}' depends on unknown chart '{}'",
-                        chart.name,
+                        chart.release_name(),
                         dep_name
                     )
                 })?;
@@ -34,7 +34,7 @@ pub fn sort_charts(charts: &[Chart]) -> Result<Vec<&Chart>> {
                     .map_err(|WouldCycle(_)| {
                         anyhow!(
                             "Circular dependency detected involving chart '{}'",
-                            chart.name
+                            chart.release_name()
                         )
                     })?;
             }
@@ -65,52 +65,52 @@ pub fn sort_charts(charts: &[Chart]) -> Result<Vec<&Chart>> {
     Ok(sorted_charts)
 }
 
-/// Returns a list of charts that depend on the given target chart.
-pub fn get_dependents<'a>(charts: &'a [Chart], target_name: &str) -> Result<Vec<&'a Chart>> {
+type ChartDag<'a> = (Dag<&'a Chart, ()>, HashMap<String, NodeIndex>);
+
+/// Builds the dependency graph of the charts (edges go from a dependency to its dependent).
+fn build_dag(charts: &[Chart]) -> Result<ChartDag<'_>> {
     let mut dag = Dag::<&Chart, ()>::new();
     let mut node_map: HashMap<String, NodeIndex> = HashMap::new();
 
-    // 1. Add all nodes to the DAG
     for chart in charts {
         let node_index = dag.add_node(chart);
-        node_map.insert(chart.name.clone(), node_index);
+        node_map.insert(chart.release_name().to_string(), node_index);
     }
 
-    // 2. Add edges based on dependencies (parent -> child)
     for chart in charts {
         if let Some(deps) = &chart.depends {
-            let child_node = node_map[&chart.name];
+            let child_node = node_map[chart.release_name()];
 
             for dep_name in deps {
-                if let Some(parent_node) = node_map.get(dep_name) {
-                    // Add edge: dependent_on -> dependent
-                    dag.add_edge(*parent_node, child_node, ())
-                        .map_err(|WouldCycle(_)| {
-                            anyhow!(
-                                "Circular dependency detected involving chart '{}'",
-                                chart.name
-                            )
-                        })?;
-                }
-                // If dependency is missing, we can ignore it here as we only care about existing relationships
-                // or we could error. sort_charts errors. Let's error to be safe.
-                else {
-                    return Err(anyhow!(
+                let parent_node = node_map.get(dep_name).ok_or_else(|| {
+                    anyhow!(
                         "Chart '{}' depends on unknown chart '{}'",
-                        chart.name,
+                        chart.release_name(),
                         dep_name
-                    ));
-                }
+                    )
+                })?;
+                dag.add_edge(*parent_node, child_node, ())
+                    .map_err(|WouldCycle(_)| {
+                        anyhow!(
+                            "Circular dependency detected involving chart '{}'",
+                            chart.release_name()
+                        )
+                    })?;
             }
         }
     }
 
-    // 3. Find target node
+    Ok((dag, node_map))
+}
+
+/// Returns a list of charts that depend on the given target chart.
+pub fn get_dependents<'a>(charts: &'a [Chart], target_name: &str) -> Result<Vec<&'a Chart>> {
+    let (dag, node_map) = build_dag(charts)?;
+
     let target_node = node_map
         .get(target_name)
         .ok_or_else(|| anyhow!("Chart '{}' not found", target_name))?;
 
-    // 4. Get dependents (children)
     // children() returns an iterator of (EdgeIndex, NodeIndex)
     let dependents: Vec<&Chart> = dag
         .children(*target_node)
@@ -127,6 +127,66 @@ pub fn get_dependents<'a>(charts: &'a [Chart], target_name: &str) -> Result<Vec<
     Ok(dependents)
 }
 
+/// Expands a selection of chart names with their transitive dependencies and/or dependents.
+///
+/// The result keeps the configuration order; callers sort it with [`sort_charts`].
+pub fn expand_selection(
+    charts: &[Chart],
+    selected: &[String],
+    with_dependencies: bool,
+    with_dependents: bool,
+) -> Result<Vec<String>> {
+    let (dag, node_map) = build_dag(charts)?;
+
+    let mut visited: HashSet<NodeIndex> = HashSet::new();
+    let mut stack: Vec<NodeIndex> = selected
+        .iter()
+        .map(|name| {
+            node_map
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow!("Chart '{}' not found", name))
+        })
+        .collect::<Result<_>>()?;
+
+    while let Some(node) = stack.pop() {
+        if !visited.insert(node) {
+            continue;
+        }
+        if with_dependencies {
+            stack.extend(dag.parents(node).iter(&dag).map(|(_, parent)| parent));
+        }
+        if with_dependents {
+            stack.extend(dag.children(node).iter(&dag).map(|(_, child)| child));
+        }
+    }
+
+    Ok(charts
+        .iter()
+        .filter(|chart| visited.contains(&node_map[chart.release_name()]))
+        .map(|chart| chart.release_name().to_string())
+        .collect())
+}
+
+/// Direct dependencies of the selected charts that are not selected, as `(chart, dependency)`.
+pub fn excluded_dependencies<'a>(
+    charts: &'a [Chart],
+    selected: &[String],
+) -> Vec<(&'a str, &'a str)> {
+    charts
+        .iter()
+        .filter(|chart| selected.iter().any(|n| n == chart.release_name()))
+        .flat_map(|chart| {
+            chart
+                .depends
+                .iter()
+                .flatten()
+                .filter(|dep| !selected.contains(dep))
+                .map(move |dep| (chart.release_name(), dep.as_str()))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,17 +198,8 @@ mod tests {
             repo_name: Some("test".to_string()),
             version: Some("1.0.0".to_string()),
             namespace: "default".to_string(),
-            dest: None,
-            chart_path: None,
-            no_sync: false,
-            no_deploy: false,
-            comment: None,
-            values_files: None,
-            helm_args_append: None,
-            helm_args_override: None,
-            values: None,
             depends,
-            no_interpolation: false,
+            ..Default::default()
         }
     }
 
@@ -229,4 +280,53 @@ mod tests {
         let deps_grandchild = get_dependents(&charts, "grandchild").unwrap();
         assert!(deps_grandchild.is_empty());
     }
+    #[test]
+    fn test_expand_selection() {
+        let charts = vec![
+            create_chart("postgres", None),
+            create_chart("redis", None),
+            create_chart(
+                "api",
+                Some(vec!["postgres".to_string(), "redis".to_string()]),
+            ),
+            create_chart("web", Some(vec!["api".to_string()])),
+            create_chart("docs", None),
+        ];
+        let names = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            expand_selection(&charts, &names(&["web"]), true, false).unwrap(),
+            names(&["postgres", "redis", "api", "web"])
+        );
+        assert_eq!(
+            expand_selection(&charts, &names(&["postgres"]), false, true).unwrap(),
+            names(&["postgres", "api", "web"])
+        );
+        assert_eq!(
+            expand_selection(&charts, &names(&["api"]), true, true).unwrap(),
+            names(&["postgres", "redis", "api", "web"])
+        );
+        assert_eq!(
+            expand_selection(&charts, &names(&["api"]), false, false).unwrap(),
+            names(&["api"])
+        );
+        assert!(expand_selection(&charts, &names(&["missing"]), true, false).is_err());
+    }
+
+    #[test]
+    fn test_excluded_dependencies() {
+        let charts = vec![
+            create_chart("postgres", None),
+            create_chart("redis", None),
+            create_chart(
+                "api",
+                Some(vec!["postgres".to_string(), "redis".to_string()]),
+            ),
+        ];
+        let selected = vec!["api".to_string(), "redis".to_string()];
+        assert_eq!(
+            excluded_dependencies(&charts, &selected),
+            vec![("api", "postgres")]
+        );
+    }
 }
@@ -0,0 +1,253 @@
+use console::style;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Header emitted by helm-diff before the changes of each resource, e.g.
+/// `default, my-app, Deployment (apps) has changed:`.
+static RESOURCE_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\S*), (\S+), (\S+) \(([^)]*)\) has (changed|been added|been removed):\s*$")
+        .expect("valid resource header regex")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// Changes of a single Kubernetes resource in a diff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceChange {
+    pub namespace: String,
+    pub name: String,
+    pub kind: String,
+    /// API group (or version for core resources) as printed by helm-diff.
+    pub group: String,
+    pub change: ChangeKind,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    /// Raw (possibly colored) lines of the resource, header included.
+    pub lines: Vec<String>,
+}
+
+impl ResourceChange {
+    /// Secrets and ConfigMaps have their data counted but never displayed.
+    pub fn is_sensitive(&self) -> bool {
+        matches!(self.kind.as_str(), "Secret" | "ConfigMap")
+    }
+}
+
+/// Splits helm-diff output into per-resource changes.
+///
+/// Lines printed before the first resource header (warnings, etc.) are ignored.
+pub fn parse_helm_diff(output: &str) -> Vec<ResourceChange> {
+    let mut changes: Vec<ResourceChange> = Vec::new();
+
+    for line in output.lines() {
+        let plain = console::strip_ansi_codes(line);
+
+        if let Some(caps) = RESOURCE_HEADER.captures(&plain) {
+            let change = match &caps[5] {
+                "been added" => ChangeKind::Added,
+                "been removed" => ChangeKind::Removed,
+                _ => ChangeKind::Modified,
+            };
+            changes.push(ResourceChange {
+                namespace: caps[1].to_string(),
+                name: caps[2].to_string(),
+                kind: caps[3].to_string(),
+                group: caps[4].to_string(),
+                change,
+                lines_added: 0,
+                lines_removed: 0,
+                lines: vec![line.to_string()],
+            });
+            continue;
+        }
+
+        if let Some(current) = changes.last_mut() {
+            if plain.starts_with('+') {
+                current.lines_added += 1;
+            } else if plain.starts_with('-') {
+                current.lines_removed += 1;
+            }
+            current.lines.push(line.to_string());
+        }
+    }
+
+    changes
+}
+
+/// How a non-empty diff is displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffView {
+    /// Print a per-resource table instead of the raw diff.
+    pub summary: bool,
+    /// With `summary`, also print the full diff after the table (sensitive data hidden).
+    pub full_diff: bool,
+}
+
+impl DiffView {
+    pub fn render(&self, output: &str) -> String {
+        if !self.summary {
+            return output.to_string();
+        }
+
+        let changes = parse_helm_diff(output);
+        let mut rendered = render_summary(&changes);
+        if self.full_diff {
+            rendered.push('\n');
+            rendered.push_str(&render_full(&changes));
+        }
+        rendered
+    }
+}
+
+/// Compact table of the changed resources followed by a totals line.
+pub fn render_summary(changes: &[ResourceChange]) -> String {
+    let rows: Vec<[String; 4]> = changes
+        .iter()
+        .map(|c| {
+            let mut lines = format!("+{} -{}", c.lines_added, c.lines_removed);
+            if c.is_sensitive() {
+                lines.push_str(" (data hidden)");
+            }
+            [
+                format!("{} ({})", c.kind, c.group),
+                c.namespace.clone(),
+                c.name.clone(),
+                lines,
+            ]
+        })
+        .collect();
+
+    let mut widths = [0; 4];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut output = String::new();
+    for (row, change) in rows.iter().zip(changes) {
+        let marker = match change.change {
+            ChangeKind::Added => style("+ added   ").green(),
+            ChangeKind::Modified => style("~ modified").yellow(),
+            ChangeKind::Removed => style("- removed ").red(),
+        };
+        output.push_str(&format!(
+            "  {}  {:<w0$}  {:<w1$}  {:<w2$}  {}\n",
+            marker,
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+        ));
+    }
+
+    let count = |kind: ChangeKind| changes.iter().filter(|c| c.change == kind).count();
+    output.push_str(&format!(
+        "  {} resource(s): {} added, {} modified, {} removed\n",
+        changes.len(),
+        count(ChangeKind::Added),
+        count(ChangeKind::Modified),
+        count(ChangeKind::Removed)
+    ));
+    output
+}
+
+/// Full diff, with the body of sensitive resources replaced by a line count.
+pub fn render_full(changes: &[ResourceChange]) -> String {
+    let mut output = String::new();
+    for change in changes {
+        if change.is_sensitive() {
+            output.push_str(&change.lines[0]);
+            output.push('\n');
+            output.push_str(&format!(
+                "  (+{} -{} line(s), content hidden)\n\n",
+                change.lines_added, change.lines_removed
+            ));
+        } else {
+            for line in &change.lines {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+default, my-app, Deployment (apps) has changed:
+  # Source: my-app/templates/deployment.yaml
+  spec:
+-   replicas: 1
++   replicas: 2
+default, my-config, ConfigMap (v1) has been added:
++ apiVersion: v1
++ data:
++   password: hunter2
+monitoring, old-svc, Service (v1) has been removed:
+- apiVersion: v1
+- kind: Service
+";
+
+    #[test]
+    fn test_parse_helm_diff() {
+        let changes = parse_helm_diff(DIFF);
+        assert_eq!(changes.len(), 3);
+
+        assert_eq!(changes[0].kind, "Deployment");
+        assert_eq!(changes[0].group, "apps");
+        assert_eq!(changes[0].namespace, "default");
+        assert_eq!(changes[0].name, "my-app");
+        assert_eq!(changes[0].change, ChangeKind::Modified);
+        assert_eq!((changes[0].lines_added, changes[0].lines_removed), (1, 1));
+
+        assert_eq!(changes[1].change, ChangeKind::Added);
+        assert!(changes[1].is_sensitive());
+        assert_eq!(changes[1].lines_added, 3);
+
+        assert_eq!(changes[2].namespace, "monitoring");
+        assert_eq!(changes[2].change, ChangeKind::Removed);
+        assert_eq!(changes[2].lines_removed, 2);
+    }
+
+    #[test]
+    fn test_parse_helm_diff_with_colors() {
+        let colored = "\u{1b}[33mdefault, my-app, Deployment (apps) has changed:\u{1b}[0m\n\u{1b}[32m+   replicas: 2\u{1b}[0m\n";
+        let changes = parse_helm_diff(colored);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].name, "my-app");
+        assert_eq!(changes[0].lines_added, 1);
+    }
+
+    #[test]
+    fn test_summary_hides_sensitive_data() {
+        let view = DiffView {
+            summary: true,
+            full_diff: true,
+        };
+        let rendered = console::strip_ansi_codes(&view.render(DIFF)).to_string();
+
+        assert!(rendered.contains("Deployment (apps)"));
+        assert!(rendered.contains("(data hidden)"));
+        assert!(rendered.contains("3 resource(s): 1 added, 1 modified, 1 removed"));
+        assert!(rendered.contains("replicas: 2"));
+        assert!(rendered.contains("content hidden"));
+        assert!(!rendered.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_default_view_is_raw() {
+        assert_eq!(DiffView::default().render(DIFF), DIFF);
+    }
+}
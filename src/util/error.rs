@@ -1,7 +1,18 @@
+use crate::error::VesshelmError;
 use anyhow::Error;
 use colored::Colorize;
 use validator::{ValidationErrors, ValidationErrorsKind};
 
+/// Process exit code for a failed command.
+///
+/// `2` signals that changes were detected (e.g. by `vesshelm diff`), `1` any other failure.
+pub fn exit_code(err: &Error) -> i32 {
+    match err.downcast_ref::<VesshelmError>() {
+        Some(VesshelmError::ChangesDetected(_)) => 2,
+        _ => 1,
+    }
+}
+
 /// Formats an error for display.
 ///
 /// If the error is a `ValidationErrors` (possibly wrapped), it will be formatted as a list of issues.
@@ -92,7 +103,7 @@ fn format_field_error(field: &str, error: &validator::ValidationError) -> String
                 .and_then(|v| v.as_str())
                 .unwrap_or("unknown");
             format!(
-                "  - {}: Duplicate chart detected: name='{}', namespace='{}'",
+                "  - {}: Duplicate chart detected: release='{}', namespace='{}'",
                 "Error".red(),
                 name.yellow(),
                 namespace.yellow()
@@ -157,6 +168,14 @@ mod tests {
         assert!(formatted.contains("Error:"));
     }
 
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&Error::msg("boom")), 1);
+        let changes = Error::from(VesshelmError::ChangesDetected(vec!["nginx".to_string()]));
+        assert_eq!(exit_code(&changes), 2);
+        assert!(format_error(&changes).contains("nginx"));
+    }
+
     #[test]
     fn test_format_validation_errors() {
         let mut errors = ValidationErrors::new();
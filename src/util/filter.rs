@@ -1,6 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
+
+use crate::config::Chart;
+use console::style;
 
 /// Validates that all charts specified in `chart_args` exist in `available_names`.
 ///
@@ -37,6 +42,233 @@ pub fn validate_chart_args(available_names: &[&str], chart_args: &[String]) -> R
     Ok(())
 }
 
+/// Slices an ordered list of names from `from` to `until` (both inclusive).
+///
+/// # Returns
+/// * The index range to keep.
+/// * `Err` if a bound does not exist or `until` comes before `from`.
+pub fn slice_range(
+    names: &[&str],
+    from: Option<&str>,
+    until: Option<&str>,
+) -> Result<std::ops::Range<usize>> {
+    let position = |name: &str| {
+        names
+            .iter()
+            .position(|n| *n == name)
+            .ok_or_else(|| anyhow::anyhow!("Chart '{}' is not part of the deployment order", name))
+    };
+
+    let start = from.map(position).transpose()?.unwrap_or(0);
+    let end = match until {
+        Some(name) => position(name)? + 1,
+        None => names.len(),
+    };
+
+    if end <= start {
+        bail!(
+            "Chart '{}' comes before '{}' in the deployment order",
+            until.unwrap_or_default(),
+            from.unwrap_or_default()
+        );
+    }
+
+    Ok(start..end)
+}
+
+/// A single requirement of a label selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LabelRequirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
+    NotExists(String),
+}
+
+impl LabelRequirement {
+    fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        match self {
+            LabelRequirement::Equals(key, value) => labels.get(key) == Some(value),
+            LabelRequirement::NotEquals(key, value) => labels.get(key) != Some(value),
+            LabelRequirement::Exists(key) => labels.contains_key(key),
+            LabelRequirement::NotExists(key) => !labels.contains_key(key),
+        }
+    }
+}
+
+/// Kubectl-style label selector, e.g. `tier=infra,env!=prod`.
+///
+/// Supported requirements are `key=value` (or `key==value`), `key!=value`, `key` and `!key`.
+/// All requirements must match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    requirements: Vec<LabelRequirement>,
+}
+
+impl Selector {
+    /// Whether the labels of a chart satisfy every requirement.
+    pub fn matches(&self, labels: Option<&BTreeMap<String, String>>) -> bool {
+        let empty = BTreeMap::new();
+        let labels = labels.unwrap_or(&empty);
+        self.requirements.iter().all(|r| r.matches(labels))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let valid_key = |key: &str| {
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+        };
+
+        let mut requirements = Vec::new();
+        for part in s.split(',').map(str::trim) {
+            let requirement = if let Some((key, value)) = part.split_once("!=") {
+                LabelRequirement::NotEquals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some((key, value)) =
+                part.split_once("==").or_else(|| part.split_once('='))
+            {
+                LabelRequirement::Equals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some(key) = part.strip_prefix('!') {
+                LabelRequirement::NotExists(key.trim().to_string())
+            } else {
+                LabelRequirement::Exists(part.to_string())
+            };
+
+            let key = match &requirement {
+                LabelRequirement::Equals(key, _)
+                | LabelRequirement::NotEquals(key, _)
+                | LabelRequirement::Exists(key)
+                | LabelRequirement::NotExists(key) => key,
+            };
+            if !valid_key(key) {
+                return Err(format!(
+                    "invalid requirement '{}' in selector '{}'",
+                    part, s
+                ));
+            }
+            requirements.push(requirement);
+        }
+
+        Ok(Self { requirements })
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .requirements
+            .iter()
+            .map(|r| match r {
+                LabelRequirement::Equals(key, value) => format!("{}={}", key, value),
+                LabelRequirement::NotEquals(key, value) => format!("{}!={}", key, value),
+                LabelRequirement::Exists(key) => key.clone(),
+                LabelRequirement::NotExists(key) => format!("!{}", key),
+            })
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// Whether a chart argument is a glob pattern rather than an exact name.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Resolves the chart names and the label selector given on the command line.
+///
+/// Names may be glob patterns (`monitoring-*`). A chart is selected when it matches one of the
+/// names (or no names are given) and the selector (if any).
+///
+/// # Returns
+/// * `None` if neither names nor selector are given (all charts).
+/// * The selected chart names, in configuration order.
+/// * `Err` if an exact name does not exist, a pattern matches no chart, or nothing is selected.
+pub fn select_charts(
+    charts: &[Chart],
+    names: Option<&[String]>,
+    selector: Option<&Selector>,
+) -> Result<Option<Vec<String>>> {
+    let names = names.filter(|n| !n.is_empty());
+    if names.is_none() && selector.is_none() {
+        return Ok(None);
+    }
+
+    let patterns = names
+        .unwrap_or_default()
+        .iter()
+        .map(|name| {
+            if is_glob(name) {
+                glob::Pattern::new(name)
+                    .map(Some)
+                    .with_context(|| format!("Invalid chart pattern '{}'", name))
+            } else {
+                Ok(None)
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Exact names keep the existing validation, patterns must match something
+    let exact: Vec<String> = names
+        .unwrap_or_default()
+        .iter()
+        .filter(|name| !is_glob(name))
+        .cloned()
+        .collect();
+    let available_names: Vec<_> = charts.iter().map(|c| c.release_name()).collect();
+    validate_chart_args(&available_names, &exact)?;
+
+    let matches_name = |chart: &Chart, name: &String, pattern: &Option<glob::Pattern>| match pattern
+    {
+        Some(pattern) => pattern.matches(chart.release_name()),
+        None => chart.release_name() == *name,
+    };
+
+    if let Some(names) = names {
+        for (name, pattern) in names.iter().zip(&patterns) {
+            if pattern.is_some() && !charts.iter().any(|c| matches_name(c, name, pattern)) {
+                bail!("No chart matches the pattern '{}'", name);
+            }
+        }
+    }
+
+    let selected: Vec<String> = charts
+        .iter()
+        .filter(|chart| {
+            names.is_none_or(|names| {
+                names
+                    .iter()
+                    .zip(&patterns)
+                    .any(|(name, pattern)| matches_name(chart, name, pattern))
+            })
+        })
+        .filter(|chart| selector.is_none_or(|s| s.matches(chart.labels.as_ref())))
+        .map(|chart| chart.release_name().to_string())
+        .collect();
+
+    if selected.is_empty() {
+        bail!("No chart matches the selection");
+    }
+
+    Ok(Some(selected))
+}
+
+/// Warns about dependencies left out of the chart selection.
+pub fn warn_excluded_dependencies(charts: &[Chart], selected: &[String]) {
+    for (chart, dependency) in crate::util::dag::excluded_dependencies(charts, selected) {
+        println!(
+            " {} '{}' depends on '{}', which is not selected (use --with-dependencies to include it)",
+            style("WARN:").yellow(),
+            style(chart).bold(),
+            style(dependency).bold()
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,4 +309,104 @@ mod tests {
         let only = vec![];
         assert!(validate_chart_args(&charts, &only).is_ok());
     }
+
+    #[test]
+    fn test_slice_range() {
+        let names = vec!["a", "b", "c", "d"];
+        assert_eq!(slice_range(&names, None, None).unwrap(), 0..4);
+        assert_eq!(slice_range(&names, Some("b"), None).unwrap(), 1..4);
+        assert_eq!(slice_range(&names, None, Some("c")).unwrap(), 0..3);
+        assert_eq!(slice_range(&names, Some("b"), Some("b")).unwrap(), 1..2);
+        assert!(slice_range(&names, Some("c"), Some("a")).is_err());
+        assert!(slice_range(&names, Some("missing"), None).is_err());
+    }
+
+    fn labeled_chart(name: &str, labels: &[(&str, &str)]) -> Chart {
+        let mut chart: Chart = serde_yaml_ng::from_str(&format!(
+            "name: {}\nrepo_name: stable\nversion: 1.0.0\nnamespace: default\n",
+            name
+        ))
+        .unwrap();
+        if !labels.is_empty() {
+            chart.labels = Some(
+                labels
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+        }
+        chart
+    }
+
+    #[test]
+    fn test_selector_parse_and_match() {
+        let selector: Selector = "tier=infra, env!=prod,team,!legacy".parse().unwrap();
+        assert_eq!(selector.to_string(), "tier=infra,env!=prod,team,!legacy");
+
+        let labels = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert!(selector.matches(Some(&labels(&[("tier", "infra"), ("team", "ops")]))));
+        assert!(!selector.matches(Some(&labels(&[
+            ("tier", "infra"),
+            ("team", "ops"),
+            ("env", "prod")
+        ]))));
+        assert!(!selector.matches(Some(&labels(&[
+            ("tier", "infra"),
+            ("team", "ops"),
+            ("legacy", "true")
+        ]))));
+        assert!(!selector.matches(None));
+
+        assert_eq!(
+            "tier==infra".parse::<Selector>().unwrap(),
+            "tier=infra".parse::<Selector>().unwrap()
+        );
+        assert!("tier=infra,".parse::<Selector>().is_err());
+        assert!("=infra".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn test_select_charts() {
+        let charts = vec![
+            labeled_chart("monitoring-prometheus", &[("tier", "infra")]),
+            labeled_chart("monitoring-grafana", &[("tier", "infra"), ("ui", "true")]),
+            labeled_chart("api", &[("tier", "app")]),
+            labeled_chart("web", &[]),
+        ];
+        let names = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let selector = |s: &str| s.parse::<Selector>().unwrap();
+
+        assert_eq!(select_charts(&charts, None, None).unwrap(), None);
+        assert_eq!(
+            select_charts(&charts, Some(&names(&["monitoring-*", "web"])), None).unwrap(),
+            Some(names(&[
+                "monitoring-prometheus",
+                "monitoring-grafana",
+                "web"
+            ]))
+        );
+        assert_eq!(
+            select_charts(&charts, None, Some(&selector("tier=infra"))).unwrap(),
+            Some(names(&["monitoring-prometheus", "monitoring-grafana"]))
+        );
+        assert_eq!(
+            select_charts(
+                &charts,
+                Some(&names(&["monitoring-*"])),
+                Some(&selector("!ui"))
+            )
+            .unwrap(),
+            Some(names(&["monitoring-prometheus"]))
+        );
+
+        assert!(select_charts(&charts, Some(&names(&["missing"])), None).is_err());
+        let err = select_charts(&charts, Some(&names(&["db-*"])), None).unwrap_err();
+        assert_eq!(err.to_string(), "No chart matches the pattern 'db-*'");
+        assert!(select_charts(&charts, None, Some(&selector("tier=db"))).is_err());
+    }
 }
@@ -0,0 +1,330 @@
+use anyhow::{Context, Result};
+use console::style;
+use serde::Deserialize;
+use serde_yaml_ng::Value;
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeMap;
+
+/// Identity of a Kubernetes object within a release.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ObjectKey {
+    pub namespace: String,
+    pub name: String,
+    pub kind: String,
+    /// API group, or the version for core objects (as displayed by helm-diff).
+    pub group: String,
+}
+
+/// Splits a multi-document manifest into objects keyed by identity.
+///
+/// Objects without namespace are attributed to `default_namespace`, like helm does on install.
+pub fn parse_manifest(
+    manifest: &str,
+    default_namespace: &str,
+) -> Result<BTreeMap<ObjectKey, Value>> {
+    let mut objects = BTreeMap::new();
+
+    for document in serde_yaml_ng::Deserializer::from_str(manifest) {
+        let value = Value::deserialize(document).context("Failed to parse manifest")?;
+        if value.is_null() {
+            continue;
+        }
+
+        let field = |path: &[&str]| -> Option<String> {
+            let mut current = &value;
+            for key in path {
+                current = current.get(*key)?;
+            }
+            current.as_str().map(String::from)
+        };
+
+        let (Some(kind), Some(name)) = (field(&["kind"]), field(&["metadata", "name"])) else {
+            continue;
+        };
+        let api_version = field(&["apiVersion"]).unwrap_or_default();
+        let group = match api_version.split_once('/') {
+            Some((group, _)) => group.to_string(),
+            None => api_version,
+        };
+        let namespace =
+            field(&["metadata", "namespace"]).unwrap_or_else(|| default_namespace.to_string());
+
+        objects.insert(
+            ObjectKey {
+                namespace,
+                name,
+                kind,
+                group,
+            },
+            value,
+        );
+    }
+
+    Ok(objects)
+}
+
+/// Pod spec of a workload object: the object spec of a Pod, the pod template of the other kinds
+/// (Deployment, StatefulSet, DaemonSet, ReplicaSet, Job) or the job template of a CronJob.
+pub fn pod_spec<'a>(kind: &str, object: &'a Value) -> Option<&'a Value> {
+    let spec = object.get("spec")?;
+    match kind {
+        "Pod" => Some(spec),
+        "CronJob" => spec
+            .get("jobTemplate")?
+            .get("spec")?
+            .get("template")?
+            .get("spec"),
+        _ => spec.get("template")?.get("spec"),
+    }
+}
+
+/// Replaces the values of `data` and `stringData` of a Secret pair.
+///
+/// Values that differ between both sides get distinct placeholders, so a change is still visible.
+fn redact_secrets(old: Option<&mut Value>, new: Option<&mut Value>) {
+    const FIELDS: [&str; 2] = ["data", "stringData"];
+
+    let old_data: Vec<Option<Value>> = FIELDS
+        .iter()
+        .map(|f| old.as_ref().and_then(|v| v.get(*f)).cloned())
+        .collect();
+
+    if let Some(new) = new {
+        for (field, old_field) in FIELDS.iter().zip(&old_data) {
+            if let Some(Value::Mapping(map)) = new.get_mut(*field) {
+                for (key, value) in map.iter_mut() {
+                    let unchanged = old_field
+                        .as_ref()
+                        .and_then(|o| o.get(key))
+                        .is_some_and(|old_value| old_value == value);
+                    *value = Value::from(if unchanged {
+                        "<redacted>"
+                    } else {
+                        "<redacted, new value>"
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(old) = old {
+        for field in FIELDS {
+            if let Some(Value::Mapping(map)) = old.get_mut(field) {
+                for (_, value) in map.iter_mut() {
+                    *value = Value::from("<redacted>");
+                }
+            }
+        }
+    }
+}
+
+/// Unified diff of two manifests, formatted like helm-diff output.
+///
+/// Only changed objects are printed; an empty string means no changes. Secret data is redacted.
+/// Hooks (and tests) are ignored: `helm template` renders them but `helm get manifest` omits them.
+pub fn diff_manifests(current: &str, desired: &str, default_namespace: &str) -> Result<String> {
+    let mut current = parse_manifest(current, default_namespace)?;
+    let mut desired = parse_manifest(desired, default_namespace)?;
+    current.retain(|_, object| !is_hook(object));
+    desired.retain(|_, object| !is_hook(object));
+
+    let mut keys: Vec<ObjectKey> = current.keys().chain(desired.keys()).cloned().collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut output = String::new();
+    for key in keys {
+        let mut old = current.remove(&key);
+        let mut new = desired.remove(&key);
+        if old == new {
+            continue;
+        }
+        if key.kind == "Secret" {
+            redact_secrets(old.as_mut(), new.as_mut());
+        }
+
+        let old_text = old.map(|v| to_yaml(&v)).transpose()?;
+        let new_text = new.map(|v| to_yaml(&v)).transpose()?;
+        let status = match (&old_text, &new_text) {
+            (None, _) => "has been added",
+            (_, None) => "has been removed",
+            _ => "has changed",
+        };
+        output.push_str(&format!(
+            "{}\n",
+            style(format!(
+                "{}, {}, {} ({}) {}:",
+                key.namespace, key.name, key.kind, key.group, status
+            ))
+            .yellow()
+        ));
+
+        let old_text = old_text.unwrap_or_default();
+        let new_text = new_text.unwrap_or_default();
+        let diff = TextDiff::from_lines(&old_text, &new_text);
+        for (index, group) in diff.grouped_ops(3).iter().enumerate() {
+            if index > 0 {
+                output.push_str(&format!("{}\n", style("...").dim()));
+            }
+            for op in group {
+                for change in diff.iter_changes(op) {
+                    let line = change.value().trim_end_matches('\n');
+                    let formatted = match change.tag() {
+                        ChangeTag::Delete => style(format!("- {}", line)).red().to_string(),
+                        ChangeTag::Insert => style(format!("+ {}", line)).green().to_string(),
+                        ChangeTag::Equal => format!("  {}", line),
+                    };
+                    output.push_str(&formatted);
+                    output.push('\n');
+                }
+            }
+        }
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// Whether an object is a helm hook, which helm does not store in the release manifest.
+fn is_hook(object: &Value) -> bool {
+    object
+        .get("metadata")
+        .and_then(|m| m.get("annotations"))
+        .and_then(|a| a.get("helm.sh/hook"))
+        .is_some()
+}
+
+fn to_yaml(value: &Value) -> Result<String> {
+    serde_yaml_ng::to_string(value).context("Failed to serialize manifest object")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT: &str = "\
+---
+# Source: app/templates/deployment.yaml
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: app
+spec:
+  replicas: 1
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: old
+  namespace: other
+---
+apiVersion: v1
+kind: Secret
+metadata:
+  name: creds
+data:
+  password: b2xk
+  user: YWRtaW4=
+";
+
+    const DESIRED: &str = "\
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: app
+spec:
+  replicas: 2
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: settings
+data:
+  key: value
+---
+apiVersion: v1
+kind: Secret
+metadata:
+  name: creds
+data:
+  password: bmV3
+  user: YWRtaW4=
+";
+
+    #[test]
+    fn test_parse_manifest() {
+        let objects = parse_manifest(CURRENT, "default").unwrap();
+        let keys: Vec<_> = objects.keys().collect();
+        assert_eq!(keys.len(), 3);
+        assert!(objects.contains_key(&ObjectKey {
+            namespace: "default".to_string(),
+            name: "app".to_string(),
+            kind: "Deployment".to_string(),
+            group: "apps".to_string(),
+        }));
+        assert!(objects.contains_key(&ObjectKey {
+            namespace: "other".to_string(),
+            name: "old".to_string(),
+            kind: "Service".to_string(),
+            group: "v1".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_diff_manifests() {
+        let diff = diff_manifests(CURRENT, DESIRED, "default").unwrap();
+        let diff = console::strip_ansi_codes(&diff);
+
+        assert!(diff.contains("default, app, Deployment (apps) has changed:"));
+        assert!(diff.contains("-   replicas: 1"));
+        assert!(diff.contains("+   replicas: 2"));
+        assert!(diff.contains("default, settings, ConfigMap (v1) has been added:"));
+        assert!(diff.contains("other, old, Service (v1) has been removed:"));
+
+        assert!(diff.contains("default, creds, Secret (v1) has changed:"));
+        assert!(diff.contains("+   password: <redacted, new value>"));
+        assert!(!diff.contains("bmV3"));
+        assert!(!diff.contains("b2xk"));
+        assert!(!diff.contains("YWRtaW4="));
+    }
+
+    #[test]
+    fn test_diff_manifests_no_changes() {
+        // Comments and document separators do not matter
+        let reformatted = CURRENT.replace("# Source: app/templates/deployment.yaml\n", "");
+        assert_eq!(
+            diff_manifests(CURRENT, &reformatted, "default").unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_diff_manifests_ignores_hooks() {
+        let with_hooks = format!(
+            "{}---\n{}",
+            CURRENT,
+            r#"apiVersion: batch/v1
+kind: Job
+metadata:
+  name: migrate
+  annotations:
+    helm.sh/hook: pre-upgrade
+---
+apiVersion: v1
+kind: Pod
+metadata:
+  name: app-test
+  annotations:
+    "helm.sh/hook": test
+"#
+        );
+        assert_eq!(diff_manifests(CURRENT, &with_hooks, "default").unwrap(), "");
+    }
+
+    #[test]
+    fn test_diff_parses_as_helm_diff() {
+        let diff = diff_manifests(CURRENT, DESIRED, "default").unwrap();
+        let changes = crate::util::diff::parse_helm_diff(&diff);
+        assert_eq!(changes.len(), 4);
+    }
+}
@@ -1,9 +1,12 @@
 pub mod config_updater;
 pub mod dag;
+pub mod diff;
 pub mod encryption;
 pub mod error;
 pub mod filter;
 pub mod helm;
 pub mod interaction;
+pub mod manifest;
 pub mod progress;
+pub mod state;
 pub mod variables;
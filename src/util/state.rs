@@ -0,0 +1,30 @@
+use std::path::{Path, PathBuf};
+
+/// Name of the directory holding vesshelm's project-local state.
+pub const STATE_DIR_NAME: &str = ".vesshelm";
+
+/// Returns the project-local state directory, located next to the configuration file.
+pub fn state_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .join(STATE_DIR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_dir_next_to_config() {
+        let dir = state_dir(Path::new("/work/project/vesshelm.yaml"));
+        assert_eq!(dir, PathBuf::from("/work/project/.vesshelm"));
+    }
+
+    #[test]
+    fn test_state_dir_relative_config() {
+        let dir = state_dir(Path::new("vesshelm.yaml"));
+        assert_eq!(dir, PathBuf::from("./.vesshelm"));
+    }
+}
@@ -50,7 +50,8 @@ pub fn render_values_file(path: &Path, context: &Value) -> Result<String> {
     render_template(&content, context, template_name)
 }
 
-fn render_template(content: &str, context: &Value, template_name: &str) -> Result<String> {
+/// Renders a template with the variables context; undefined variables are errors.
+pub fn render_template(content: &str, context: &Value, template_name: &str) -> Result<String> {
     let mut env = minijinja::Environment::new();
     env.set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
     env.add_template(template_name, content)?;
@@ -73,6 +74,7 @@ fn read_file_handling_encryption(path: &Path) -> Result<String> {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrows_for_generic_args)]
 mod tests {
     use super::*;
     use std::io::Write;
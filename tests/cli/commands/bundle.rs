@@ -0,0 +1,130 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+const CONFIG: &str = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: nginx
+    repo_name: stable
+    version: 1.0.0
+    namespace: web
+    values_files:
+      - values/nginx.yaml
+
+destinations:
+  - name: default
+    path: ./charts
+
+secrets_files:
+  - secrets.yaml
+"#;
+
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("vesshelm.yaml"), CONFIG).unwrap();
+    fs::write(
+        temp.path().join("vesshelm.lock"),
+        "charts:\n- name: nginx\n  repo_name: stable\n  release: nginx\n  namespace: web\n  version: 1.0.0\n",
+    )
+    .unwrap();
+    fs::create_dir_all(temp.path().join("charts/nginx/templates")).unwrap();
+    fs::write(
+        temp.path().join("charts/nginx/Chart.yaml"),
+        "name: nginx\nversion: 1.0.0\n",
+    )
+    .unwrap();
+    fs::write(
+        temp.path().join("charts/nginx/templates/deployment.yaml"),
+        "kind: Deployment\n",
+    )
+    .unwrap();
+    fs::create_dir_all(temp.path().join("values")).unwrap();
+    fs::write(temp.path().join("values/nginx.yaml"), "replicas: 2\n").unwrap();
+    fs::write(
+        temp.path().join("secrets.yaml"),
+        "password: ENC[AES256_GCM,data:abc]\nsops:\n  version: 3.8.1\n",
+    )
+    .unwrap();
+    temp
+}
+
+#[test]
+fn test_bundle_create_and_import_offline() {
+    let project = setup();
+    let bundle = project.path().join("site.tar.gz");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&project)
+        .args(["bundle", "create", "site.tar.gz"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 charts and 6 files bundled"));
+
+    let site = TempDir::new().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&site)
+        .args(["bundle", "import"])
+        .arg(&bundle)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("6 files verified and unpacked"))
+        .stdout(predicate::str::contains("1 remote charts marked no_sync"));
+
+    assert!(
+        site.path()
+            .join("charts/nginx/templates/deployment.yaml")
+            .exists()
+    );
+    assert_eq!(
+        fs::read_to_string(site.path().join("secrets.yaml")).unwrap(),
+        fs::read_to_string(project.path().join("secrets.yaml")).unwrap()
+    );
+
+    // Nothing left to fetch: sync succeeds without helm nor network
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&site)
+        .env("PATH", site.path())
+        .args(["--no-progress", "sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nginx (no_sync=true)"))
+        .stdout(predicate::str::contains("Synced:  0"));
+}
+
+#[test]
+fn test_bundle_create_requires_synced_charts() {
+    let project = setup();
+    fs::remove_dir_all(project.path().join("charts/nginx")).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&project)
+        .args(["bundle", "create", "site.tar.gz"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Chart 'nginx' is not synced (charts/nginx not found)",
+        ));
+}
+
+#[test]
+fn test_bundle_import_refuses_to_overwrite() {
+    let project = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&project)
+        .args(["bundle", "create", "site.tar.gz"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&project)
+        .args(["bundle", "import", "site.tar.gz"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --force to overwrite it"));
+}
@@ -20,6 +20,10 @@ fn test_check_updates_helm_outdated() {
     // We construct the script carefully to preserve newlines in the mocked output
     let helm_script = format!(
         r#"#!/bin/sh
+if [ "$1" = "repo" ] && [ "$2" = "add" ]; then
+    echo "Mock helm repo add"
+    exit 0
+fi
 if [ "$1" = "repo" ] && [ "$2" = "update" ]; then
     echo "Mock helm repo update"
     exit 0
@@ -91,6 +95,7 @@ fn test_check_updates_apply() {
 ";
     let helm_script = format!(
         r#"#!/bin/sh
+if [ "$1" = "repo" ] && [ "$2" = "add" ]; then exit 0; fi
 if [ "$1" = "repo" ] && [ "$2" = "update" ]; then exit 0; fi
 if [ "$1" = "search" ]; then
     cat <<EOF
@@ -144,6 +149,69 @@ destinations:
     assert!(new_content.contains("version: 1.2.0"));
 }
 
+#[test]
+fn test_check_updates_apply_several_instances() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let bin_dir = temp_dir.path().join("bin");
+    fs::create_dir(&bin_dir).unwrap();
+
+    let helm_path = bin_dir.join("helm");
+    let helm_script = r#"#!/bin/sh
+if [ "$1" = "search" ]; then
+    echo "- name: stable/nginx"
+    echo "  version: 1.2.0"
+fi
+exit 0
+"#;
+    fs::write(&helm_path, helm_script).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&helm_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&helm_path, perms).unwrap();
+    }
+
+    let config_path = temp_dir.path().join("vesshelm.yaml");
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+charts:
+  - name: nginx
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: nginx
+    release: internal
+    repo_name: stable
+    version: 1.1.0
+    namespace: default
+  - name: nginx
+    repo_name: stable
+    version: 1.0.0
+    namespace: edge
+destinations:
+  - name: default
+    path: ./charts
+"#;
+    fs::write(&config_path, config_content).unwrap();
+
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .env("PATH", format!("{}:{}", bin_dir.display(), path_env))
+        .arg("check-updates")
+        .arg("--apply")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated internal to 1.2.0"));
+
+    let new_content = fs::read_to_string(&config_path).unwrap();
+    assert_eq!(new_content.matches("version: 1.2.0").count(), 3);
+}
+
 #[test]
 fn test_check_updates_helm_failure() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -12,6 +12,7 @@ fn test_delete_non_existent_chart() {
     let config = Config {
         variables_files: None,
         secrets_files: None,
+        policies: None,
         repositories: vec![],
         charts: vec![],
         destinations: vec![],
@@ -45,17 +46,7 @@ fn test_delete_chart_no_interactive() {
         repo_name: Some("test-repo".to_string()),
         version: Some("1.0.0".to_string()),
         namespace: "default".to_string(),
-        dest: None,
-        chart_path: None,
-        no_sync: false,
-        no_deploy: false,
-        comment: None,
-        values_files: None,
-        helm_args_append: None,
-        helm_args_override: None,
-        values: None,
-        depends: None,
-        no_interpolation: false,
+        ..Default::default()
     };
 
     let config = Config {
@@ -72,6 +63,7 @@ fn test_delete_chart_no_interactive() {
         vesshelm: None,
         variables_files: None,
         secrets_files: None,
+        policies: None,
     };
 
     let yaml = serde_yaml_ng::to_string(&config).unwrap();
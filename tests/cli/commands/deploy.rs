@@ -554,6 +554,241 @@ vesshelm:
         .stdout(predicates::str::contains("-f values.yaml"));
 }
 
+#[test]
+fn test_deploy_rejects_values_not_matching_schema() {
+    let temp = TempDir::new().unwrap();
+    setup_mock_helm_initial(&temp);
+
+    fs::create_dir_all(temp.path().join("charts/chart-a")).unwrap();
+    fs::write(
+        temp.path().join("charts/chart-a/values.schema.json"),
+        r#"{"properties": {"replicaCount": {"type": "integer"}}}"#,
+    )
+    .unwrap();
+    fs::write(temp.path().join("values.yaml"), "replicaCount: two").unwrap();
+
+    let config_path = temp.path().join("vesshelm.yaml");
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    values_files:
+      - values.yaml
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade"
+"#;
+    fs::write(&config_path, config_content).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"));
+    cmd.current_dir(temp.path())
+        .env("PATH", temp.path())
+        .arg("deploy")
+        .arg("--no-interactive")
+        .arg("--no-progress")
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(
+            r#"replicaCount: value is not of type "integer" (values.yaml)"#,
+        ))
+        .stdout(predicates::str::contains(
+            "Values of chart-a do not match the chart's values.schema.json",
+        ))
+        .stdout(predicates::str::contains("Mock Helm called with").not());
+}
+
+/// Mock helm printing the values files as the rendered manifest of `helm template`.
+fn setup_mock_helm_template(temp_dir: &TempDir) {
+    let mock_helm_path = temp_dir.path().join("helm");
+    let mock_helm_content = r#"#!/bin/sh
+if [ "$1" = "template" ]; then
+    prev=""
+    for arg in "$@"; do
+        if [ "$prev" = "-f" ]; then
+            while IFS= read -r line; do echo "$line"; done < "$arg"
+        fi
+        prev="$arg"
+    done
+    exit 0
+fi
+echo "Mock Helm called with: $@"
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    fs::set_permissions(&mock_helm_path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn test_deploy_policies() {
+    let temp = TempDir::new().unwrap();
+    setup_mock_helm_template(&temp);
+
+    fs::write(
+        temp.path().join("manifest.yaml"),
+        r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  template:
+    spec:
+      containers:
+        - name: web
+          image: nginx:latest
+          resources:
+            limits:
+              cpu: 100m
+              memory: 64Mi
+"#,
+    )
+    .unwrap();
+
+    let config_content = |level: &str| {
+        format!(
+            r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    values_files:
+      - manifest.yaml
+
+destinations:
+  - name: default
+    path: ./charts
+
+policies:
+  builtin:
+    no_latest_tag: {}
+
+vesshelm:
+    helm_args: "upgrade --install chart-a ./charts/chart-a"
+"#,
+            level
+        )
+    };
+
+    fs::write(temp.path().join("vesshelm.yaml"), config_content("error")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(
+            "[no_latest_tag] Deployment/web: container 'web' uses image 'nginx:latest'",
+        ))
+        .stdout(predicates::str::contains("chart-a violates 1 policy"))
+        .stdout(predicates::str::contains("Mock Helm called with: upgrade").not());
+
+    fs::write(temp.path().join("vesshelm.yaml"), config_content("warn")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "[no_latest_tag] Deployment/web: container 'web' uses image 'nginx:latest'",
+        ))
+        .stdout(predicates::str::contains("Mock Helm called with: upgrade"));
+}
+
+#[test]
+fn test_deploy_policies_with_helm_args_override() {
+    let temp = TempDir::new().unwrap();
+    setup_mock_helm_template(&temp);
+
+    fs::write(
+        temp.path().join("manifest.yaml"),
+        r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  template:
+    spec:
+      containers:
+        - name: web
+          image: nginx:latest
+          resources:
+            limits:
+              cpu: 100m
+              memory: 64Mi
+"#,
+    )
+    .unwrap();
+
+    let config_content = |level: &str| {
+        format!(
+            r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    values_files:
+      - manifest.yaml
+    helm_args_override: "--kube-context prod upgrade --install chart-a ./charts/chart-a"
+
+destinations:
+  - name: default
+    path: ./charts
+
+policies:
+  builtin:
+    no_latest_tag: {}
+
+vesshelm:
+    helm_args: "upgrade --install chart-a ./charts/chart-a"
+"#,
+            level
+        )
+    };
+
+    // The override cannot be turned into helm template arguments: the chart is rendered alone
+    fs::write(temp.path().join("vesshelm.yaml"), config_content("error")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(
+            "[no_latest_tag] Deployment/web: container 'web' uses image 'nginx:latest'",
+        ))
+        .stdout(predicates::str::contains("chart-a violates 1 policy"));
+
+    fs::write(temp.path().join("vesshelm.yaml"), config_content("warn")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "Mock Helm called with: --kube-context prod upgrade",
+        ));
+}
+
 #[test]
 fn test_deploy_inline_values() {
     let temp = TempDir::new().unwrap();
@@ -984,3 +1219,715 @@ vesshelm:
         // YES. So it points to the original values.yaml in charts/skip-int-chart/values.yaml
         .stdout(predicates::str::contains("key: {{ global_var }}"));
 }
+
+#[test]
+fn test_deploy_review_first() {
+    let temp = TempDir::new().unwrap();
+    let mock_helm_path = temp.path().join("helm");
+    // Records every call; only chart-a has changes
+    let mock_helm_content = r#"#!/bin/sh
+echo "$@" >> "$(dirname "$0")/helm-calls.log"
+if [ "$1" = "diff" ]; then
+    case "$*" in
+        *chart-a*) echo "default, chart-a, Deployment (apps) has changed:" ;;
+    esac
+fi
+exit 0
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    let config_path = temp.path().join("vesshelm.yaml");
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: chart-b
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    depends: [chart-a]
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }} -n {{ namespace }}"
+"#;
+    fs::write(&config_path, config_content).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args([
+            "deploy",
+            "--review-first",
+            "--no-interactive",
+            "--no-progress",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deployment plan:"))
+        .stdout(predicate::str::contains("chart-b (no changes, skipped)"))
+        .stdout(predicate::str::contains("Deployed: 1"));
+
+    let calls = fs::read_to_string(temp.path().join("helm-calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(calls.len(), 3, "unexpected helm calls: {:?}", calls);
+    assert!(calls[0].starts_with("diff upgrade") && calls[0].contains("chart-a"));
+    assert!(calls[1].starts_with("diff upgrade") && calls[1].contains("chart-b"));
+    assert!(calls[2].starts_with("upgrade --install chart-a"));
+}
+
+fn setup_hooks_test(temp: &TempDir, chart_hooks: &str) {
+    let mock_helm_path = temp.path().join("helm");
+    let mock_helm_content = r#"#!/bin/sh
+echo "helm $@" >> "$(dirname "$0")/calls.log"
+exit 0
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    fs::write(temp.path().join("vars.yaml"), "db_host: postgres.local\n").unwrap();
+
+    let config_content = format!(
+        r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+variables_files:
+  - vars.yaml
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+{}
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{{{ name }}}} {{{{ destination }}}} -n {{{{ namespace }}}}"
+    diff_enabled: false
+    hooks:
+      post_deploy:
+        - echo "post {{{{ name }}}}" >> calls.log
+      on_failure:
+        - echo "failure {{{{ name }}}}" >> calls.log
+"#,
+        chart_hooks
+    );
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+}
+
+#[test]
+fn test_deploy_hooks() {
+    let temp = TempDir::new().unwrap();
+    setup_hooks_test(
+        &temp,
+        r#"    hooks:
+      pre_deploy:
+        - echo "pre {{ name }} {{ namespace }} {{ db_host }}" >> calls.log"#,
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "pre_deploy echo \"pre {{ name }} {{ namespace }} {{ db_host }}\" >> calls.log",
+        ))
+        .stdout(predicate::str::contains("postgres.local").not());
+
+    let calls = fs::read_to_string(temp.path().join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        vec![
+            "pre chart-a default postgres.local",
+            "helm upgrade --install chart-a ./charts -n default",
+            "post chart-a",
+        ]
+    );
+}
+
+#[test]
+fn test_deploy_failing_hook_does_not_leak_variables() {
+    let temp = TempDir::new().unwrap();
+    setup_hooks_test(
+        &temp,
+        r#"    hooks:
+      pre_deploy:
+        - test "{{ db_host }}" = other"#,
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "pre_deploy hook 'test \"{{ db_host }}\" = other' failed",
+        ))
+        .stdout(predicate::str::contains("postgres.local").not())
+        .stderr(predicate::str::contains("postgres.local").not());
+
+    let audit = fs::read_to_string(temp.path().join(".vesshelm/audit.jsonl")).unwrap();
+    assert!(audit.contains("pre_deploy hook"));
+    assert!(!audit.contains("postgres.local"));
+}
+
+#[test]
+fn test_deploy_failing_pre_hook_aborts_chart() {
+    let temp = TempDir::new().unwrap();
+    setup_hooks_test(
+        &temp,
+        r#"    hooks:
+      pre_deploy:
+        - exit 3"#,
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("pre_deploy hook 'exit 3' failed"));
+
+    let calls = fs::read_to_string(temp.path().join("calls.log")).unwrap();
+    assert_eq!(calls.lines().collect::<Vec<_>>(), vec!["failure chart-a"]);
+}
+
+#[test]
+fn test_deploy_schema_violation_runs_failure_hooks() {
+    let temp = TempDir::new().unwrap();
+    setup_hooks_test(
+        &temp,
+        r#"    values:
+      - replicaCount: two"#,
+    );
+    fs::create_dir_all(temp.path().join("charts/chart-a")).unwrap();
+    fs::write(
+        temp.path().join("charts/chart-a/values.schema.json"),
+        r#"{ "properties": { "replicaCount": { "type": "integer" } } }"#,
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Values of chart-a do not match the chart's values.schema.json",
+        ));
+
+    let calls = fs::read_to_string(temp.path().join("calls.log")).unwrap();
+    assert_eq!(calls.lines().collect::<Vec<_>>(), vec!["failure chart-a"]);
+}
+
+fn setup_helm_test_mock(temp: &TempDir, test_exit_code: i32) {
+    let mock_helm_path = temp.path().join("helm");
+    let mock_helm_content = format!(
+        r#"#!/bin/sh
+echo "helm $@" >> "$(dirname "$0")/calls.log"
+if [ "$1" = "test" ]; then
+    exit {}
+fi
+exit 0
+"#,
+        test_exit_code
+    );
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: database
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    test_after_deploy: true
+  - name: api
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    depends: [database]
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }} -n {{ namespace }}"
+    diff_enabled: false
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+}
+
+#[test]
+fn test_deploy_failed_helm_test_blocks_dependents() {
+    let temp = TempDir::new().unwrap();
+    setup_helm_test_mock(&temp, 1);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Tests failed for chart database"))
+        .stdout(predicate::str::contains("Tests:    0 passed, 1 failed"));
+
+    let calls = fs::read_to_string(temp.path().join("calls.log")).unwrap();
+    assert_eq!(
+        calls.lines().collect::<Vec<_>>(),
+        vec![
+            "helm upgrade --install database ./charts -n default",
+            "helm test database -n default",
+        ]
+    );
+}
+
+#[test]
+fn test_deploy_test_flag_runs_all_tests() {
+    let temp = TempDir::new().unwrap();
+    setup_helm_test_mock(&temp, 0);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--test", "--no-interactive", "--no-progress"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tests:    2 passed, 0 failed"));
+
+    let calls = fs::read_to_string(temp.path().join("calls.log")).unwrap();
+    assert!(calls.contains("helm test api -n default"));
+}
+
+fn setup_resume_test(temp: &TempDir) {
+    let mock_helm_path = temp.path().join("helm");
+    // Upgrading chart-b fails while the `fail-b` marker exists
+    let mock_helm_content = r#"#!/bin/sh
+dir="$(dirname "$0")"
+echo "helm $@" >> "$dir/calls.log"
+if [ "$3" = "chart-b" ] && [ -f "$dir/fail-b" ]; then
+    exit 1
+fi
+exit 0
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: chart-b
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    depends: [chart-a]
+  - name: chart-c
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    depends: [chart-b]
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }} -n {{ namespace }}"
+    diff_enabled: false
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+}
+
+fn take_calls(temp: &TempDir) -> Vec<String> {
+    let path = temp.path().join("calls.log");
+    let calls = fs::read_to_string(&path).unwrap_or_default();
+    fs::remove_file(&path).ok();
+    calls.lines().map(String::from).collect()
+}
+
+#[test]
+fn test_deploy_resume_after_failure() {
+    let temp = TempDir::new().unwrap();
+    setup_resume_test(&temp);
+    fs::write(temp.path().join("fail-b"), "").unwrap();
+    let path = format!("{}:/usr/bin:/bin", temp.path().display());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", &path)
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("vesshelm deploy --resume"));
+    assert_eq!(
+        take_calls(&temp),
+        vec![
+            "helm upgrade --install chart-a ./charts -n default",
+            "helm upgrade --install chart-b ./charts -n default",
+        ]
+    );
+    let state_file = temp.path().join(".vesshelm/deploy-run.yaml");
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(state.contains("failed: chart-b"));
+
+    fs::remove_file(temp.path().join("fail-b")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", &path)
+        .args(["deploy", "--resume", "--no-interactive", "--no-progress"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Resuming from chart-b"));
+    assert_eq!(
+        take_calls(&temp),
+        vec![
+            "helm upgrade --install chart-b ./charts -n default",
+            "helm upgrade --install chart-c ./charts -n default",
+        ]
+    );
+    assert!(!state_file.exists());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", &path)
+        .args(["deploy", "--resume", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No unfinished deploy run to resume",
+        ));
+}
+
+#[test]
+fn test_deploy_from_until() {
+    let temp = TempDir::new().unwrap();
+    setup_resume_test(&temp);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args([
+            "deploy",
+            "--from",
+            "chart-b",
+            "--until",
+            "chart-b",
+            "--no-interactive",
+            "--no-progress",
+        ])
+        .assert()
+        .success();
+    assert_eq!(
+        take_calls(&temp),
+        vec!["helm upgrade --install chart-b ./charts -n default"]
+    );
+}
+
+fn setup_labels_test(temp: &TempDir) {
+    let mock_helm_path = temp.path().join("helm");
+    let mock_helm_content = r#"#!/bin/sh
+echo "helm $@" >> "$(dirname "$0")/calls.log"
+exit 0
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: monitoring-prometheus
+    repo_name: stable
+    version: 1.0.0
+    namespace: monitoring
+    labels:
+      tier: infra
+  - name: monitoring-grafana
+    repo_name: stable
+    version: 1.0.0
+    namespace: monitoring
+    labels:
+      tier: infra
+      ui: "true"
+  - name: api
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    labels:
+      tier: app
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }} -n {{ namespace }}"
+    diff_enabled: false
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+}
+
+fn deployed_charts(temp: &TempDir, args: &[&str]) -> Vec<String> {
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .args(args)
+        .assert()
+        .success();
+
+    // Independent charts have no guaranteed relative order
+    let mut charts: Vec<String> = take_calls(temp)
+        .iter()
+        .filter_map(|call| call.strip_prefix("helm upgrade --install "))
+        .map(|rest| rest.split_whitespace().next().unwrap().to_string())
+        .collect();
+    charts.sort();
+    charts
+}
+
+#[test]
+fn test_deploy_label_selector_and_globs() {
+    let temp = TempDir::new().unwrap();
+    setup_labels_test(&temp);
+
+    assert_eq!(
+        deployed_charts(&temp, &["-l", "tier=infra"]),
+        vec!["monitoring-grafana", "monitoring-prometheus"]
+    );
+    assert_eq!(
+        deployed_charts(&temp, &["--selector", "tier=infra,!ui"]),
+        vec!["monitoring-prometheus"]
+    );
+    assert_eq!(
+        deployed_charts(&temp, &["monitoring-*", "--selector", "ui=true"]),
+        vec!["monitoring-grafana"]
+    );
+    assert_eq!(
+        deployed_charts(&temp, &["api", "monitoring-g*"]),
+        vec!["api", "monitoring-grafana"]
+    );
+}
+
+#[test]
+fn test_deploy_selection_matching_nothing_fails() {
+    let temp = TempDir::new().unwrap();
+    setup_labels_test(&temp);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"));
+    cmd.current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress", "db-*"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No chart matches the pattern 'db-*'",
+        ));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"));
+    cmd.current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "-l", "=infra"])
+        .assert()
+        .failure();
+    assert!(take_calls(&temp).is_empty());
+}
+
+fn setup_dependency_chain_test(temp: &TempDir) {
+    let mock_helm_path = temp.path().join("helm");
+    let mock_helm_content = r#"#!/bin/sh
+echo "helm $@" >> "$(dirname "$0")/calls.log"
+exit 0
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: web
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    depends: [api]
+  - name: api
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    depends: [postgres]
+  - name: postgres
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: docs
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }} -n {{ namespace }}"
+    diff_enabled: false
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+}
+
+fn deploy_order(temp: &TempDir, args: &[&str]) -> (Vec<String>, String) {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .args(args)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let order = take_calls(temp)
+        .iter()
+        .filter_map(|call| call.strip_prefix("helm upgrade --install "))
+        .map(|rest| rest.split_whitespace().next().unwrap().to_string())
+        .collect();
+    (order, String::from_utf8_lossy(&output).to_string())
+}
+
+#[test]
+fn test_deploy_with_dependencies_and_dependents() {
+    let temp = TempDir::new().unwrap();
+    setup_dependency_chain_test(&temp);
+
+    let (order, stdout) = deploy_order(&temp, &["web", "--with-dependencies"]);
+    assert_eq!(order, vec!["postgres", "api", "web"]);
+    assert!(!stdout.contains("which is not selected"));
+
+    let (order, _) = deploy_order(&temp, &["postgres", "--with-dependents"]);
+    assert_eq!(order, vec!["postgres", "api", "web"]);
+
+    let (order, stdout) = deploy_order(&temp, &["api"]);
+    assert_eq!(order, vec!["api"]);
+    assert!(stdout.contains("'api' depends on 'postgres', which is not selected"));
+}
+
+#[test]
+fn test_deploy_release_names() {
+    let temp = TempDir::new().unwrap();
+    let mock_helm_path = temp.path().join("helm");
+    fs::write(
+        &mock_helm_path,
+        "#!/bin/sh\necho \"helm $@\" >> \"$(dirname \"$0\")/calls.log\"\nexit 0\n",
+    )
+    .unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://kubernetes.github.io/ingress-nginx
+
+charts:
+  - name: ingress-nginx
+    release: internal
+    repo_name: stable
+    version: 4.10.0
+    namespace: ingress
+  - name: ingress-nginx
+    release: external
+    repo_name: stable
+    version: 4.10.0
+    namespace: ingress
+    depends: [internal]
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+    diff_enabled: false
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        take_calls(&temp),
+        vec![
+            "helm upgrade --install internal ./charts/ingress-nginx -n ingress",
+            "helm upgrade --install external ./charts/ingress-nginx -n ingress",
+        ]
+    );
+
+    // Charts are selected by release name
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()))
+        .args(["deploy", "--no-interactive", "--no-progress", "external"])
+        .assert()
+        .success();
+    assert_eq!(
+        take_calls(&temp),
+        vec!["helm upgrade --install external ./charts/ingress-nginx -n ingress"]
+    );
+}
@@ -0,0 +1,200 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+/// Mock helm reporting changes only for `chart-b`.
+fn setup_mock_helm_diff(temp_dir: &TempDir) {
+    let mock_helm_path = temp_dir.path().join("helm");
+    let mock_helm_content = r#"#!/bin/sh
+if [ "$1" = "diff" ]; then
+    case "$*" in
+        *chart-b*)
+            echo "default, chart-b, Deployment (apps) has changed:"
+            echo "-   replicas: 1"
+            echo "+   replicas: 2"
+            echo "default, chart-b, Secret (v1) has been added:"
+            echo "+   password: hunter2"
+            ;;
+    esac
+    exit 0
+fi
+echo "Unexpected helm call: $@"
+exit 1
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+}
+
+fn write_config(temp_dir: &TempDir) {
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: chart-b
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }} -n {{ namespace }}"
+"#;
+    fs::write(temp_dir.path().join("vesshelm.yaml"), config_content).unwrap();
+}
+
+#[test]
+fn test_diff_no_changes_exits_zero() {
+    let temp = TempDir::new().unwrap();
+    setup_mock_helm_diff(&temp);
+    write_config(&temp);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["diff", "chart-a", "--no-progress"])
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains("chart-a has no changes"));
+}
+
+#[test]
+fn test_diff_changes_exit_two() {
+    let temp = TempDir::new().unwrap();
+    setup_mock_helm_diff(&temp);
+    write_config(&temp);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["diff", "--no-progress"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("chart-b has changes"))
+        .stdout(predicate::str::contains("Deployment (apps) has changed"))
+        .stderr(predicate::str::contains("chart-b"));
+}
+
+#[test]
+fn test_diff_helm_failure_exits_one() {
+    let temp = TempDir::new().unwrap();
+    write_config(&temp);
+    let mock_helm_path = temp.path().join("helm");
+    fs::write(&mock_helm_path, "#!/bin/sh\necho boom >&2\nexit 1\n").unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["diff", "--no-progress"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Failed to diff chart"));
+}
+
+#[test]
+fn test_diff_summary() {
+    let temp = TempDir::new().unwrap();
+    setup_mock_helm_diff(&temp);
+    write_config(&temp);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["diff", "chart-b", "--no-progress", "--summary"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("Deployment (apps)"))
+        .stdout(predicate::str::contains(
+            "2 resource(s): 1 added, 1 modified, 0 removed",
+        ))
+        .stdout(predicate::str::contains("replicas: 2").not())
+        .stdout(predicate::str::contains("hunter2").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args([
+            "diff",
+            "chart-b",
+            "--no-progress",
+            "--summary",
+            "--full-diff",
+        ])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("replicas: 2"))
+        .stdout(predicate::str::contains("content hidden"))
+        .stdout(predicate::str::contains("hunter2").not());
+}
+
+#[test]
+fn test_diff_native_engine() {
+    let temp = TempDir::new().unwrap();
+    let mock_helm_path = temp.path().join("helm");
+    let mock_helm_content = r#"#!/bin/sh
+if [ "$1" = "diff" ]; then
+    echo "helm-diff plugin must not be used"
+    exit 1
+fi
+if [ "$1" = "template" ]; then
+    echo "---"
+    echo "apiVersion: apps/v1"
+    echo "kind: Deployment"
+    echo "metadata:"
+    echo "  name: $2"
+    echo "spec:"
+    echo "  replicas: 2"
+    exit 0
+fi
+if [ "$1" = "get" ] && [ "$2" = "manifest" ]; then
+    if [ "$3" = "chart-b" ]; then
+        echo "Error: release: not found" >&2
+        exit 1
+    fi
+    echo "apiVersion: apps/v1"
+    echo "kind: Deployment"
+    echo "metadata:"
+    echo "  name: $3"
+    echo "spec:"
+    echo "  replicas: 2"
+    exit 0
+fi
+exit 1
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    write_config(&temp);
+    let config_path = temp.path().join("vesshelm.yaml");
+    let config = fs::read_to_string(&config_path).unwrap();
+    fs::write(&config_path, format!("{}    diff_engine: native\n", config)).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp.path())
+        .env("PATH", temp.path())
+        .args(["diff", "--no-progress"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("chart-a has no changes"))
+        .stdout(predicate::str::contains(
+            "default, chart-b, Deployment (apps) has been added:",
+        ))
+        .stdout(predicate::str::contains("+   replicas: 2"));
+}
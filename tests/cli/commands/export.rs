@@ -0,0 +1,176 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let config_content = r#"
+repositories:
+  - name: bitnami
+    url: https://charts.bitnami.com/bitnami
+  - name: ghcr
+    url: oci://ghcr.io/acme/charts
+    type: oci
+  - name: platform
+    url: https://github.com/acme/platform.git
+    type: git
+
+charts:
+  - name: postgresql
+    repo_name: bitnami
+    version: 15.0.0
+    namespace: data
+    values_files:
+      - values/postgresql.yaml
+      - values/postgresql-secrets.yaml
+  - name: api
+    repo_name: ghcr
+    version: 2.1.0
+    namespace: app
+    depends: [postgresql]
+    values:
+      - replicas: 2
+  - name: web
+    repo_name: platform
+    chart_path: charts/web
+    version: v1.4.0
+    namespace: app
+    depends: [api]
+  - name: local-tools
+    chart_path: ./local/tools
+    namespace: ops
+
+destinations:
+  - name: default
+    path: ./charts
+
+variables_files:
+  - vars.yaml
+secrets_files:
+  - secrets.yaml
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+    fs::write(temp.path().join("vars.yaml"), "storage_class: fast\n").unwrap();
+    fs::write(
+        temp.path().join("secrets.yaml"),
+        "db_password: ENC[AES256_GCM,data:abc]\nsops:\n  version: 3.8.1\n  mac: ENC[AES256_GCM,data:def]\n",
+    )
+    .unwrap();
+    fs::create_dir_all(temp.path().join("values")).unwrap();
+    fs::write(
+        temp.path().join("values/postgresql.yaml"),
+        "primary:\n  persistence:\n    storageClass: \"{{ storage_class }}\"\n",
+    )
+    .unwrap();
+    fs::write(
+        temp.path().join("values/postgresql-secrets.yaml"),
+        "auth:\n  password: \"{{ db_password }}\"\n",
+    )
+    .unwrap();
+    temp
+}
+
+#[test]
+fn test_export_argocd() {
+    let temp = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["export", "--format", "argocd", "--output-dir", "gitops"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("local-tools (local chart)"))
+        .stdout(predicate::str::contains(
+            "provide them to Argo CD separately:\n  - postgresql: values/postgresql-secrets.yaml",
+        ))
+        .stdout(predicate::str::contains("lower precedence").not())
+        .stdout(predicate::str::contains("Exported 3 chart(s)"));
+
+    let out = temp.path().join("gitops");
+    let postgresql = fs::read_to_string(out.join("postgresql.yaml")).unwrap();
+    assert!(postgresql.contains("kind: Application"));
+    assert!(postgresql.contains("namespace: argocd"));
+    assert!(postgresql.contains("argocd.argoproj.io/sync-wave: '0'"));
+    assert!(postgresql.contains("repoURL: https://charts.bitnami.com/bitnami"));
+    assert!(postgresql.contains("targetRevision: 15.0.0"));
+    assert!(postgresql.contains("storageClass: fast"));
+    assert!(!postgresql.contains("postgresql-secrets"));
+    assert!(!postgresql.contains("valueFiles"));
+    assert!(!postgresql.contains("ENC["));
+
+    let api = fs::read_to_string(out.join("api.yaml")).unwrap();
+    assert!(api.contains("argocd.argoproj.io/sync-wave: '1'"));
+    assert!(api.contains("repoURL: ghcr.io/acme/charts"));
+    assert!(api.contains("replicas: 2"));
+
+    let web = fs::read_to_string(out.join("web.yaml")).unwrap();
+    assert!(web.contains("argocd.argoproj.io/sync-wave: '2'"));
+    assert!(web.contains("path: charts/web"));
+    assert!(web.contains("targetRevision: v1.4.0"));
+
+    assert!(!out.join("local-tools.yaml").exists());
+    assert!(!out.join("repositories.yaml").exists());
+}
+
+#[test]
+fn test_export_flux() {
+    let temp = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["export", "--format", "flux", "--output-dir", "gitops"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "(Secret 'postgresql-postgresql-secrets', key 'values.yaml')",
+        ))
+        .stdout(predicate::str::contains(
+            "now have a lower precedence (valuesFrom is applied before values): postgresql",
+        ))
+        .stdout(predicate::str::contains("are not ordered").not());
+
+    let out = temp.path().join("gitops");
+    let repositories = fs::read_to_string(out.join("repositories.yaml")).unwrap();
+    assert!(repositories.contains("name: bitnami"));
+    assert!(repositories.contains("url: oci://ghcr.io/acme/charts"));
+    assert!(repositories.contains("type: oci"));
+    assert!(!repositories.contains("platform"));
+
+    let postgresql = fs::read_to_string(out.join("postgresql.yaml")).unwrap();
+    assert!(postgresql.contains("kind: HelmRelease"));
+    assert!(postgresql.contains("namespace: flux-system"));
+    assert!(postgresql.contains("targetNamespace: data"));
+    assert!(postgresql.contains("name: postgresql-postgresql-secrets"));
+    assert!(postgresql.contains("storageClass: fast"));
+
+    let api = fs::read_to_string(out.join("api.yaml")).unwrap();
+    assert!(api.contains("dependsOn:\n  - name: postgresql"));
+
+    let web = fs::read_to_string(out.join("web.yaml")).unwrap();
+    assert!(web.contains("kind: GitRepository"));
+    assert!(web.contains("tag: v1.4.0"));
+    assert!(web.contains("chart: charts/web"));
+    assert!(web.contains("kind: GitRepository\n        name: web"));
+}
+
+#[test]
+fn test_export_warns_about_dependencies_not_exported() {
+    let temp = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["export", "web", "--format", "flux", "--output-dir", "gitops"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Dependencies on charts that were not exported are not ordered:\n  - web depends on api (not selected)",
+        ))
+        .stdout(predicate::str::contains("Exported 1 chart(s)"));
+
+    let web = fs::read_to_string(temp.path().join("gitops/web.yaml")).unwrap();
+    assert!(!web.contains("dependsOn"));
+}
@@ -0,0 +1,110 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+fn setup(temp: &TempDir) {
+    let mock_helm_path = temp.path().join("helm");
+    // Upgrading chart-b always fails
+    let mock_helm_content = r#"#!/bin/sh
+if [ "$1" = "upgrade" ] && [ "$3" = "chart-b" ]; then
+    exit 1
+fi
+exit 0
+"#;
+    fs::write(&mock_helm_path, mock_helm_content).unwrap();
+    let mut perms = fs::metadata(&mock_helm_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&mock_helm_path, perms).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: chart-a
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+  - name: chart-b
+    repo_name: stable
+    version: 2.0.0
+    namespace: backend
+    depends: [chart-a]
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }} -n {{ namespace }}"
+    diff_enabled: false
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+}
+
+fn vesshelm(temp: &TempDir) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"));
+    cmd.current_dir(temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", temp.path().display()));
+    cmd
+}
+
+#[test]
+fn test_history_records_deployments() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+
+    vesshelm(&temp)
+        .args(["deploy", "--no-interactive", "--no-progress"])
+        .assert()
+        .failure();
+
+    let log = fs::read_to_string(temp.path().join(".vesshelm/audit.jsonl")).unwrap();
+    assert_eq!(log.lines().count(), 2);
+
+    let output = vesshelm(&temp)
+        .args(["history", "--output", "json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let records: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(records[0]["chart"], "chart-a");
+    assert_eq!(records[0]["status"], "deployed");
+    assert_eq!(records[0]["version"], "1.0.0");
+    assert_eq!(records[0]["values_hash"].as_str().unwrap().len(), 64);
+    assert_eq!(records[1]["chart"], "chart-b");
+    assert_eq!(records[1]["namespace"], "backend");
+    assert_eq!(records[1]["status"], "failed");
+    assert!(records[1]["error"].is_string());
+
+    vesshelm(&temp)
+        .args(["history", "chart-b"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("chart-b"))
+        .stdout(predicate::str::contains("failed"))
+        .stdout(predicate::str::contains("chart-a").not());
+}
+
+#[test]
+fn test_history_skips_dry_runs() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+
+    vesshelm(&temp)
+        .args(["deploy", "--dry-run", "--no-interactive", "--no-progress"])
+        .assert()
+        .success();
+
+    assert!(!temp.path().join(".vesshelm/audit.jsonl").exists());
+    vesshelm(&temp)
+        .arg("history")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No deployment recorded yet."));
+}
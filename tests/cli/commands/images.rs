@@ -0,0 +1,164 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+/// Mock helm printing the values files as the rendered manifest of `helm template`.
+fn setup(temp: &TempDir) {
+    let helm_script = r#"#!/bin/sh
+[ "$1" = "template" ] || exit 1
+prev=""
+for arg in "$@"; do
+    if [ "$prev" = "-f" ]; then
+        while IFS= read -r line || [ -n "$line" ]; do echo "$line"; done < "$arg"
+    fi
+    prev="$arg"
+done
+"#;
+    let helm_path = temp.path().join("helm");
+    fs::write(&helm_path, helm_script).unwrap();
+    fs::set_permissions(&helm_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: web
+    repo_name: stable
+    version: 1.0.0
+    namespace: front
+    values_files:
+      - values/web.yaml
+  - name: worker
+    repo_name: stable
+    version: 1.0.0
+    namespace: jobs
+    values_files:
+      - values/worker.yaml
+  - name: tools
+    repo_name: stable
+    version: 1.0.0
+    namespace: tools
+    no_deploy: true
+
+destinations:
+  - name: default
+    path: ./charts
+
+variables_files:
+  - vars.yaml
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+    fs::write(temp.path().join("vars.yaml"), "nginx_tag: \"1.25\"\n").unwrap();
+    fs::create_dir_all(temp.path().join("values")).unwrap();
+    fs::write(
+        temp.path().join("values/web.yaml"),
+        r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  template:
+    spec:
+      initContainers:
+        - name: init
+          image: busybox:1.36
+      containers:
+        - name: web
+          image: nginx:{{ nginx_tag }}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        temp.path().join("values/worker.yaml"),
+        r#"apiVersion: batch/v1
+kind: CronJob
+metadata:
+  name: worker
+spec:
+  jobTemplate:
+    spec:
+      template:
+        spec:
+          containers:
+            - name: worker
+              image: ghcr.io/acme/worker:2.0
+            - name: proxy
+              image: nginx:1.25
+"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_images_text() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .env("PATH", temp.path())
+        .arg("images")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "web:\n  busybox:1.36\n  nginx:1.25\n",
+        ))
+        .stdout(predicate::str::contains(
+            "worker:\n  ghcr.io/acme/worker:2.0\n  nginx:1.25\n",
+        ))
+        .stdout(predicate::str::contains("tools").not());
+}
+
+#[test]
+fn test_images_json_selected_chart() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .env("PATH", temp.path())
+        .args(["images", "worker", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let inventory: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        inventory,
+        serde_json::json!([{
+            "chart": "worker",
+            "namespace": "jobs",
+            "images": ["ghcr.io/acme/worker:2.0", "nginx:1.25"]
+        }])
+    );
+}
+
+#[test]
+fn test_images_mirror_script() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .env("PATH", temp.path())
+        .args(["images", "--format", "mirror"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("#!/bin/sh\n"))
+        .stdout(predicate::str::contains(
+            "docker://docker.io/library/nginx:1.25 docker://$REGISTRY/library/nginx:1.25",
+        ))
+        .stdout(predicate::str::contains(
+            "docker://ghcr.io/acme/worker:2.0 docker://$REGISTRY/acme/worker:2.0",
+        ))
+        .stdout(predicate::function(|out: &str| {
+            out.matches("library/nginx:1.25 ").count() == 1
+        }));
+}
@@ -0,0 +1,95 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+const HELMFILE: &str = r#"
+repositories:
+  - name: bitnami
+    url: https://charts.bitnami.com/bitnami
+
+helmDefaults:
+  wait: true
+  timeout: 300
+
+environments:
+  production: {}
+
+releases:
+  - name: db
+    namespace: data
+    chart: bitnami/postgresql
+    version: 15.0.0
+    values:
+      - values/db.yaml
+    set:
+      - name: auth.database
+        value: app
+  - name: api
+    namespace: app
+    chart: ./charts/api
+    needs:
+      - data/db
+"#;
+
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("helmfile.yaml"), HELMFILE).unwrap();
+    fs::create_dir_all(temp.path().join("values")).unwrap();
+    fs::write(temp.path().join("values/db.yaml"), "primary: {}\n").unwrap();
+    fs::create_dir_all(temp.path().join("charts/api")).unwrap();
+    temp
+}
+
+#[test]
+fn test_import_helmfile() {
+    let temp = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["import", "helmfile", "helmfile.yaml"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1 repositories and 2 charts written to vesshelm.yaml",
+        ))
+        .stdout(predicate::str::contains(
+            "top-level 'environments' is not supported",
+        ));
+
+    let config = fs::read_to_string(temp.path().join("vesshelm.yaml")).unwrap();
+    assert!(config.contains("- name: postgresql\n  release: db\n  repo_name: bitnami\n"));
+    assert!(config.contains("chart_path: ./charts/api"));
+    assert!(config.contains("depends:\n  - db"));
+    assert!(config.contains("--wait --timeout 300s --create-namespace"));
+    assert!(!config.contains("null"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .arg("validate")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_import_helmfile_keeps_existing_config() {
+    let temp = setup();
+    fs::write(temp.path().join("vesshelm.yaml"), "charts: []\n").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["import", "helmfile", "helmfile.yaml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --force to overwrite it"));
+    assert_eq!(
+        fs::read_to_string(temp.path().join("vesshelm.yaml")).unwrap(),
+        "charts: []\n"
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["import", "helmfile", "helmfile.yaml", "--force"])
+        .assert()
+        .success();
+}
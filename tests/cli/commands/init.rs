@@ -46,3 +46,43 @@ fn test_init_existing_config() {
     let content = fs::read_to_string(config_path).unwrap();
     assert_eq!(content, "existing content");
 }
+
+#[test]
+fn test_init_native_diff_skips_plugin() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let bin_dir = temp_dir.path().join("bin");
+    fs::create_dir(&bin_dir).unwrap();
+    let helm_path = bin_dir.join("helm");
+    // Any plugin operation fails
+    fs::write(&helm_path, "#!/bin/sh\nexit 1\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&helm_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&helm_path, perms).unwrap();
+    }
+
+    fs::write(
+        temp_dir.path().join("vesshelm.yaml"),
+        r#"
+repositories: []
+charts: []
+destinations:
+  - name: default
+    path: ./charts
+vesshelm:
+  helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+  diff_engine: native
+"#,
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .env("PATH", &bin_dir)
+        .arg("init")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("skipping helm-diff plugin"));
+}
@@ -1,9 +1,18 @@
+pub mod bundle;
 pub mod check_updates;
 pub mod delete;
 pub mod deploy;
+pub mod diff;
+pub mod export;
 pub mod graph;
+pub mod history;
+pub mod images;
+pub mod import;
 pub mod init;
+pub mod status;
 pub mod sync;
+pub mod template;
 pub mod uninstall;
 pub mod validate;
+pub mod values;
 pub mod version;
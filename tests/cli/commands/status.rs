@@ -0,0 +1,115 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+fn setup(helm_script: &str) -> (tempfile::TempDir, String) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let bin_dir = temp_dir.path().join("bin");
+    fs::create_dir(&bin_dir).unwrap();
+
+    let helm_path = bin_dir.join("helm");
+    fs::write(&helm_path, helm_script).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&helm_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&helm_path, perms).unwrap();
+    }
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://example.com/stable
+charts:
+  - name: nginx
+    repo_name: stable
+    version: 1.2.0
+    namespace: web
+  - name: redis
+    repo_name: stable
+    version: 2.0.0
+    namespace: data
+destinations:
+  - name: default
+    path: ./charts
+"#;
+    fs::write(temp_dir.path().join("vesshelm.yaml"), config_content).unwrap();
+
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    let new_path = format!("{}:{}", bin_dir.display(), path_env);
+    (temp_dir, new_path)
+}
+
+#[test]
+fn test_status_all_in_sync() {
+    let (temp_dir, path) = setup(
+        r#"#!/bin/sh
+# Every release must be listed, not only helm's first page
+if [ "$1" = "list" ] && echo "$*" | grep -q -- "--max 0"; then
+    echo "- name: nginx"
+    echo "  namespace: web"
+    echo "  revision: '4'"
+    echo "  status: deployed"
+    echo "  chart: nginx-1.2.0"
+    echo "  app_version: 1.25.0"
+    echo "- name: redis"
+    echo "  namespace: data"
+    echo "  revision: '1'"
+    echo "  status: deployed"
+    echo "  chart: redis-2.0.0"
+    echo "  app_version: 7.2.0"
+    exit 0
+fi
+exit 1
+"#,
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .env("PATH", path)
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("in sync"))
+        .stdout(predicate::str::contains(
+            "All charts are deployed as desired",
+        ));
+}
+
+#[test]
+fn test_status_reports_drift_as_json() {
+    let (temp_dir, path) = setup(
+        r#"#!/bin/sh
+if [ "$1" = "list" ]; then
+    echo "- name: nginx"
+    echo "  namespace: web"
+    echo "  revision: '5'"
+    echo "  status: pending-upgrade"
+    echo "  chart: nginx-1.1.0"
+    echo "  app_version: 1.25.0"
+    exit 0
+fi
+exit 1
+"#,
+    );
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .env("PATH", path)
+        .args(["status", "--output", "json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("2 chart(s)"))
+        .get_output()
+        .stdout
+        .clone();
+
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report[0]["name"], "nginx");
+    assert_eq!(report[0]["state"], "pending");
+    assert_eq!(report[0]["deployed_version"], "1.1.0");
+    assert_eq!(report[1]["name"], "redis");
+    assert_eq!(report[1]["state"], "not-deployed");
+}
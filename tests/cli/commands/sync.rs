@@ -734,3 +734,319 @@ destinations:
         .success()
         .stdout(predicates::str::contains("Synced:  1"));
 }
+
+#[test]
+fn test_sync_uses_project_local_repositories() {
+    let temp_dir = TempDir::new().unwrap();
+    let bin_dir = temp_dir.path().join("bin");
+    fs::create_dir(&bin_dir).unwrap();
+
+    // Mock helm recording the repository environment and the repo commands it receives
+    let helm_path = bin_dir.join("helm");
+    let helm_script = r#"#!/bin/sh
+if [ "$1" = "repo" ]; then
+    echo "$* config=$HELM_REPOSITORY_CONFIG cache=$HELM_REPOSITORY_CACHE" >> helm-calls.log
+    exit 0
+fi
+if [ "$1" = "pull" ]; then
+    for last_arg in "$@"; do :; done
+    mkdir -p "$last_arg/nginx"
+    touch "$last_arg/nginx/Chart.yaml"
+    exit 0
+fi
+exit 1
+"#;
+    fs::write(&helm_path, helm_script).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&helm_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&helm_path, perms).unwrap();
+    }
+
+    let config_path = temp_dir.path().join("vesshelm.yaml");
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+  - name: unused
+    url: https://unused.example.com
+
+charts:
+  - name: nginx
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+
+destinations:
+  - name: default
+    path: ./charts
+"#;
+    fs::write(&config_path, config_content).unwrap();
+
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    let new_path = format!("{}:{}", bin_dir.display(), path_env);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"));
+    cmd.current_dir(temp_dir.path())
+        .env("PATH", &new_path)
+        .arg("--no-progress")
+        .arg("sync")
+        .assert()
+        .success();
+
+    let calls = fs::read_to_string(temp_dir.path().join("helm-calls.log")).unwrap();
+    assert!(calls.contains("repo add stable https://charts.helm.sh/stable"));
+    assert!(calls.contains("repo update stable config="));
+    assert!(calls.contains(".vesshelm/repositories.yaml"));
+    assert!(calls.contains(".vesshelm/repository-cache"));
+    assert!(!calls.contains("unused"));
+}
+
+#[test]
+fn test_sync_repository_url_mismatch_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let state_dir = temp_dir.path().join(".vesshelm");
+    fs::create_dir(&state_dir).unwrap();
+    fs::write(
+        state_dir.join("repositories.yaml"),
+        "repositories:\n- name: stable\n  url: https://mirror.example.com/stable\n",
+    )
+    .unwrap();
+
+    let config_path = temp_dir.path().join("vesshelm.yaml");
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: nginx
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+
+destinations:
+  - name: default
+    path: ./charts
+"#;
+    fs::write(&config_path, config_content).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"));
+    cmd.current_dir(temp_dir.path())
+        .arg("--no-progress")
+        .arg("sync")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "nginx: Failed to register repository 'stable'",
+        ))
+        .stderr(predicate::str::contains("Some charts failed to sync"));
+}
+
+#[test]
+fn test_sync_chart_instances_with_different_versions() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = r#"
+charts:
+  - name: redis
+    release: cache
+    repo_name: stable
+    version: 17.0.0
+    namespace: data
+  - name: redis
+    release: queue
+    repo_name: stable
+    version: 18.0.0
+    namespace: data
+  - name: nginx
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+    diff_enabled: false
+"#;
+    fs::write(temp_dir.path().join("vesshelm.yaml"), config).unwrap();
+
+    // Lockfile written before chart instances were tracked
+    fs::write(
+        temp_dir.path().join("vesshelm.lock"),
+        "charts:\n- name: nginx\n  repo_name: stable\n  version: 1.0.0\n",
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join("charts/nginx")).unwrap();
+
+    let bin_dir = temp_dir.path().join("bin");
+    fs::create_dir(&bin_dir).unwrap();
+    let helm_path = bin_dir.join("helm");
+    let helm_script = r#"#!/bin/sh
+echo "helm $@" >> "$(dirname "$0")/calls.log"
+if [ "$1" = "pull" ]; then
+    # helm pull repo/chart --version v --untar --untardir dir
+    chart="${2#*/}"
+    mkdir -p "$7/$chart"
+    echo "version: $4" > "$7/$chart/Chart.yaml"
+fi
+exit 0
+"#;
+    fs::write(&helm_path, helm_script).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&helm_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&helm_path, perms).unwrap();
+    }
+
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    let new_path = format!("{}:{}", bin_dir.display(), path_env);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp_dir.path())
+        .env("PATH", &new_path)
+        .args(["--no-progress", "sync"])
+        .assert()
+        .success();
+
+    // Each version has its own directory, nginx is still up to date after the migration
+    let chart_yaml = |dir: &str| {
+        fs::read_to_string(temp_dir.path().join("charts").join(dir).join("Chart.yaml"))
+            .unwrap_or_default()
+    };
+    assert_eq!(chart_yaml("redis-17.0.0"), "version: 17.0.0\n");
+    assert_eq!(chart_yaml("redis-18.0.0"), "version: 18.0.0\n");
+    let calls = fs::read_to_string(bin_dir.join("calls.log")).unwrap();
+    assert!(!calls.contains("pull stable/nginx"));
+
+    let lockfile: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+        &fs::read_to_string(temp_dir.path().join("vesshelm.lock")).unwrap(),
+    )
+    .unwrap();
+    let entries = lockfile["charts"].as_sequence().unwrap();
+    assert_eq!(entries.len(), 3);
+    let version_of = |release: &str| {
+        entries
+            .iter()
+            .find(|e| e["release"].as_str() == Some(release))
+            .map(|e| e["version"].as_str().unwrap().to_string())
+    };
+    assert_eq!(version_of("cache").as_deref(), Some("17.0.0"));
+    assert_eq!(version_of("queue").as_deref(), Some("18.0.0"));
+    assert_eq!(version_of("nginx").as_deref(), Some("1.0.0"));
+
+    // A second sync has nothing to do
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp_dir.path())
+        .env("PATH", &new_path)
+        .args(["--no-progress", "sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Synced:  0"))
+        .stdout(predicate::str::contains("Skipped: 3"));
+
+    // Deploy uses the directory of each version
+    fs::remove_file(bin_dir.join("calls.log")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp_dir.path())
+        .env("PATH", &new_path)
+        .args([
+            "deploy",
+            "--no-interactive",
+            "--no-progress",
+            "cache",
+            "queue",
+        ])
+        .assert()
+        .success();
+    let calls = fs::read_to_string(bin_dir.join("calls.log")).unwrap();
+    assert!(calls.contains("upgrade --install cache ./charts/redis-17.0.0 -n data"));
+    assert!(calls.contains("upgrade --install queue ./charts/redis-18.0.0 -n data"));
+}
+
+#[test]
+fn test_sync_resyncs_chart_locked_in_another_directory() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let config = r#"
+charts:
+  - name: redis
+    release: queue
+    repo_name: stable
+    version: 18.0.0
+    namespace: data
+
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+destinations:
+  - name: default
+    path: ./charts
+"#;
+    fs::write(temp_dir.path().join("vesshelm.yaml"), config).unwrap();
+
+    // Synced while another instance used 17.0.0: the default directory holds that version
+    fs::write(
+        temp_dir.path().join("vesshelm.lock"),
+        "charts:\n- name: redis\n  repo_name: stable\n  release: queue\n  namespace: data\n  version: 18.0.0\n  dir: ./charts/redis-18.0.0\n",
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join("charts/redis")).unwrap();
+    fs::write(
+        temp_dir.path().join("charts/redis/Chart.yaml"),
+        "version: 17.0.0\n",
+    )
+    .unwrap();
+
+    let bin_dir = temp_dir.path().join("bin");
+    fs::create_dir(&bin_dir).unwrap();
+    let helm_path = bin_dir.join("helm");
+    let helm_script = r#"#!/bin/sh
+if [ "$1" = "pull" ]; then
+    # helm pull repo/chart --version v --untar --untardir dir
+    chart="${2#*/}"
+    mkdir -p "$7/$chart"
+    echo "version: $4" > "$7/$chart/Chart.yaml"
+fi
+exit 0
+"#;
+    fs::write(&helm_path, helm_script).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&helm_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&helm_path, perms).unwrap();
+    }
+
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    let new_path = format!("{}:{}", bin_dir.display(), path_env);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(temp_dir.path())
+        .env("PATH", &new_path)
+        .args(["--no-progress", "sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Synced:  1"));
+
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("charts/redis/Chart.yaml")).unwrap(),
+        "version: 18.0.0\n"
+    );
+    let lockfile = fs::read_to_string(temp_dir.path().join("vesshelm.lock")).unwrap();
+    assert!(lockfile.contains("dir: ./charts/redis\n"));
+}
@@ -0,0 +1,206 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+/// Mock helm writing its arguments and the content of its values files to the output directory.
+fn setup(temp: &TempDir) -> String {
+    let helm_script = r#"#!/bin/sh
+if [ "$1" != "template" ]; then
+    echo "Unexpected helm call: $@" >&2
+    exit 1
+fi
+case "$*" in
+    *broken*) echo "Error: template: broken" >&2; exit 1 ;;
+esac
+out=""
+values=""
+prev=""
+for arg in "$@"; do
+    [ "$prev" = "--output-dir" ] && out="$arg"
+    [ "$prev" = "-f" ] && values="$values $arg"
+    prev="$arg"
+done
+mkdir -p "$out/chart/templates"
+echo "helm $@" > "$out/args.txt"
+for f in $values; do cat "$f"; done > "$out/chart/templates/values.yaml"
+"#;
+    let helm_path = temp.path().join("helm");
+    fs::write(&helm_path, helm_script).unwrap();
+    fs::set_permissions(&helm_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: web
+    repo_name: stable
+    version: 1.0.0
+    namespace: front
+    helm_args_append: "--history-max 5 --wait"
+    values_files:
+      - values/web.yaml
+  - name: web
+    release: web-canary
+    repo_name: stable
+    version: 1.0.0
+    namespace: front
+    values:
+      - replicas: 1
+  - name: tools
+    repo_name: stable
+    version: 1.0.0
+    namespace: tools
+    no_deploy: true
+
+destinations:
+  - name: default
+    path: ./charts
+
+secrets_files:
+  - secrets.yaml
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+    fs::write(temp.path().join("secrets.yaml"), "api_key: s3cr3t\n").unwrap();
+    fs::create_dir_all(temp.path().join("values")).unwrap();
+    fs::write(
+        temp.path().join("values/web.yaml"),
+        "apiKey: \"{{ api_key }}\"\n",
+    )
+    .unwrap();
+
+    format!("{}:/usr/bin:/bin", temp.path().display())
+}
+
+#[test]
+fn test_template_renders_one_directory_per_release() {
+    let temp = TempDir::new().unwrap();
+    let path = setup(&temp);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .env("PATH", path)
+        .args(["--no-progress", "template", "--output-dir", "out"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rendered: 2"))
+        .stdout(predicate::str::contains("Skipped:  1"));
+
+    let out = temp.path().join("out");
+    let args = fs::read_to_string(out.join("web/args.txt")).unwrap();
+    assert!(args.starts_with("helm template web ./charts/web -n front --wait -f "));
+    assert!(!args.contains("--install"));
+    assert!(!args.contains("--history-max"));
+    assert_eq!(
+        fs::read_to_string(out.join("web/chart/templates/values.yaml"))
+            .unwrap()
+            .trim_end(),
+        "apiKey: \"s3cr3t\""
+    );
+
+    let args = fs::read_to_string(out.join("web-canary/args.txt")).unwrap();
+    assert!(args.starts_with("helm template web-canary ./charts/web -n front -f "));
+    assert_eq!(
+        fs::read_to_string(out.join("web-canary/chart/templates/values.yaml"))
+            .unwrap()
+            .trim_end(),
+        "replicas: 1"
+    );
+    assert!(!out.join("tools").exists());
+}
+
+#[test]
+fn test_template_removes_stale_manifests() {
+    let temp = TempDir::new().unwrap();
+    let path = setup(&temp);
+    let stale = temp.path().join("out/web/chart/templates/removed.yaml");
+    fs::create_dir_all(stale.parent().unwrap()).unwrap();
+    fs::write(&stale, "kind: ConfigMap\n").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .env("PATH", path)
+        .args(["--no-progress", "template", "web", "--output-dir", "out"])
+        .assert()
+        .success();
+
+    assert!(!stale.exists());
+    assert!(temp.path().join("out/web/args.txt").exists());
+    assert!(!temp.path().join("out/web-canary").exists());
+}
+
+#[test]
+fn test_template_same_release_in_several_namespaces() {
+    let temp = TempDir::new().unwrap();
+    let path = setup(&temp);
+    fs::write(
+        temp.path().join("vesshelm.yaml"),
+        r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: web
+    repo_name: stable
+    version: 1.0.0
+    namespace: front
+  - name: web
+    repo_name: stable
+    version: 1.0.0
+    namespace: back
+
+destinations:
+  - name: default
+    path: ./charts
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#,
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .env("PATH", path)
+        .args(["--no-progress", "template", "--output-dir", "out"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rendered: 2"));
+
+    let out = temp.path().join("out");
+    let args = fs::read_to_string(out.join("front-web/args.txt")).unwrap();
+    assert!(args.starts_with("helm template web ./charts/web -n front"));
+    let args = fs::read_to_string(out.join("back-web/args.txt")).unwrap();
+    assert!(args.starts_with("helm template web ./charts/web -n back"));
+    assert!(!out.join("web").exists());
+}
+
+#[test]
+fn test_template_reports_failures() {
+    let temp = TempDir::new().unwrap();
+    let path = setup(&temp);
+    let config = fs::read_to_string(temp.path().join("vesshelm.yaml")).unwrap();
+    fs::write(
+        temp.path().join("vesshelm.yaml"),
+        config.replace("release: web-canary", "release: broken"),
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .env("PATH", path)
+        .args(["--no-progress", "template", "--output-dir", "out"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Error: template: broken"))
+        .stderr(predicate::str::contains("Failed to render: broken"));
+
+    assert!(temp.path().join("out/web/args.txt").exists());
+}
@@ -87,3 +87,81 @@ destinations: []
         .failure()
         .stderr(predicate::str::contains("Chart 'missing-chart' not found"));
 }
+
+#[test]
+fn test_uninstall_by_selector() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let helm_path = temp_dir.path().join("helm");
+    fs::write(
+        &helm_path,
+        "#!/bin/sh\necho \"helm $@\" >> \"$(dirname \"$0\")/calls.log\"\nexit 0\n",
+    )
+    .unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(&helm_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&helm_path, perms).unwrap();
+    }
+
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://example.com/stable
+charts:
+  - name: preview-api
+    repo_name: stable
+    version: 1.0.0
+    namespace: preview
+    labels:
+      env: preview
+  - name: web
+    release: preview-web
+    repo_name: stable
+    version: 1.0.0
+    namespace: preview
+    depends:
+      - preview-api
+    labels:
+      env: preview
+  - name: api
+    repo_name: stable
+    version: 1.0.0
+    namespace: prod
+    labels:
+      env: prod
+destinations:
+  - name: default
+    path: ./charts
+"#;
+    fs::write(temp_dir.path().join("vesshelm.yaml"), config_content).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .env(
+            "PATH",
+            format!("{}:/usr/bin:/bin", temp_dir.path().display()),
+        )
+        .args(["uninstall", "--selector", "env=preview", "--no-interactive"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Successfully uninstalled preview-api",
+        ))
+        .stdout(predicate::str::contains(
+            "Successfully uninstalled preview-web",
+        ))
+        .stdout(predicate::str::contains("  - preview-web\n"));
+
+    // Dependents are uninstalled before their dependencies
+    let calls = fs::read_to_string(temp_dir.path().join("calls.log")).unwrap();
+    assert_eq!(
+        calls.lines().collect::<Vec<_>>(),
+        vec![
+            "helm uninstall preview-web -n preview",
+            "helm uninstall preview-api -n preview"
+        ]
+    );
+}
@@ -1,6 +1,7 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use std::fs;
+use std::os::unix::fs::PermissionsExt;
 
 #[test]
 fn test_validate_valid_config() {
@@ -138,6 +139,54 @@ destinations:
         .success();
 }
 
+#[test]
+fn test_validate_same_chart_different_releases() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let config_path = temp_dir.path().join("vesshelm.yaml");
+    let config = |second_release: &str| {
+        format!(
+            r#"
+repositories:
+  - name: my-repo
+    url: https://kubernetes.github.io/ingress-nginx
+charts:
+  - name: ingress-nginx
+    release: internal
+    repo_name: my-repo
+    version: 4.10.0
+    namespace: ingress
+  - name: ingress-nginx
+    release: {}
+    repo_name: my-repo
+    version: 4.10.0
+    namespace: ingress
+destinations:
+  - name: default
+    path: ./charts
+"#,
+            second_release
+        )
+    };
+
+    // Valid: same chart installed twice under different release names
+    fs::write(&config_path, config("external")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .success();
+
+    // Invalid: duplicate release in the same namespace
+    fs::write(&config_path, config("internal")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("duplicate_chart_name_namespace"))
+        .stderr(predicate::str::contains("\"internal\""));
+}
+
 #[test]
 fn test_validate_missing_file() {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"));
@@ -171,3 +220,371 @@ destinations:
         .failure()
         .stderr(predicate::str::contains("values_file_not_found"));
 }
+
+#[test]
+fn test_validate_values_against_chart_schema() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        temp_dir.path().join("vesshelm.yaml"),
+        r#"
+repositories:
+  - name: my-repo
+    url: https://charts.bitnami.com/bitnami
+charts:
+  - name: nginx
+    repo_name: my-repo
+    version: 13.2.1
+    namespace: default
+    values_files:
+      - values/nginx.yaml
+destinations:
+  - name: default
+    path: ./charts
+vesshelm:
+  helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#,
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join("charts/nginx")).unwrap();
+    fs::write(
+        temp_dir.path().join("charts/nginx/values.yaml"),
+        "replicaCount: 1\nimage:\n  tag: latest\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("charts/nginx/values.schema.json"),
+        r#"{
+  "type": "object",
+  "properties": {
+    "replicaCount": { "type": "integer" },
+    "image": {
+      "type": "object",
+      "properties": { "tag": { "type": "string" } },
+      "additionalProperties": false
+    }
+  }
+}"#,
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join("values")).unwrap();
+    fs::write(
+        temp_dir.path().join("values/nginx.yaml"),
+        "replicaCount: two\nimage:\n  tga: stable\n",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Chart 'nginx': values do not match values.schema.json",
+        ))
+        .stderr(predicate::str::contains(
+            r#"replicaCount: value is not of type "integer" (values/nginx.yaml)"#,
+        ))
+        .stderr(predicate::str::contains(
+            "image: Additional properties are not allowed ('tga' was unexpected)",
+        ))
+        .stderr(predicate::str::contains("two").not());
+
+    fs::write(
+        temp_dir.path().join("values/nginx.yaml"),
+        "replicaCount: 2\n",
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_validate_loads_variables_only_for_charts_with_a_schema() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        temp_dir.path().join("vesshelm.yaml"),
+        r#"
+repositories:
+  - name: my-repo
+    url: https://charts.bitnami.com/bitnami
+charts:
+  - name: nginx
+    repo_name: my-repo
+    version: 13.2.1
+    namespace: default
+    values_files:
+      - values/nginx.yaml
+  - name: redis
+    repo_name: my-repo
+    version: 18.0.0
+    namespace: default
+    values_files:
+      - values/redis.yaml
+destinations:
+  - name: default
+    path: ./charts
+secrets_files:
+  - secrets.yaml
+vesshelm:
+  helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#,
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("secrets.yaml"), "password: [broken\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join("values")).unwrap();
+    fs::write(
+        temp_dir.path().join("values/nginx.yaml"),
+        "replicaCount: two\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("values/redis.yaml"),
+        "port: {{ broken\n",
+    )
+    .unwrap();
+
+    // No chart has a schema: the secrets are never loaded
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .success();
+
+    for name in ["nginx", "redis"] {
+        fs::create_dir_all(temp_dir.path().join("charts").join(name)).unwrap();
+        fs::write(
+            temp_dir
+                .path()
+                .join("charts")
+                .join(name)
+                .join("values.schema.json"),
+            r#"{ "properties": { "replicaCount": { "type": "integer" } } }"#,
+        )
+        .unwrap();
+    }
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Variables: Failed to load variables/secrets",
+        ));
+
+    // A chart failing to render its values does not stop the others from being checked
+    fs::write(temp_dir.path().join("secrets.yaml"), "password: secret\n").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Chart 'redis': "))
+        .stderr(predicate::str::contains(
+            "Chart 'nginx': values do not match values.schema.json",
+        ));
+}
+
+#[test]
+fn test_validate_values_reports_unknown_keys() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        temp_dir.path().join("vesshelm.yaml"),
+        r#"
+repositories:
+  - name: my-repo
+    url: https://charts.bitnami.com/bitnami
+charts:
+  - name: nginx
+    repo_name: my-repo
+    version: 13.2.1
+    namespace: default
+    values_files:
+      - values/nginx.yaml
+    values:
+      - image:
+          tga: stable
+    free_form_values:
+      - ingress.annotations
+destinations:
+  - name: default
+    path: ./charts
+vesshelm:
+  helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#,
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join("charts/nginx")).unwrap();
+    fs::write(
+        temp_dir.path().join("charts/nginx/Chart.yaml"),
+        "name: nginx\ndependencies:\n  - name: common\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("charts/nginx/values.yaml"),
+        "image:\n  tag: latest\nresources: {}\ningress:\n  annotations:\n    a: b\n",
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join("values")).unwrap();
+    fs::write(
+        temp_dir.path().join("values/nginx.yaml"),
+        "resouces:\n  limits:\n    cpu: 1\ningress:\n  annotations:\n    team: web\ncommon:\n  x: y\n",
+    )
+    .unwrap();
+
+    // Without --values, unknown keys are not checked
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .args(["validate", "--values"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Chart 'nginx': unknown values keys",
+        ))
+        .stderr(predicate::str::contains(
+            "resouces (values/nginx.yaml), did you mean 'resources'?",
+        ))
+        .stderr(predicate::str::contains(
+            "image.tga (inline values), did you mean 'tag'?",
+        ))
+        .stderr(predicate::str::contains("ingress").not())
+        .stderr(predicate::str::contains("common").not());
+}
+
+#[test]
+fn test_validate_render_checks_policies() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    // Mock helm printing the values files as the rendered manifest
+    let helm_path = temp_dir.path().join("helm");
+    fs::write(
+        &helm_path,
+        r#"#!/bin/sh
+[ "$1" = "template" ] || exit 1
+prev=""
+for arg in "$@"; do
+    if [ "$prev" = "-f" ]; then
+        while IFS= read -r line; do echo "$line"; done < "$arg"
+    fi
+    prev="$arg"
+done
+"#,
+    )
+    .unwrap();
+    fs::set_permissions(&helm_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    fs::write(
+        temp_dir.path().join("vesshelm.yaml"),
+        r#"
+repositories:
+  - name: my-repo
+    url: https://charts.bitnami.com/bitnami
+charts:
+  - name: agent
+    repo_name: my-repo
+    version: 1.0.0
+    namespace: default
+    values_files:
+      - agent.yaml
+destinations:
+  - name: default
+    path: ./charts
+policies:
+  builtin:
+    require_limits: off
+    no_host_path: warn
+  rules:
+    - name: team-label
+      kinds: [DaemonSet]
+      path: metadata.labels.team
+      operator: exists
+vesshelm:
+  helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("agent.yaml"),
+        r#"apiVersion: apps/v1
+kind: DaemonSet
+metadata:
+  name: agent
+spec:
+  template:
+    spec:
+      containers:
+        - name: agent
+          image: agent:1.0
+          securityContext:
+            privileged: true
+      volumes:
+        - name: root
+          hostPath:
+            path: /
+"#,
+    )
+    .unwrap();
+
+    // Without --render, nothing is rendered
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .env("PATH", temp_dir.path())
+        .arg("validate")
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .env("PATH", temp_dir.path())
+        .args(["validate", "--render"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Chart 'agent': policy violations"))
+        .stderr(predicate::str::contains(
+            "[no_privileged] DaemonSet/agent: container 'agent' runs privileged",
+        ))
+        .stderr(predicate::str::contains(
+            "[no_host_path] DaemonSet/agent: volume 'root' mounts host path '/'",
+        ))
+        .stderr(predicate::str::contains(
+            "[team-label] DaemonSet/agent: metadata.labels.team must exist",
+        ))
+        .stderr(predicate::str::contains("require_limits").not());
+}
+
+#[test]
+fn test_validate_invalid_policy_rule() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        temp_dir.path().join("vesshelm.yaml"),
+        r#"
+repositories: []
+charts: []
+destinations:
+  - name: default
+    path: ./charts
+policies:
+  rules:
+    - name: bad-regex
+      path: metadata.name
+      operator: matches
+      value: "web-("
+"#,
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp_dir)
+        .arg("validate")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid_policy_rule"))
+        .stderr(predicate::str::contains("bad-regex"));
+}
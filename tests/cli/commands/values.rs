@@ -0,0 +1,101 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let config_content = r#"
+repositories:
+  - name: stable
+    url: https://charts.helm.sh/stable
+
+charts:
+  - name: web
+    repo_name: stable
+    version: 1.0.0
+    namespace: default
+    values_files:
+      - values/prod.yaml
+    values:
+      - replicas: 5
+
+destinations:
+  - name: default
+    path: ./charts
+
+variables_files:
+  - vars.yaml
+secrets_files:
+  - secrets.yaml
+
+vesshelm:
+    helm_args: "upgrade --install {{ name }} {{ destination }}/{{ name }} -n {{ namespace }}"
+"#;
+    fs::write(temp.path().join("vesshelm.yaml"), config_content).unwrap();
+    fs::write(temp.path().join("vars.yaml"), "env: prod\n").unwrap();
+    fs::write(temp.path().join("secrets.yaml"), "db_password: hunter2\n").unwrap();
+
+    fs::create_dir_all(temp.path().join("charts/web")).unwrap();
+    fs::write(
+        temp.path().join("charts/web/values.yaml"),
+        "replicas: 1\nimage:\n  repository: nginx\n  tag: latest\n",
+    )
+    .unwrap();
+
+    fs::create_dir_all(temp.path().join("values")).unwrap();
+    fs::write(
+        temp.path().join("values/prod.yaml"),
+        "image:\n  tag: \"{{ env }}\"\ndb:\n  password: \"{{ db_password }}\"\n",
+    )
+    .unwrap();
+    temp
+}
+
+#[test]
+fn test_values_annotates_sources_and_masks_secrets() {
+    let temp = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["values", "web"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("replicas: 5  # inline values"))
+        .stdout(predicate::str::contains(
+            "  repository: nginx  # ./charts/web/values.yaml",
+        ))
+        .stdout(predicate::str::contains("  tag: prod  # values/prod.yaml"))
+        .stdout(predicate::str::contains(
+            "  password: '********'  # values/prod.yaml",
+        ))
+        .stdout(predicate::str::contains("hunter2").not());
+}
+
+#[test]
+fn test_values_show_secrets() {
+    let temp = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["values", "web", "--show-secrets"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  password: hunter2  # values/prod.yaml",
+        ));
+}
+
+#[test]
+fn test_values_unknown_chart() {
+    let temp = setup();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vesshelm"))
+        .current_dir(&temp)
+        .args(["values", "missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Chart 'missing' not found in vesshelm.yaml",
+        ));
+}
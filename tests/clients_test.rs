@@ -119,7 +119,8 @@ exit 1
     unsafe {
         env::set_var("PATH", original_path);
     }
-    assert!(res.is_ok()); // Handled
+    // A name clash with another registration is no longer silently accepted
+    assert!(res.is_err());
 }
 
 #[test]
@@ -135,7 +136,7 @@ exit 1
         env::set_var("PATH", &new_path);
     }
     let client = RealHelmClient::new();
-    assert!(client.repo_update().is_ok());
+    assert!(client.repo_update(&["my-repo".to_string()]).is_ok());
     unsafe {
         env::set_var("PATH", original_path);
     }
@@ -199,6 +200,48 @@ exit 1
     }
 }
 
+#[test]
+fn test_history_and_rollback() {
+    let _lock = PATH_LOCK.lock().unwrap();
+    let script = r#"#!/bin/sh
+if [ "$1" = "history" ]; then
+    echo "- revision: 1"
+    echo "  updated: '2024-01-01T00:00:00Z'"
+    echo "  status: superseded"
+    echo "  chart: nginx-1.0.0"
+    echo "  app_version: 1.25.0"
+    echo "  description: Install complete"
+    echo "- revision: 2"
+    echo "  updated: '2024-01-02T00:00:00Z'"
+    echo "  status: deployed"
+    echo "  chart: nginx-1.1.0"
+    echo "  app_version: 1.25.1"
+    echo "  description: Upgrade complete"
+    exit 0
+fi
+if [ "$1" = "rollback" ] && [ "$2" = "release" ] && [ "$3" = "1" ]; then exit 0; fi
+exit 1
+"#;
+    let (_temp, new_path) = setup_mock_helm(script);
+    let original_path = env::var("PATH").unwrap_or_default();
+    unsafe {
+        env::set_var("PATH", &new_path);
+    }
+    let client = RealHelmClient::new();
+    let history = client.history("release", "ns");
+    let rollback = client.rollback("release", "ns", Some(1));
+    unsafe {
+        env::set_var("PATH", original_path);
+    }
+
+    let history = history.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].revision, 2);
+    assert_eq!(history[1].status, "deployed");
+    assert_eq!(history[1].chart, "nginx-1.1.0");
+    assert!(rollback.is_ok());
+}
+
 #[test]
 fn test_plugins() {
     let _lock = PATH_LOCK.lock().unwrap();
@@ -225,6 +268,7 @@ exit 1
 fn test_check_updates_run() {
     let _lock = PATH_LOCK.lock().unwrap();
     let script = r#"#!/bin/sh
+if [ "$1" = "repo" ] && [ "$2" = "add" ]; then exit 0; fi
 if [ "$1" = "repo" ] && [ "$2" = "update" ]; then exit 0; fi
 if [ "$1" = "search" ] && [ "$2" = "repo" ]; then
     echo "- name: stable/nginx"
@@ -261,6 +305,7 @@ destinations:
         apply: false,
         apply_sync: false,
         charts: None,
+        selector: None,
     };
     let res = tokio::runtime::Runtime::new().unwrap().block_on(
         vesshelm::cli::commands::check_updates::run(args, true, &config_path),
@@ -276,6 +321,7 @@ fn test_check_updates_semver_fail() {
     let _lock = PATH_LOCK.lock().unwrap();
     // Return invalid version
     let script = r#"#!/bin/sh
+if [ "$1" = "repo" ] && [ "$2" = "add" ]; then exit 0; fi
 if [ "$1" = "repo" ] && [ "$2" = "update" ]; then exit 0; fi
 if [ "$1" = "search" ] && [ "$2" = "repo" ]; then
     echo "- name: stable/nginx"
@@ -312,6 +358,7 @@ destinations:
         apply: false,
         apply_sync: false,
         charts: None,
+        selector: None,
     };
     let res = tokio::runtime::Runtime::new().unwrap().block_on(
         vesshelm::cli::commands::check_updates::run(args, true, &config_path),
@@ -364,10 +411,19 @@ destinations:
     use vesshelm::cli::commands::DeployArgs;
     let args = DeployArgs {
         charts: None,
+        selector: None,
+        with_dependencies: false,
+        with_dependents: false,
         dry_run: false,
         no_interactive: true,
         force: false,
         take_ownership: false,
+        resume: false,
+        from: None,
+        until: None,
+        test: false,
+        review_first: false,
+        diff_view: Default::default(),
     };
     let res =
         tokio::runtime::Runtime::new()